@@ -6,15 +6,22 @@
 //!   memory-cli list --user <USER_ID>
 //!   memory-cli delete --id <MEMORY_ID>
 //!   memory-cli export --user <USER_ID> [--output <FILE>]
-//!   memory-cli import --user <USER_ID> --input <FILE>
+//!   memory-cli import --user <USER_ID> --input <FILE> [--resume] [--map content=<KEY>,type=<KEY>]
+//!   memory-cli backup --output <FILE>
+//!   memory-cli restore --input <FILE>
+//!
+//! Pass `--dry-run` to print what destructive commands would do without
+//! changing the store, and `--format json|table|plain` to choose how
+//! `search`, `list` and `stats` render their results.
 
+use std::io::Write;
 use std::sync::Arc;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use memory_rs::{
     config::MemoryConfig,
     embeddings::LocalEmbedder,
-    memory::{Memory, MemoryBase},
+    memory::{batch::BatchResult, AddOptions, Memory, MemoryBase, MemoryItem, MemorySnapshot, SearchResultItem},
     vector_store::InMemoryStore,
 };
 
@@ -27,10 +34,33 @@ struct Cli {
     #[arg(short, long, default_value = "memory.db")]
     database: String,
 
+    /// TOML configuration file; `MEMORY_*` environment variables override it
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// Print what destructive commands would do without changing the store
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Output format for search, list and stats
+    #[arg(long, global = true, value_enum, default_value = "plain")]
+    format: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Output format
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text
+    Plain,
+    /// Serialized JSON
+    Json,
+    /// Aligned columns
+    Table,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Add a new memory
@@ -77,7 +107,7 @@ enum Commands {
         id: String,
     },
 
-    /// Export memories to JSON
+    /// Export memories to JSON (JSON Lines when writing to a file)
     Export {
         /// User ID
         #[arg(short, long)]
@@ -97,6 +127,32 @@ enum Commands {
         /// Input file
         #[arg(short, long)]
         input: String,
+
+        /// Skip the items recorded in the checkpoint file by a previous run
+        #[arg(long)]
+        resume: bool,
+
+        /// Checkpoint file (default: <INPUT>.checkpoint)
+        #[arg(long)]
+        checkpoint: Option<String>,
+
+        /// JSON keys holding the memory fields, e.g. `content=text,type=category`
+        #[arg(long, value_parser = parse_field_map)]
+        map: Option<FieldMap>,
+    },
+
+    /// Back up the memories of all users to a snapshot file
+    Backup {
+        /// Output file
+        #[arg(short, long)]
+        output: String,
+    },
+
+    /// Restore memories from a snapshot file
+    Restore {
+        /// Input file
+        #[arg(short, long)]
+        input: String,
     },
 
     /// Show memory statistics
@@ -107,8 +163,232 @@ enum Commands {
     },
 }
 
+/// Write rows as left-aligned columns
+fn write_table(out: &mut impl Write, headers: &[&str], rows: &[Vec<String>]) -> std::io::Result<()> {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+/// Render search results in the requested format
+fn write_search_results(
+    out: &mut impl Write,
+    format: OutputFormat,
+    query: &str,
+    results: &[SearchResultItem],
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(results)?)?,
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = results
+                .iter()
+                .map(|r| {
+                    vec![
+                        format!("{:.3}", r.score),
+                        r.memory.id.clone(),
+                        r.memory.memory_type.clone(),
+                        r.memory.content.clone(),
+                    ]
+                })
+                .collect();
+            write_table(out, &["SCORE", "ID", "TYPE", "CONTENT"], &rows)?;
+        }
+        OutputFormat::Plain => {
+            if results.is_empty() {
+                writeln!(out, "No memories found for query: {}", query)?;
+            } else {
+                writeln!(out, "Found {} memories:", results.len())?;
+                for (i, result) in results.iter().enumerate() {
+                    writeln!(out, "\n{}. [Score: {:.3}]", i + 1, result.score)?;
+                    writeln!(out, "   ID: {}", result.memory.id)?;
+                    writeln!(out, "   Type: {}", result.memory.memory_type)?;
+                    writeln!(out, "   Content: {}", result.memory.content)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render a user's memories in the requested format
+fn write_memories(
+    out: &mut impl Write,
+    format: OutputFormat,
+    user: &str,
+    memories: &[MemoryItem],
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(memories)?)?,
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = memories
+                .iter()
+                .map(|m| {
+                    vec![
+                        m.id.clone(),
+                        m.memory_type.clone(),
+                        m.created_at.clone(),
+                        m.content.clone(),
+                    ]
+                })
+                .collect();
+            write_table(out, &["ID", "TYPE", "CREATED", "CONTENT"], &rows)?;
+        }
+        OutputFormat::Plain => {
+            if memories.is_empty() {
+                writeln!(out, "No memories found for user: {}", user)?;
+            } else {
+                writeln!(out, "Found {} memories for user {}:", memories.len(), user)?;
+                for (i, mem) in memories.iter().enumerate() {
+                    writeln!(out, "\n{}. {}", i + 1, mem.id)?;
+                    writeln!(out, "   Type: {}", mem.memory_type)?;
+                    writeln!(out, "   Content: {}", mem.content)?;
+                    writeln!(out, "   Created: {}", mem.created_at)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// JSON keys an import reads the memory fields from
+#[derive(Debug, Clone)]
+struct FieldMap {
+    content: String,
+    memory_type: String,
+}
+
+/// Keys of an exported `MemoryItem` that never become custom metadata
+const RESERVED_IMPORT_KEYS: &[&str] = &[
+    "id",
+    "user_id",
+    "agent_id",
+    "run_id",
+    "hash",
+    "created_at",
+    "updated_at",
+    "metadata",
+    "attachments",
+    "tags",
+    "acl",
+];
+
+impl Default for FieldMap {
+    fn default() -> Self {
+        Self {
+            content: "content".to_string(),
+            memory_type: "memory_type".to_string(),
+        }
+    }
+}
+
+impl FieldMap {
+    /// Custom metadata for an item: its `metadata` object plus every unmapped
+    /// scalar key
+    fn metadata(&self, item: &serde_json::Value) -> std::collections::HashMap<String, String> {
+        let mut metadata = std::collections::HashMap::new();
+        let Some(object) = item.as_object() else {
+            return metadata;
+        };
+        if let Some(nested) = object.get("metadata").and_then(|v| v.as_object()) {
+            for (key, value) in nested {
+                if let Some(value) = scalar_to_string(value) {
+                    metadata.insert(key.clone(), value);
+                }
+            }
+        }
+        for (key, value) in object {
+            if *key == self.content
+                || *key == self.memory_type
+                || key == "content"
+                || key == "memory_type"
+                || RESERVED_IMPORT_KEYS.contains(&key.as_str())
+            {
+                continue;
+            }
+            if let Some(value) = scalar_to_string(value) {
+                metadata.insert(key.clone(), value);
+            }
+        }
+        metadata
+    }
+}
+
+fn scalar_to_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse `content=<KEY>,type=<KEY>`; omitted fields keep their default keys
+fn parse_field_map(s: &str) -> Result<FieldMap, String> {
+    let mut map = FieldMap::default();
+    for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (field, key) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected FIELD=KEY, got '{}'", pair))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("empty key for field '{}'", field.trim()));
+        }
+        match field.trim() {
+            "content" => map.content = key.to_string(),
+            "type" | "memory_type" => map.memory_type = key.to_string(),
+            other => return Err(format!("unknown field '{}' (expected content or type)", other)),
+        }
+    }
+    Ok(map)
+}
+
+/// Read an import checkpoint, treating a missing file as no progress
+fn read_checkpoint(path: &str) -> anyhow::Result<usize> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.trim().parse()?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Remove the checkpoint of a finished import, if any
+fn remove_checkpoint(path: &str) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
 fn create_memory(db_path: &str) -> Memory {
-    let config = MemoryConfig::new(db_path.to_string());
+    create_memory_with(MemoryConfig::new(db_path.to_string()))
+}
+
+/// Load the configuration from `--config` or `--database`, then the environment
+fn load_config(cli: &Cli) -> anyhow::Result<MemoryConfig> {
+    let config = match &cli.config {
+        Some(path) => MemoryConfig::from_toml(path)?,
+        None => MemoryConfig::new(cli.database.clone()),
+    };
+    Ok(config.with_env_overrides()?)
+}
+
+fn create_memory_with(config: MemoryConfig) -> Memory {
     let vector_store = Arc::new(InMemoryStore::new());
     let embedder = Arc::new(LocalEmbedder::with_defaults());
     Memory::new(config, vector_store, embedder)
@@ -117,8 +397,12 @@ fn create_memory(db_path: &str) -> Memory {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let memory = create_memory(&cli.database);
+    let memory = create_memory_with(load_config(&cli)?);
+    run(cli, &memory, &mut std::io::stdout()).await
+}
 
+/// Execute a parsed command against a memory instance
+async fn run(cli: Cli, memory: &Memory, out: &mut impl Write) -> anyhow::Result<()> {
     match cli.command {
         Commands::Add {
             user,
@@ -126,101 +410,430 @@ async fn main() -> anyhow::Result<()> {
             memory_type,
         } => {
             let item = memory.add(&user, &content, Some(&memory_type)).await?;
-            println!("Added memory:");
-            println!("  ID: {}", item.id);
-            println!("  User: {}", item.user_id);
-            println!("  Type: {}", item.memory_type);
-            println!("  Content: {}", item.content);
+            writeln!(out, "Added memory:")?;
+            writeln!(out, "  ID: {}", item.id)?;
+            writeln!(out, "  User: {}", item.user_id)?;
+            writeln!(out, "  Type: {}", item.memory_type)?;
+            writeln!(out, "  Content: {}", item.content)?;
         }
 
         Commands::Search { user, query, limit } => {
             let results = memory.search(&user, &query, limit).await?;
-            if results.is_empty() {
-                println!("No memories found for query: {}", query);
-            } else {
-                println!("Found {} memories:", results.len());
-                for (i, result) in results.iter().enumerate() {
-                    println!("\n{}. [Score: {:.3}]", i + 1, result.score);
-                    println!("   ID: {}", result.memory.id);
-                    println!("   Type: {}", result.memory.memory_type);
-                    println!("   Content: {}", result.memory.content);
-                }
-            }
+            write_search_results(out, cli.format, &query, &results)?;
         }
 
         Commands::List { user } => {
             let memories = memory.get_all(&user).await?;
-            if memories.is_empty() {
-                println!("No memories found for user: {}", user);
-            } else {
-                println!("Found {} memories for user {}:", memories.len(), user);
-                for (i, mem) in memories.iter().enumerate() {
-                    println!("\n{}. {}", i + 1, mem.id);
-                    println!("   Type: {}", mem.memory_type);
-                    println!("   Content: {}", mem.content);
-                    println!("   Created: {}", mem.created_at);
-                }
-            }
+            write_memories(out, cli.format, &user, &memories)?;
         }
 
         Commands::Delete { id } => {
-            memory.delete(&id).await?;
-            println!("Deleted memory: {}", id);
+            if cli.dry_run {
+                writeln!(out, "[dry-run] Would delete memory: {}", id)?;
+            } else {
+                memory.delete(&id).await?;
+                writeln!(out, "Deleted memory: {}", id)?;
+            }
         }
 
         Commands::Export { user, output } => {
-            let memories = memory.get_all(&user).await?;
-            let json = serde_json::to_string_pretty(&memories)?;
-
             if let Some(path) = output {
-                std::fs::write(&path, &json)?;
-                println!("Exported {} memories to {}", memories.len(), path);
+                let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                let exported = memory.export_to_writer(&user, file).await?;
+                writeln!(out, "Exported {} memories to {}", exported, path)?;
             } else {
-                println!("{}", json);
+                let memories = memory.get_all(&user).await?;
+                writeln!(out, "{}", serde_json::to_string_pretty(&memories)?)?;
             }
         }
 
-        Commands::Import { user, input } => {
+        Commands::Import {
+            user,
+            input,
+            resume,
+            checkpoint,
+            map,
+        } => {
+            let map = map.unwrap_or_default();
             let json = std::fs::read_to_string(&input)?;
-            let items: Vec<serde_json::Value> = serde_json::from_str(&json)?;
-
-            let mut count = 0;
-            for item in items {
-                if let (Some(content), memory_type) = (
-                    item.get("content").and_then(|v| v.as_str()),
-                    item.get("memory_type")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("general"),
-                ) {
-                    memory.add(&user, content, Some(memory_type)).await?;
-                    count += 1;
+            // File exports are JSON Lines; stdout exports are a JSON array
+            let items: Vec<serde_json::Value> = if json.trim_start().starts_with('[') {
+                serde_json::from_str(&json)?
+            } else {
+                json.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<Result<_, serde_json::Error>>()?
+            };
+
+            // The checkpoint holds the number of leading items already imported
+            let checkpoint = checkpoint.unwrap_or_else(|| format!("{}.checkpoint", input));
+            let skip = if resume {
+                read_checkpoint(&checkpoint)?.min(items.len())
+            } else {
+                0
+            };
+            let pending = &items[skip..];
+
+            if cli.dry_run {
+                let importable = pending
+                    .iter()
+                    .filter(|item| item.get(&map.content).and_then(|v| v.as_str()).is_some())
+                    .count();
+                writeln!(
+                    out,
+                    "[dry-run] Would import {} memories for user {}",
+                    importable, user
+                )?;
+                return Ok(());
+            }
+
+            // Continue past failing items and report them at the end. The
+            // checkpoint only advances while every item so far succeeded, so
+            // a resumed run retries the first failure.
+            let mut result = BatchResult::new(pending.len());
+            let mut failed_indices = Vec::new();
+            for (index, item) in pending.iter().enumerate() {
+                let index = skip + index;
+                let added = match item.get(&map.content).and_then(|v| v.as_str()) {
+                    None => {
+                        result.add_error(format!("item {}: missing content", index));
+                        false
+                    }
+                    Some(content) => {
+                        let memory_type = item
+                            .get(&map.memory_type)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("general");
+                        let mut options =
+                            AddOptions::new().with_memory_type(memory_type.to_string());
+                        options.metadata = map.metadata(item);
+
+                        match memory.add_with_options(&user, content, options).await {
+                            Ok(_) => {
+                                result.add_success();
+                                true
+                            }
+                            Err(e) => {
+                                result.add_error(format!("item {}: {}", index, e));
+                                false
+                            }
+                        }
+                    }
+                };
+                if !added {
+                    failed_indices.push(index);
+                } else if failed_indices.is_empty() {
+                    std::fs::write(&checkpoint, (index + 1).to_string())?;
                 }
             }
-            println!("Imported {} memories for user {}", count, user);
+
+            if skip > 0 {
+                writeln!(out, "Skipped {} previously imported items", skip)?;
+            }
+            writeln!(
+                out,
+                "Imported {} of {} memories for user {}, {} failed",
+                result.successful, result.total, user, result.failed
+            )?;
+            if !result.all_succeeded() {
+                for error in &result.errors {
+                    writeln!(out, "  {}", error)?;
+                }
+                anyhow::bail!("import failed for items {:?}", failed_indices);
+            }
+            remove_checkpoint(&checkpoint)?;
+        }
+
+        Commands::Backup { output } => {
+            let snapshot = memory.export_snapshot().await?;
+            snapshot.save(&output)?;
+            writeln!(
+                out,
+                "Backed up {} memories in {} collections to {}",
+                snapshot.memory_count(),
+                snapshot.collections.len(),
+                output
+            )?;
+        }
+
+        Commands::Restore { input } => {
+            let snapshot = MemorySnapshot::load(&input)?;
+
+            if cli.dry_run {
+                writeln!(
+                    out,
+                    "[dry-run] Would restore {} memories in {} collections",
+                    snapshot.memory_count(),
+                    snapshot.collections.len()
+                )?;
+                return Ok(());
+            }
+
+            let restored = memory.import_snapshot(snapshot).await?;
+            writeln!(out, "Restored {} memories from {}", restored, input)?;
         }
 
         Commands::Stats { user } => {
             if let Some(user_id) = user {
-                let memories = memory.get_all(&user_id).await?;
-                println!("Statistics for user {}:", user_id);
-                println!("  Total memories: {}", memories.len());
-
-                // Count by type
-                let mut type_counts: std::collections::HashMap<&str, usize> =
-                    std::collections::HashMap::new();
-                for mem in &memories {
-                    *type_counts.entry(&mem.memory_type).or_insert(0) += 1;
-                }
-                println!("  By type:");
-                for (t, count) in type_counts {
-                    println!("    {}: {}", t, count);
+                let type_counts: std::collections::BTreeMap<String, usize> =
+                    memory.count_by_type(&user_id).await?.into_iter().collect();
+                let total: usize = type_counts.values().sum();
+
+                match cli.format {
+                    OutputFormat::Json => {
+                        let stats = serde_json::json!({
+                            "user_id": user_id,
+                            "total": total,
+                            "by_type": type_counts,
+                        });
+                        writeln!(out, "{}", serde_json::to_string_pretty(&stats)?)?;
+                    }
+                    OutputFormat::Table => {
+                        let rows: Vec<Vec<String>> = type_counts
+                            .iter()
+                            .map(|(t, count)| vec![t.to_string(), count.to_string()])
+                            .collect();
+                        write_table(out, &["TYPE", "COUNT"], &rows)?;
+                    }
+                    OutputFormat::Plain => {
+                        writeln!(out, "Statistics for user {}:", user_id)?;
+                        writeln!(out, "  Total memories: {}", total)?;
+                        writeln!(out, "  By type:")?;
+                        for (t, count) in type_counts {
+                            writeln!(out, "    {}: {}", t, count)?;
+                        }
+                    }
                 }
             } else {
-                println!("Stats for all users not yet implemented.");
-                println!("Use --user <USER_ID> to see stats for a specific user.");
+                writeln!(out, "Stats for all users not yet implemented.")?;
+                writeln!(out, "Use --user <USER_ID> to see stats for a specific user.")?;
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run_args(args: &[&str], memory: &Memory) -> String {
+        let cli = Cli::parse_from(std::iter::once("memory-cli").chain(args.iter().copied()));
+        let mut out = Vec::new();
+        run(cli, memory, &mut out).await.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_dry_run() {
+        let memory = create_memory("memory.db");
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        let output = run_args(&["--dry-run", "delete", "--id", item.id.as_str()], &memory).await;
+        assert!(output.contains(&format!("Would delete memory: {}", item.id)));
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 1);
+
+        run_args(&["delete", "--id", item.id.as_str()], &memory).await;
+        assert!(memory.get_all("user_1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_json_format() {
+        let memory = create_memory("memory.db");
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        let output = run_args(
+            &["--format", "json", "search", "--user", "user_1", "--query", "I like coffee"],
+            &memory,
+        )
+        .await;
+        let results: Vec<SearchResultItem> = serde_json::from_str(&output).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, item.id);
+    }
+
+    #[tokio::test]
+    async fn test_list_table_format() {
+        let memory = create_memory("memory.db");
+        memory.add("user_1", "I like coffee", Some("preference")).await.unwrap();
+
+        let output = run_args(&["list", "--user", "user_1", "--format", "table"], &memory).await;
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID"));
+        assert!(lines[1].contains("preference"));
+    }
+
+    #[tokio::test]
+    async fn test_export_output_with_format() {
+        let memory = create_memory("memory.db");
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("export_{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let output = run_args(
+            &["--format", "json", "export", "--user", "user_1", "--output", path],
+            &memory,
+        )
+        .await;
+        let exported = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(output.contains(&format!("Exported 1 memories to {}", path)));
+        assert_eq!(exported.lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_partial_failure() {
+        let memory = create_memory("memory.db");
+        let path = std::env::temp_dir().join(format!("import_{}.json", uuid::Uuid::new_v4()));
+        let items = serde_json::json!([
+            { "content": "I like coffee" },
+            { "memory_type": "fact" },
+            { "content": "I live in Paris", "memory_type": "fact" },
+        ]);
+        std::fs::write(&path, items.to_string()).unwrap();
+
+        let cli = Cli::parse_from([
+            "memory-cli",
+            "import",
+            "--user",
+            "user_1",
+            "--input",
+            path.to_str().unwrap(),
+        ]);
+        let mut out = Vec::new();
+        let result = run(cli, &memory, &mut out).await;
+        let checkpoint = format!("{}.checkpoint", path.display());
+        std::fs::remove_file(&path).unwrap();
+        // Only the item before the first failure counts as done
+        assert_eq!(std::fs::read_to_string(&checkpoint).unwrap(), "1");
+        std::fs::remove_file(&checkpoint).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(result.is_err());
+        assert!(output.contains("Imported 2 of 3 memories for user user_1, 1 failed"));
+        assert!(output.contains("item 1: missing content"));
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_backup_restore_round_trip() {
+        let source = create_memory("memory.db");
+        let coffee = source.add("user_1", "I like coffee", Some("preference")).await.unwrap();
+        source.add("user_1", "I live in Paris", Some("fact")).await.unwrap();
+        source.add("user_2", "I have a cat", None).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("backup_{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let output = run_args(&["backup", "--output", path], &source).await;
+        assert!(output.contains("Backed up 3 memories in 2 collections"));
+
+        let target = create_memory("memory.db");
+        let output = run_args(&["restore", "--input", path], &target).await;
+        std::fs::remove_file(path).unwrap();
+        assert!(output.contains("Restored 3 memories"));
+
+        assert_eq!(target.get_all("user_1").await.unwrap().len(), 2);
+        assert_eq!(target.get_all("user_2").await.unwrap().len(), 1);
+        let restored = target.get(&coffee.id).await.unwrap().unwrap();
+        assert_eq!(restored.memory_type, "preference");
+
+        let results = target.search("user_1", "I like coffee", 1).await.unwrap();
+        assert_eq!(results[0].memory.id, coffee.id);
+    }
+
+    #[tokio::test]
+    async fn test_import_resume_from_checkpoint() {
+        let memory = create_memory("memory.db");
+        let path = std::env::temp_dir().join(format!("import_{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap().to_string();
+        let contents = [
+            "I like coffee",
+            "I live in Paris",
+            "I have a cat",
+            "I work remotely",
+            "I read at night",
+        ];
+        let items: Vec<serde_json::Value> = contents
+            .iter()
+            .map(|c| serde_json::json!({ "content": c }))
+            .collect();
+        std::fs::write(&path, serde_json::to_string(&items).unwrap()).unwrap();
+
+        // A previous run committed the first two items before being interrupted
+        for content in &contents[..2] {
+            memory.add("user_1", content, None).await.unwrap();
+        }
+        let checkpoint = format!("{}.checkpoint", path);
+        std::fs::write(&checkpoint, "2").unwrap();
+
+        let output = run_args(
+            &["import", "--user", "user_1", "--input", &path, "--resume"],
+            &memory,
+        )
+        .await;
+        assert!(output.contains("Skipped 2 previously imported items"));
+        assert!(output.contains("Imported 3 of 3 memories"));
+        assert!(!std::path::Path::new(&checkpoint).exists());
+
+        let mut stored: Vec<String> = memory
+            .get_all("user_1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        stored.sort();
+        let mut expected: Vec<String> = contents.iter().map(|c| c.to_string()).collect();
+        expected.sort();
+        assert_eq!(stored, expected);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_field_mapping() {
+        let memory = create_memory("memory.db");
+        let path = std::env::temp_dir().join(format!("import_{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap().to_string();
+        let items = serde_json::json!([
+            { "body": "I like coffee", "category": "preference", "source": "notes", "rating": 5 },
+        ]);
+        std::fs::write(&path, items.to_string()).unwrap();
+
+        let output = run_args(
+            &[
+                "import",
+                "--user",
+                "user_1",
+                "--input",
+                &path,
+                "--map",
+                "content=body,type=category",
+            ],
+            &memory,
+        )
+        .await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(output.contains("Imported 1 of 1 memories"));
+
+        let stored = memory.get_all("user_1").await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].content, "I like coffee");
+        assert_eq!(stored[0].memory_type, "preference");
+        assert_eq!(stored[0].metadata.get("source").map(String::as_str), Some("notes"));
+        assert_eq!(stored[0].metadata.get("rating").map(String::as_str), Some("5"));
+        assert!(!stored[0].metadata.contains_key("body"));
+    }
+
+    #[test]
+    fn test_parse_field_map() {
+        let map = parse_field_map("content=body").unwrap();
+        assert_eq!(map.content, "body");
+        assert_eq!(map.memory_type, "memory_type");
+        assert!(parse_field_map("content").is_err());
+        assert!(parse_field_map("title=name").is_err());
+    }
+}
@@ -1,7 +1,13 @@
 //! MCP Server binary for memory-rs
 //!
 //! This binary runs the memory MCP server using STDIO transport.
+//!
+//! Configuration is read from the TOML file named by `MEMORY_CONFIG`, if
+//! set, with `MEMORY_*` environment variables taking precedence. Set
+//! `MEMORY_MCP_RATE_LIMIT_RPS` (and optionally `MEMORY_MCP_RATE_LIMIT_BURST`)
+//! to limit the tool calls each user may make per second.
 
+use memory_rs::config::MemoryConfig;
 use memory_rs::mcp::MemoryMcpServer;
 use rmcp::{transport::stdio, ServiceExt};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -19,8 +25,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("Starting Memory MCP Server");
 
+    let config = match std::env::var("MEMORY_CONFIG") {
+        Ok(path) => MemoryConfig::from_toml(path)?,
+        Err(_) => MemoryConfig::new("memory.db".to_string()),
+    }
+    .with_env_overrides()?;
+
     // Create and run the server with STDIO transport
-    let server = MemoryMcpServer::new();
+    let mut server = MemoryMcpServer::with_config(config);
+    if let Ok(rps) = std::env::var("MEMORY_MCP_RATE_LIMIT_RPS") {
+        let rps: f64 = rps.trim().parse()?;
+        let burst = match std::env::var("MEMORY_MCP_RATE_LIMIT_BURST") {
+            Ok(burst) => burst.trim().parse()?,
+            Err(_) => rps.ceil() as u32,
+        };
+        server = server.with_rate_limit(rps, burst)?;
+    }
     let service = server.serve(stdio()).await.inspect_err(|e| {
         tracing::error!("Error starting server: {}", e);
     })?;
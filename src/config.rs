@@ -1,6 +1,51 @@
 //! Configuration types for memory-rs
 
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::embeddings::ModelRegistry;
+use crate::vector_store::DistanceMetric;
+use crate::{Error, Result};
+
+/// Prefix of environment variables read by `MemoryConfig::from_env`
+pub const ENV_PREFIX: &str = "MEMORY_";
+
+/// How memories are partitioned into vector store collections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CollectionStrategy {
+    /// One collection per user
+    #[default]
+    PerUser,
+    /// One collection per user and agent
+    PerUserAgent,
+    /// One collection per user and run
+    PerUserRun,
+}
+
+/// Memory ID generation scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IdStrategy {
+    /// Random UUIDv4
+    #[default]
+    Uuidv4,
+    /// Time-ordered UUIDv7
+    Uuidv7,
+    /// Time-ordered KSUID (27 base62 characters)
+    Ksuid,
+}
+
+impl IdStrategy {
+    /// Generate a new ID
+    pub fn generate(&self) -> String {
+        match self {
+            Self::Uuidv4 => uuid::Uuid::new_v4().to_string(),
+            Self::Uuidv7 => uuid::Uuid::now_v7().to_string(),
+            Self::Ksuid => crate::utils::generate_ksuid(),
+        }
+    }
+}
 
 /// Memory configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,7 +65,7 @@ pub struct MemoryConfig {
     /// Embedding model name
     pub embedding_model: Option<String>,
 
-    /// Vector dimension (default: 384)
+    /// Vector dimension (default: the embedding model's known dimension, else 384)
     pub vector_dimension: Option<usize>,
 
     /// Collection name prefix (default: "mem0")
@@ -31,6 +76,64 @@ pub struct MemoryConfig {
 
     /// Batch size for operations (default: 32)
     pub batch_size: Option<usize>,
+
+    /// Minimum trimmed content length accepted by `add` (default: 1, 0 disables)
+    pub min_content_chars: Option<usize>,
+
+    /// Collection partitioning strategy (default: per user)
+    pub collection_strategy: Option<CollectionStrategy>,
+
+    /// Minimum score for search results (default: none)
+    ///
+    /// Hash-based embedders such as `LocalEmbedder` give unrelated texts
+    /// nonzero similarity; setting this floor drops that noise.
+    pub min_score: Option<f32>,
+
+    /// Normalize search scores into `[0, 1]` using the store's normalizer (default: false)
+    pub normalize_scores: Option<bool>,
+
+    /// Memory ID generation scheme (default: UUIDv4)
+    pub id_strategy: Option<IdStrategy>,
+
+    /// Maximum memories kept per user collection before consolidation (default: unbounded)
+    pub max_memories_per_user: Option<usize>,
+
+    /// Tag memories with their detected language on `add` (default: false)
+    pub detect_language: Option<bool>,
+
+    /// Reject reads of missing collections instead of creating them (default: false)
+    pub strict_collections: Option<bool>,
+
+    /// Redact emails, phone numbers and card numbers before storage (default: false)
+    ///
+    /// The original text is discarded; only the redacted content is
+    /// embedded and stored.
+    pub redact_pii: Option<bool>,
+
+    /// Minimum LLM confidence for storing LLM-derived memories (default: 0.0)
+    pub min_llm_confidence: Option<f32>,
+
+    /// Store LLM-derived memories below `min_llm_confidence` as `unverified`
+    /// instead of skipping them (default: false)
+    pub downgrade_unverified: Option<bool>,
+
+    /// Distance metric of collections created by `Memory` (default: the store's default)
+    pub distance_metric: Option<DistanceMetric>,
+
+    /// Timeout of each LLM call in milliseconds (default: none)
+    pub llm_timeout_ms: Option<u64>,
+
+    /// Timeout of each embedding call in milliseconds (default: none)
+    pub embedding_timeout_ms: Option<u64>,
+
+    /// Timeout of each vector store call in milliseconds (default: none)
+    pub vector_store_timeout_ms: Option<u64>,
+
+    /// Text prepended to search queries before embedding, e.g. `"search_query: "` (default: none)
+    pub embedding_query_prefix: Option<String>,
+
+    /// Text prepended to stored memories before embedding, e.g. `"search_document: "` (default: none)
+    pub embedding_document_prefix: Option<String>,
 }
 
 impl MemoryConfig {
@@ -46,6 +149,23 @@ impl MemoryConfig {
             collection_prefix: Some("memory".to_string()),
             enable_telemetry: Some(false),
             batch_size: Some(32),
+            min_content_chars: Some(1),
+            collection_strategy: None,
+            min_score: None,
+            normalize_scores: Some(false),
+            id_strategy: None,
+            max_memories_per_user: None,
+            detect_language: Some(false),
+            strict_collections: Some(false),
+            redact_pii: Some(false),
+            min_llm_confidence: Some(0.0),
+            downgrade_unverified: Some(false),
+            distance_metric: None,
+            llm_timeout_ms: None,
+            embedding_timeout_ms: None,
+            vector_store_timeout_ms: None,
+            embedding_query_prefix: None,
+            embedding_document_prefix: None,
         }
     }
 
@@ -69,7 +189,13 @@ impl MemoryConfig {
     }
 
     /// Set embedding model
+    ///
+    /// Also sets the vector dimension when the model is known to
+    /// `ModelRegistry`; call `with_vector_dimension` afterwards to override it.
     pub fn with_embedding_model(mut self, model: String) -> Self {
+        if let Some(dim) = ModelRegistry::dimension_of(&model) {
+            self.vector_dimension = Some(dim);
+        }
         self.embedding_model = Some(model);
         self
     }
@@ -98,6 +224,108 @@ impl MemoryConfig {
         self
     }
 
+    /// Set minimum content length
+    pub fn with_min_content_chars(mut self, chars: usize) -> Self {
+        self.min_content_chars = Some(chars);
+        self
+    }
+
+    /// Set collection partitioning strategy
+    pub fn with_collection_strategy(mut self, strategy: CollectionStrategy) -> Self {
+        self.collection_strategy = Some(strategy);
+        self
+    }
+
+    /// Set minimum search score
+    pub fn with_min_score(mut self, score: f32) -> Self {
+        self.min_score = Some(score);
+        self
+    }
+
+    /// Enable/disable score normalization
+    pub fn with_normalized_scores(mut self, enabled: bool) -> Self {
+        self.normalize_scores = Some(enabled);
+        self
+    }
+
+    /// Set memory ID generation scheme
+    pub fn with_id_strategy(mut self, strategy: IdStrategy) -> Self {
+        self.id_strategy = Some(strategy);
+        self
+    }
+
+    /// Set the per-user memory capacity
+    pub fn with_max_memories_per_user(mut self, max: usize) -> Self {
+        self.max_memories_per_user = Some(max);
+        self
+    }
+
+    /// Enable/disable language detection on `add`
+    pub fn with_language_detection(mut self, enabled: bool) -> Self {
+        self.detect_language = Some(enabled);
+        self
+    }
+
+    /// Enable/disable strict collection resolution on reads
+    pub fn with_strict_collections(mut self, enabled: bool) -> Self {
+        self.strict_collections = Some(enabled);
+        self
+    }
+
+    /// Enable/disable PII redaction on `add` and `update`
+    pub fn with_pii_redaction(mut self, enabled: bool) -> Self {
+        self.redact_pii = Some(enabled);
+        self
+    }
+
+    /// Set the minimum confidence for LLM-derived memories
+    pub fn with_min_llm_confidence(mut self, confidence: f32) -> Self {
+        self.min_llm_confidence = Some(confidence);
+        self
+    }
+
+    /// Enable/disable storing low-confidence LLM-derived memories as `unverified`
+    pub fn with_unverified_downgrade(mut self, enabled: bool) -> Self {
+        self.downgrade_unverified = Some(enabled);
+        self
+    }
+
+    /// Set the distance metric of new collections
+    pub fn with_distance_metric(mut self, metric: DistanceMetric) -> Self {
+        self.distance_metric = Some(metric);
+        self
+    }
+
+    /// Set the timeout of each LLM call
+    pub fn with_llm_timeout(mut self, timeout: Duration) -> Self {
+        self.llm_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    /// Set the timeout of each embedding call
+    pub fn with_embedding_timeout(mut self, timeout: Duration) -> Self {
+        self.embedding_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    /// Set the timeout of each vector store call
+    pub fn with_vector_store_timeout(mut self, timeout: Duration) -> Self {
+        self.vector_store_timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    /// Set the prefix instruction-tuned embedding models expect on queries
+    pub fn with_embedding_query_prefix(mut self, prefix: String) -> Self {
+        self.embedding_query_prefix = Some(prefix);
+        self
+    }
+
+    /// Set the prefix instruction-tuned embedding models expect on documents
+    pub fn with_embedding_document_prefix(mut self, prefix: String) -> Self {
+        self.embedding_document_prefix = Some(prefix);
+        self
+    }
+
     /// Get LLM model name
     pub fn get_llm_model(&self) -> String {
         self.llm_model
@@ -106,8 +334,12 @@ impl MemoryConfig {
     }
 
     /// Get vector dimension
+    ///
+    /// When unset, falls back to the dimension of a known embedding model.
     pub fn get_vector_dimension(&self) -> usize {
-        self.vector_dimension.unwrap_or(384)
+        self.vector_dimension
+            .or_else(|| self.embedding_model.as_deref().and_then(ModelRegistry::dimension_of))
+            .unwrap_or(384)
     }
 
     /// Get collection prefix
@@ -126,6 +358,255 @@ impl MemoryConfig {
     pub fn get_batch_size(&self) -> usize {
         self.batch_size.unwrap_or(32)
     }
+
+    /// Get minimum content length
+    pub fn get_min_content_chars(&self) -> usize {
+        self.min_content_chars.unwrap_or(1)
+    }
+
+    /// Get collection partitioning strategy
+    pub fn get_collection_strategy(&self) -> CollectionStrategy {
+        self.collection_strategy.unwrap_or_default()
+    }
+
+    /// Get minimum search score, if set
+    pub fn get_min_score(&self) -> Option<f32> {
+        self.min_score
+    }
+
+    /// Check if score normalization is enabled
+    pub fn is_score_normalization_enabled(&self) -> bool {
+        self.normalize_scores.unwrap_or(false)
+    }
+
+    /// Get memory ID generation scheme
+    pub fn get_id_strategy(&self) -> IdStrategy {
+        self.id_strategy.unwrap_or_default()
+    }
+
+    /// Get the per-user memory capacity, if bounded
+    pub fn get_max_memories_per_user(&self) -> Option<usize> {
+        self.max_memories_per_user
+    }
+
+    /// Check if language detection is enabled
+    pub fn is_language_detection_enabled(&self) -> bool {
+        self.detect_language.unwrap_or(false)
+    }
+
+    /// Check if strict collection resolution is enabled
+    pub fn is_strict_collections_enabled(&self) -> bool {
+        self.strict_collections.unwrap_or(false)
+    }
+
+    /// Check if PII redaction is enabled
+    pub fn is_pii_redaction_enabled(&self) -> bool {
+        self.redact_pii.unwrap_or(false)
+    }
+
+    /// Get the minimum confidence for LLM-derived memories
+    pub fn get_min_llm_confidence(&self) -> f32 {
+        self.min_llm_confidence.unwrap_or(0.0)
+    }
+
+    /// Check if low-confidence LLM-derived memories are stored as `unverified`
+    pub fn is_unverified_downgrade_enabled(&self) -> bool {
+        self.downgrade_unverified.unwrap_or(false)
+    }
+
+    /// Get the distance metric of new collections, if configured
+    pub fn get_distance_metric(&self) -> Option<DistanceMetric> {
+        self.distance_metric
+    }
+
+    /// Get the LLM call timeout, if configured
+    pub fn get_llm_timeout(&self) -> Option<Duration> {
+        self.llm_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Get the embedding call timeout, if configured
+    pub fn get_embedding_timeout(&self) -> Option<Duration> {
+        self.embedding_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Get the vector store call timeout, if configured
+    pub fn get_vector_store_timeout(&self) -> Option<Duration> {
+        self.vector_store_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Get the query embedding prefix, if configured
+    pub fn get_embedding_query_prefix(&self) -> Option<&str> {
+        self.embedding_query_prefix.as_deref()
+    }
+
+    /// Get the document embedding prefix, if configured
+    pub fn get_embedding_document_prefix(&self) -> Option<&str> {
+        self.embedding_document_prefix.as_deref()
+    }
+
+    /// Load and validate a TOML configuration file
+    ///
+    /// Unset optional fields fall back to the getters' defaults.
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::config(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let config: Self = toml::from_str(&text)
+            .map_err(|e| Error::config(format!("Invalid config {}: {}", path.display(), e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Build a configuration from `MEMORY_*` environment variables
+    ///
+    /// `MEMORY_DB_PATH` is required; see `with_env_overrides` for the
+    /// other variables.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Override fields with any `MEMORY_*` environment variables that are set
+    ///
+    /// Reads `MEMORY_DB_PATH`, `MEMORY_WATSONX_API_KEY`,
+    /// `MEMORY_WATSONX_PROJECT_ID`, `MEMORY_LLM_MODEL`,
+    /// `MEMORY_EMBEDDING_MODEL`, `MEMORY_COLLECTION_PREFIX`,
+    /// `MEMORY_EMBEDDING_QUERY_PREFIX`, `MEMORY_EMBEDDING_DOCUMENT_PREFIX`,
+    /// `MEMORY_VECTOR_DIMENSION`, `MEMORY_ENABLE_TELEMETRY`,
+    /// `MEMORY_BATCH_SIZE`, `MEMORY_MIN_CONTENT_CHARS`, `MEMORY_MIN_SCORE`,
+    /// `MEMORY_NORMALIZE_SCORES`, `MEMORY_MAX_MEMORIES_PER_USER`,
+    /// `MEMORY_DETECT_LANGUAGE`, `MEMORY_STRICT_COLLECTIONS`,
+    /// `MEMORY_REDACT_PII`, `MEMORY_MIN_LLM_CONFIDENCE`,
+    /// `MEMORY_DOWNGRADE_UNVERIFIED`, `MEMORY_DISTANCE_METRIC`,
+    /// `MEMORY_LLM_TIMEOUT_MS`, `MEMORY_EMBEDDING_TIMEOUT_MS` and
+    /// `MEMORY_VECTOR_STORE_TIMEOUT_MS`.
+    pub fn with_env_overrides(self) -> Result<Self> {
+        self.apply_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Build a configuration from variables returned by `lookup`
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let db_path = lookup(&format!("{}DB_PATH", ENV_PREFIX))
+            .ok_or_else(|| Error::config(format!("{}DB_PATH is not set", ENV_PREFIX)))?;
+        Self::new(db_path).apply_vars(lookup)
+    }
+
+    /// Override fields with variables returned by `lookup`, then validate
+    fn apply_vars(mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| lookup(&format!("{}{}", ENV_PREFIX, name));
+
+        if let Some(value) = var("DB_PATH") {
+            self.db_path = value;
+        }
+        if let Some(value) = var("WATSONX_API_KEY") {
+            self.watsonx_api_key = Some(value);
+        }
+        if let Some(value) = var("WATSONX_PROJECT_ID") {
+            self.watsonx_project_id = Some(value);
+        }
+        if let Some(value) = var("LLM_MODEL") {
+            self.llm_model = Some(value);
+        }
+        if let Some(value) = var("EMBEDDING_MODEL") {
+            self.vector_dimension = ModelRegistry::dimension_of(&value).or(self.vector_dimension);
+            self.embedding_model = Some(value);
+        }
+        if let Some(value) = var("COLLECTION_PREFIX") {
+            self.collection_prefix = Some(value);
+        }
+        if let Some(value) = var("EMBEDDING_QUERY_PREFIX") {
+            self.embedding_query_prefix = Some(value);
+        }
+        if let Some(value) = var("EMBEDDING_DOCUMENT_PREFIX") {
+            self.embedding_document_prefix = Some(value);
+        }
+        self.vector_dimension = parse_var("VECTOR_DIMENSION", var("VECTOR_DIMENSION"))?
+            .or(self.vector_dimension);
+        self.enable_telemetry = parse_var("ENABLE_TELEMETRY", var("ENABLE_TELEMETRY"))?
+            .or(self.enable_telemetry);
+        self.batch_size = parse_var("BATCH_SIZE", var("BATCH_SIZE"))?.or(self.batch_size);
+        self.min_content_chars = parse_var("MIN_CONTENT_CHARS", var("MIN_CONTENT_CHARS"))?
+            .or(self.min_content_chars);
+        self.min_score = parse_var("MIN_SCORE", var("MIN_SCORE"))?.or(self.min_score);
+        self.normalize_scores = parse_var("NORMALIZE_SCORES", var("NORMALIZE_SCORES"))?
+            .or(self.normalize_scores);
+        self.max_memories_per_user =
+            parse_var("MAX_MEMORIES_PER_USER", var("MAX_MEMORIES_PER_USER"))?
+                .or(self.max_memories_per_user);
+        self.detect_language = parse_var("DETECT_LANGUAGE", var("DETECT_LANGUAGE"))?
+            .or(self.detect_language);
+        self.strict_collections = parse_var("STRICT_COLLECTIONS", var("STRICT_COLLECTIONS"))?
+            .or(self.strict_collections);
+        self.redact_pii = parse_var("REDACT_PII", var("REDACT_PII"))?.or(self.redact_pii);
+        self.min_llm_confidence = parse_var("MIN_LLM_CONFIDENCE", var("MIN_LLM_CONFIDENCE"))?
+            .or(self.min_llm_confidence);
+        self.downgrade_unverified =
+            parse_var("DOWNGRADE_UNVERIFIED", var("DOWNGRADE_UNVERIFIED"))?
+                .or(self.downgrade_unverified);
+        self.distance_metric = parse_var("DISTANCE_METRIC", var("DISTANCE_METRIC"))?
+            .or(self.distance_metric);
+        self.llm_timeout_ms = parse_var("LLM_TIMEOUT_MS", var("LLM_TIMEOUT_MS"))?
+            .or(self.llm_timeout_ms);
+        self.embedding_timeout_ms =
+            parse_var("EMBEDDING_TIMEOUT_MS", var("EMBEDDING_TIMEOUT_MS"))?
+                .or(self.embedding_timeout_ms);
+        self.vector_store_timeout_ms =
+            parse_var("VECTOR_STORE_TIMEOUT_MS", var("VECTOR_STORE_TIMEOUT_MS"))?
+                .or(self.vector_store_timeout_ms);
+
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check that the configuration values are usable
+    pub fn validate(&self) -> Result<()> {
+        if self.db_path.trim().is_empty() {
+            return Err(Error::config("db_path must not be empty"));
+        }
+        if self.vector_dimension == Some(0) {
+            return Err(Error::config("vector_dimension must be greater than 0"));
+        }
+        if self.batch_size == Some(0) {
+            return Err(Error::config("batch_size must be greater than 0"));
+        }
+        if self.max_memories_per_user == Some(0) {
+            return Err(Error::config("max_memories_per_user must be greater than 0"));
+        }
+        if let Some(score) = self.min_score
+            && !score.is_finite()
+        {
+            return Err(Error::config("min_score must be finite"));
+        }
+        if let Some(confidence) = self.min_llm_confidence
+            && !(0.0..=1.0).contains(&confidence)
+        {
+            return Err(Error::config("min_llm_confidence must be between 0 and 1"));
+        }
+        for (name, timeout) in [
+            ("llm_timeout_ms", self.llm_timeout_ms),
+            ("embedding_timeout_ms", self.embedding_timeout_ms),
+            ("vector_store_timeout_ms", self.vector_store_timeout_ms),
+        ] {
+            if timeout == Some(0) {
+                return Err(Error::config(format!("{} must be greater than 0", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse an optional `MEMORY_*` variable value
+fn parse_var<T: FromStr>(name: &str, value: Option<String>) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .map(|v| {
+            v.trim().parse().map_err(|e| {
+                Error::config(format!("Invalid {}{} {:?}: {}", ENV_PREFIX, name, v, e))
+            })
+        })
+        .transpose()
 }
 
 #[cfg(test)]
@@ -140,6 +621,97 @@ mod tests {
         assert_eq!(config.get_collection_prefix(), "memory");
         assert!(!config.is_telemetry_enabled());
         assert_eq!(config.get_batch_size(), 32);
+        assert_eq!(config.get_min_content_chars(), 1);
+        assert_eq!(config.get_collection_strategy(), CollectionStrategy::PerUser);
+        assert_eq!(config.get_id_strategy(), IdStrategy::Uuidv4);
+    }
+
+    /// Variable lookup backed by a fixed list
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: std::collections::HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_env_overrides_toml() {
+        let path = std::env::temp_dir().join(format!("memory_config_{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "db_path = \"file.db\"\nvector_dimension = 768\nbatch_size = 16\n")
+            .unwrap();
+
+        let config = MemoryConfig::from_toml(&path)
+            .unwrap()
+            .apply_vars(vars(&[("MEMORY_VECTOR_DIMENSION", "1024")]))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.db_path, "file.db");
+        assert_eq!(config.get_vector_dimension(), 1024);
+        assert_eq!(config.get_batch_size(), 16);
+    }
+
+    #[test]
+    fn test_embedding_prefix_vars() {
+        let config = MemoryConfig::new("memory.db".to_string())
+            .apply_vars(vars(&[
+                ("MEMORY_EMBEDDING_QUERY_PREFIX", "search_query: "),
+                ("MEMORY_EMBEDDING_DOCUMENT_PREFIX", "search_document: "),
+            ]))
+            .unwrap();
+        assert_eq!(config.get_embedding_query_prefix(), Some("search_query: "));
+        assert_eq!(config.get_embedding_document_prefix(), Some("search_document: "));
+        assert_eq!(MemoryConfig::new("memory.db".to_string()).get_embedding_query_prefix(), None);
+    }
+
+    #[test]
+    fn test_embedding_model_sets_dimension() {
+        let config = MemoryConfig::new("memory.db".to_string())
+            .with_embedding_model("text-embedding-3-small".to_string());
+        assert_eq!(config.get_vector_dimension(), 1536);
+
+        let config = MemoryConfig::new("memory.db".to_string())
+            .with_embedding_model("my-custom-model".to_string());
+        assert_eq!(config.get_vector_dimension(), 384);
+
+        let config = MemoryConfig::new("memory.db".to_string())
+            .apply_vars(vars(&[
+                ("MEMORY_EMBEDDING_MODEL", "embed-english-v3.0"),
+                ("MEMORY_VECTOR_DIMENSION", "512"),
+            ]))
+            .unwrap();
+        assert_eq!(config.get_vector_dimension(), 512);
+    }
+
+    #[test]
+    fn test_timeout_vars() {
+        let config = MemoryConfig::new("memory.db".to_string())
+            .apply_vars(vars(&[("MEMORY_EMBEDDING_TIMEOUT_MS", "250")]))
+            .unwrap();
+        assert_eq!(config.get_embedding_timeout(), Some(Duration::from_millis(250)));
+        assert_eq!(config.get_llm_timeout(), None);
+
+        let result = MemoryConfig::new("memory.db".to_string())
+            .apply_vars(vars(&[("MEMORY_LLM_TIMEOUT_MS", "0")]));
+        assert!(matches!(result, Err(Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_missing_required_fields() {
+        let path = std::env::temp_dir().join(format!("memory_config_{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "vector_dimension = 768\n").unwrap();
+        let result = MemoryConfig::from_toml(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::ConfigError(_))));
+
+        assert!(matches!(MemoryConfig::from_vars(vars(&[])), Err(Error::ConfigError(_))));
+        assert!(matches!(
+            MemoryConfig::from_vars(vars(&[("MEMORY_DB_PATH", "env.db"), ("MEMORY_BATCH_SIZE", "many")])),
+            Err(Error::ConfigError(_))
+        ));
+        let config = MemoryConfig::from_vars(vars(&[("MEMORY_DB_PATH", "env.db")])).unwrap();
+        assert_eq!(config.db_path, "env.db");
     }
 
     #[test]
@@ -162,4 +734,24 @@ mod tests {
         assert_eq!(config.watsonx_api_key, Some("api-key".to_string()));
         assert_eq!(config.watsonx_project_id, Some("project-id".to_string()));
     }
+
+    #[test]
+    fn test_uuidv7_ids_sort_in_creation_order() {
+        let ids: Vec<String> = (0..100).map(|_| IdStrategy::Uuidv7.generate()).collect();
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+        assert_eq!(ids.iter().collect::<std::collections::HashSet<_>>().len(), ids.len());
+    }
+
+    #[test]
+    fn test_ksuid_ids() {
+        let a = IdStrategy::Ksuid.generate();
+        let b = IdStrategy::Ksuid.generate();
+
+        assert_eq!(a.len(), 27);
+        assert!(a.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(a, b);
+    }
 }
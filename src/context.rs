@@ -0,0 +1,187 @@
+//! Per-operation context propagated to backend requests
+//!
+//! Run memory operations inside [`OperationContext::scope`] to tag outbound
+//! embedder and store HTTP requests, and audit events, with a trace id and
+//! namespace. [`OperationContext::run`] additionally enforces the context's
+//! deadline.
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{Error, Result};
+
+/// Header carrying the trace id
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+
+/// Header carrying the namespace
+pub const NAMESPACE_HEADER: &str = "x-namespace";
+
+tokio::task_local! {
+    static CURRENT: OperationContext;
+}
+
+/// Observability context for a memory operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationContext {
+    /// Trace ID from the incoming request
+    pub trace_id: Option<String>,
+
+    /// Namespace (tenant, environment, etc.)
+    pub namespace: Option<String>,
+
+    /// Time by which the operation must complete
+    #[serde(skip)]
+    pub deadline: Option<Instant>,
+}
+
+impl OperationContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set trace ID
+    pub fn with_trace_id(mut self, trace_id: String) -> Self {
+        self.trace_id = Some(trace_id);
+        self
+    }
+
+    /// Set namespace
+    pub fn with_namespace(mut self, namespace: String) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Set the deadline
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline to `timeout` from now
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Run a future with this context as the current context
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Run a fallible operation in this context, enforcing the deadline
+    ///
+    /// When the deadline passes first the operation is dropped, cancelling
+    /// its in-flight embedding, LLM and store requests, and
+    /// `Error::Timeout` is returned.
+    pub async fn run<T, F>(self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, self.scope(future))
+                .await
+                .map_err(|_| Error::timeout("operation deadline exceeded"))?,
+            None => self.scope(future).await,
+        }
+    }
+
+    /// Get the current context, if running inside `scope`
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|context| context.clone()).ok()
+    }
+
+    /// Headers to attach to outbound requests for the current context
+    pub fn current_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let Some(context) = Self::current() else {
+            return headers;
+        };
+
+        for (name, value) in [
+            (TRACE_ID_HEADER, context.trace_id),
+            (NAMESPACE_HEADER, context.namespace),
+        ] {
+            let Some(value) = value else { continue };
+            match HeaderValue::from_str(&value) {
+                Ok(value) => {
+                    headers.insert(name, value);
+                }
+                Err(_) => tracing::warn!("Skipping invalid {} header value", name),
+            }
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::{EmbedderBase, OpenAIEmbedder};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer one embeddings request, returning its lowercased request head
+    async fn capture_request(listener: &TcpListener) -> String {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4096];
+        let head_end = loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the request head");
+            received.extend_from_slice(&buf[..n]);
+            if let Some(pos) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&received[..head_end]).to_lowercase();
+
+        // Drain the body before replying
+        let content_length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|len| len.trim().parse().unwrap())
+            .unwrap_or(0);
+        while received.len() < head_end + content_length {
+            let n = socket.read(&mut buf).await.unwrap();
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        let body = r#"{"data":[{"index":0,"embedding":[0.5,0.5]}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        head
+    }
+
+    #[tokio::test]
+    async fn test_trace_id_on_outbound_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let embedder = OpenAIEmbedder::new("test-key".to_string(), "test-model".to_string(), 2)
+            .with_base_url(base_url);
+
+        let context = OperationContext::new()
+            .with_trace_id("trace-123".to_string())
+            .with_namespace("tenant-a".to_string());
+        let (head, embedding) = tokio::join!(
+            capture_request(&listener),
+            context.scope(embedder.embed("tea"))
+        );
+        assert_eq!(embedding.unwrap(), vec![0.5, 0.5]);
+        assert!(head.starts_with("post /v1/embeddings "));
+        assert!(head.contains(&format!("{}: trace-123\r\n", TRACE_ID_HEADER)));
+        assert!(head.contains(&format!("{}: tenant-a\r\n", NAMESPACE_HEADER)));
+
+        // Outside a scope no context headers are sent
+        let (head, embedding) = tokio::join!(capture_request(&listener), embedder.embed("tea"));
+        assert!(embedding.is_ok());
+        assert!(!head.contains(TRACE_ID_HEADER));
+        assert!(!head.contains(NAMESPACE_HEADER));
+    }
+}
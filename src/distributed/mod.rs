@@ -1,7 +1,11 @@
 //! Distributed memory management
 
 use async_trait::async_trait;
-use crate::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use crate::{Error, Result};
+use crate::memory::main::{DEFAULT_IMPORTANCE, IMPORTANCE_KEY};
+use crate::vector_store::VectorMetadata;
 
 /// Node role in the cluster
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -150,6 +154,139 @@ impl ConsensusProtocol {
     }
 }
 
+/// Rule for choosing between divergent copies of the same memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    /// Keep the copy with the latest `updated_at`
+    #[default]
+    LastWriteWins,
+    /// Keep the copy with the highest importance, then the latest
+    HighestImportance,
+    /// Keep neither; report the conflict for an operator to resolve
+    Manual,
+}
+
+impl ConflictResolution {
+    /// Get resolution name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LastWriteWins => "last_write_wins",
+            Self::HighestImportance => "highest_importance",
+            Self::Manual => "manual",
+        }
+    }
+
+    /// Choose between a local and a remote copy of the same memory
+    ///
+    /// Returns `None` under `Manual`. Ties keep the local copy; unparsable
+    /// timestamps count as oldest.
+    pub fn resolve<'a>(
+        &self,
+        local: &'a VectorMetadata,
+        remote: &'a VectorMetadata,
+    ) -> Option<&'a VectorMetadata> {
+        let updated = |m: &VectorMetadata| {
+            DateTime::parse_from_rfc3339(&m.updated_at)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let importance = |m: &VectorMetadata| {
+            m.custom_metadata
+                .get(IMPORTANCE_KEY)
+                .and_then(|v| v.parse::<f32>().ok())
+                .unwrap_or(DEFAULT_IMPORTANCE)
+        };
+
+        let remote_wins = match self {
+            Self::LastWriteWins => updated(remote) > updated(local),
+            Self::HighestImportance => match importance(remote).total_cmp(&importance(local)) {
+                std::cmp::Ordering::Equal => updated(remote) > updated(local),
+                ordering => ordering.is_gt(),
+            },
+            Self::Manual => return None,
+        };
+
+        Some(if remote_wins { remote } else { local })
+    }
+
+    /// Merge a local and a remote set of memories
+    ///
+    /// Memories present on one side only are kept; copies that differ are
+    /// resolved with this rule or reported as conflicts.
+    pub fn reconcile(
+        &self,
+        local: Vec<VectorMetadata>,
+        remote: Vec<VectorMetadata>,
+    ) -> Reconciliation {
+        let mut remote: HashMap<String, VectorMetadata> =
+            remote.into_iter().map(|m| (m.id.clone(), m)).collect();
+        let mut result = Reconciliation::default();
+
+        for local in local {
+            let Some(remote) = remote.remove(&local.id) else {
+                result.resolved.push(local);
+                continue;
+            };
+            if local.updated_at == remote.updated_at && local.text == remote.text {
+                result.resolved.push(local);
+                continue;
+            }
+            match self.resolve(&local, &remote) {
+                Some(winner) => result.resolved.push(winner.clone()),
+                None => result.conflicts.push((local, remote)),
+            }
+        }
+        result.resolved.extend(remote.into_values());
+
+        result
+    }
+}
+
+/// Number of replica acknowledgements a write must collect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Consistency {
+    /// A single replica
+    One,
+    /// A majority of replicas (`replica_count / 2 + 1`)
+    #[default]
+    Quorum,
+    /// Every replica
+    All,
+}
+
+impl Consistency {
+    /// Get consistency level name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::One => "one",
+            Self::Quorum => "quorum",
+            Self::All => "all",
+        }
+    }
+
+    /// Acknowledgements required out of `replica_count` replicas
+    ///
+    /// Never exceeds `replica_count`, so writes to an unreplicated store
+    /// need no acknowledgements.
+    pub fn required_acks(&self, replica_count: usize) -> usize {
+        let required = match self {
+            Self::One => 1,
+            Self::Quorum => replica_count / 2 + 1,
+            Self::All => replica_count,
+        };
+        required.min(replica_count)
+    }
+}
+
+/// Outcome of reconciling two sets of memories
+#[derive(Debug, Clone, Default)]
+pub struct Reconciliation {
+    /// Memories to keep
+    pub resolved: Vec<VectorMetadata>,
+    /// Unresolved (local, remote) pairs left for manual resolution
+    pub conflicts: Vec<(VectorMetadata, VectorMetadata)>,
+}
+
 /// Distributed configuration
 #[derive(Debug, Clone)]
 pub struct DistributedConfig {
@@ -165,6 +302,8 @@ pub struct DistributedConfig {
     pub consensus: ConsensusProtocol,
     /// Number of shards
     pub shard_count: usize,
+    /// Rule for divergent copies found during rebalancing and replication
+    pub conflict_resolution: ConflictResolution,
 }
 
 impl DistributedConfig {
@@ -177,6 +316,7 @@ impl DistributedConfig {
             sharding: ShardingStrategy::Hash,
             consensus: ConsensusProtocol::Raft,
             shard_count: 16,
+            conflict_resolution: ConflictResolution::default(),
         }
     }
 
@@ -203,6 +343,12 @@ impl DistributedConfig {
         self.shard_count = count;
         self
     }
+
+    /// Set conflict resolution rule
+    pub fn with_conflict_resolution(mut self, resolution: ConflictResolution) -> Self {
+        self.conflict_resolution = resolution;
+        self
+    }
 }
 
 /// Base trait for distributed store implementations
@@ -226,10 +372,47 @@ pub trait DistributedStoreBase: Send + Sync {
     /// Replicate data to node
     async fn replicate(&self, node_id: &str, data: Vec<u8>) -> Result<()>;
 
+    /// Replicate data to every secondary node at a consistency level
+    ///
+    /// Succeeds once the level's required number of `replicate` calls have
+    /// acknowledged, returning the acknowledgement count; fails otherwise.
+    async fn replicate_write(&self, data: Vec<u8>, consistency: Consistency) -> Result<usize> {
+        let replicas: Vec<NodeInfo> = self
+            .get_nodes()
+            .await?
+            .into_iter()
+            .filter(|n| n.role == NodeRole::Secondary)
+            .collect();
+
+        let mut acks = 0;
+        let mut last_error = None;
+        for replica in &replicas {
+            match self.replicate(&replica.id, data.clone()).await {
+                Ok(()) => acks += 1,
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        let required = consistency.required_acks(replicas.len());
+        if acks < required {
+            return Err(Error::internal(format!(
+                "Write at consistency {} got {} of {} required acks{}",
+                consistency.name(),
+                acks,
+                required,
+                last_error.map(|e| format!(": {}", e)).unwrap_or_default()
+            )));
+        }
+        Ok(acks)
+    }
+
     /// Get replication status
     async fn replication_status(&self) -> Result<ReplicationStatus>;
 
     /// Trigger rebalancing
+    ///
+    /// Divergent copies of a memory are merged with the configured
+    /// `ConflictResolution` (see `ConflictResolution::reconcile`).
     async fn rebalance(&self) -> Result<()>;
 
     /// Get node health
@@ -330,4 +513,157 @@ mod tests {
         assert_eq!(ConsensusProtocol::Paxos.name(), "paxos");
         assert_eq!(ConsensusProtocol::Quorum.name(), "quorum");
     }
+
+    fn version(id: &str, text: &str, updated_at: &str) -> VectorMetadata {
+        VectorMetadata {
+            id: id.to_string(),
+            user_id: "user_1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: text.to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: updated_at.to_string(),
+            custom_metadata: HashMap::new(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_last_write_wins_reconcile() {
+        let local = vec![
+            version("1", "I live in Paris", "2024-01-02T00:00:00Z"),
+            version("2", "I like tea", "2024-01-01T00:00:00Z"),
+        ];
+        let remote = vec![
+            version("1", "I live in Berlin", "2024-03-01T00:00:00Z"),
+            version("3", "I have a cat", "2024-01-01T00:00:00Z"),
+        ];
+
+        let result = ConflictResolution::LastWriteWins.reconcile(local.clone(), remote.clone());
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.resolved.len(), 3);
+        let merged = result.resolved.iter().find(|m| m.id == "1").unwrap();
+        assert_eq!(merged.text, "I live in Berlin");
+
+        let manual = ConflictResolution::Manual.reconcile(local, remote);
+        assert_eq!(manual.resolved.len(), 2);
+        assert_eq!(manual.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn test_consistency_required_acks() {
+        assert_eq!(Consistency::One.required_acks(3), 1);
+        assert_eq!(Consistency::Quorum.required_acks(3), 2);
+        assert_eq!(Consistency::Quorum.required_acks(4), 3);
+        assert_eq!(Consistency::All.required_acks(3), 3);
+        assert_eq!(Consistency::One.required_acks(0), 0);
+    }
+
+    /// Cluster of in-process replicas, some of which reject writes
+    struct SimulatedCluster {
+        replicas: Vec<NodeInfo>,
+        failing: Vec<String>,
+        written: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl SimulatedCluster {
+        fn new(replica_count: usize, failing: &[&str]) -> Self {
+            Self {
+                replicas: (0..replica_count)
+                    .map(|i| {
+                        NodeInfo::new(
+                            format!("replica_{}", i),
+                            format!("localhost:{}", 7001 + i),
+                            NodeRole::Secondary,
+                        )
+                    })
+                    .collect(),
+                failing: failing.iter().map(|id| id.to_string()).collect(),
+                written: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DistributedStoreBase for SimulatedCluster {
+        async fn join_cluster(&self, _seed_nodes: Vec<String>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn leave_cluster(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn cluster_status(&self) -> Result<ClusterStatus> {
+            Ok(ClusterStatus {
+                name: "simulated".to_string(),
+                total_nodes: self.replicas.len() + 1,
+                healthy_nodes: self.replicas.len() + 1 - self.failing.len(),
+                total_shards: 1,
+                leader: Some("primary".to_string()),
+            })
+        }
+
+        async fn get_nodes(&self) -> Result<Vec<NodeInfo>> {
+            let primary = NodeInfo::new(
+                "primary".to_string(),
+                "localhost:7000".to_string(),
+                NodeRole::Primary,
+            );
+            Ok(std::iter::once(primary).chain(self.replicas.clone()).collect())
+        }
+
+        async fn get_shards(&self) -> Result<Vec<ShardInfo>> {
+            Ok(vec![ShardInfo::new(0, "primary".to_string())])
+        }
+
+        async fn replicate(&self, node_id: &str, _data: Vec<u8>) -> Result<()> {
+            if self.failing.iter().any(|id| id == node_id) {
+                return Err(Error::internal(format!("{} is unreachable", node_id)));
+            }
+            self.written.lock().unwrap().push(node_id.to_string());
+            Ok(())
+        }
+
+        async fn replication_status(&self) -> Result<ReplicationStatus> {
+            Ok(ReplicationStatus {
+                total_replicated: self.written.lock().unwrap().len(),
+                pending: 0,
+                failed: 0,
+                lag_ms: 0,
+            })
+        }
+
+        async fn rebalance(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn node_health(&self, node_id: &str) -> Result<bool> {
+            Ok(!self.failing.iter().any(|id| id == node_id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_consistency_levels() {
+        let cluster = SimulatedCluster::new(3, &["replica_1"]);
+
+        let result = cluster.replicate_write(b"memory".to_vec(), Consistency::All).await;
+        assert!(result.is_err());
+
+        assert_eq!(
+            cluster.replicate_write(b"memory".to_vec(), Consistency::One).await.unwrap(),
+            2
+        );
+        assert_eq!(
+            cluster.replicate_write(b"memory".to_vec(), Consistency::Quorum).await.unwrap(),
+            2
+        );
+        assert!(!cluster.written.lock().unwrap().contains(&"primary".to_string()));
+
+        let degraded = SimulatedCluster::new(3, &["replica_0", "replica_1"]);
+        assert!(degraded.replicate_write(b"memory".to_vec(), Consistency::Quorum).await.is_err());
+        assert!(degraded.replicate_write(b"memory".to_vec(), Consistency::One).await.is_ok());
+    }
 }
@@ -0,0 +1,295 @@
+//! Embedder decorator that coalesces concurrent requests into batches
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+/// A queued `embed` or `embed_document` call awaiting its batch
+struct PendingEmbed {
+    text: String,
+    /// Whether the text is a document, embedded with `embed_documents`
+    document: bool,
+    reply: oneshot::Sender<Result<Vec<f32>>>,
+}
+
+/// Embedder that buffers single `embed` calls and sends them as one `embed_batch`
+///
+/// A batch is flushed when `max_batch` calls are queued or `max_wait` has
+/// passed since the first queued call, whichever comes first. Queued
+/// `embed_document` calls are sent together as one `embed_documents`.
+/// Calls to `embed_query`, `embed_batch` and `embed_documents` go straight
+/// to the inner embedder.
+pub struct BatchingEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    max_batch: usize,
+    max_wait: Duration,
+    /// Queue of the background worker, started on first use
+    ///
+    /// Replaced when the worker has stopped, e.g. because the runtime it
+    /// was spawned on shut down.
+    queue: Mutex<Option<mpsc::UnboundedSender<PendingEmbed>>>,
+}
+
+impl BatchingEmbedder {
+    /// Create a batching embedder
+    pub fn new(inner: Arc<dyn EmbedderBase>, max_batch: usize, max_wait: Duration) -> Self {
+        Self {
+            inner,
+            max_batch: max_batch.max(1),
+            max_wait,
+            queue: Mutex::new(None),
+        }
+    }
+
+    /// Start the worker that collects queued calls into batches
+    ///
+    /// The worker stops once the embedder, and with it the sender, is
+    /// dropped, or when its runtime shuts down.
+    fn spawn_worker(&self) -> mpsc::UnboundedSender<PendingEmbed> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<PendingEmbed>();
+        let inner = self.inner.clone();
+        let max_batch = self.max_batch;
+        let max_wait = self.max_wait;
+
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                let mut batch = vec![first];
+                let deadline = tokio::time::Instant::now() + max_wait;
+                while batch.len() < max_batch {
+                    match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Some(pending)) => batch.push(pending),
+                        Ok(None) | Err(_) => break,
+                    }
+                }
+
+                // Embed concurrently with collecting the next batch
+                tokio::spawn(flush(inner.clone(), batch));
+            }
+        });
+
+        sender
+    }
+
+    /// Hand a call to the worker, starting a new one if it has stopped
+    fn enqueue(&self, pending: PendingEmbed) -> Result<()> {
+        let mut queue = self.queue.lock().unwrap();
+        let pending = match queue.as_ref() {
+            Some(sender) => match sender.send(pending) {
+                Ok(()) => return Ok(()),
+                Err(mpsc::error::SendError(pending)) => pending,
+            },
+            None => pending,
+        };
+
+        let sender = self.spawn_worker();
+        sender
+            .send(pending)
+            .map_err(|_| Error::embedding("Batching worker stopped"))?;
+        *queue = Some(sender);
+        Ok(())
+    }
+
+    /// Queue a single text for the next batch and wait for its embedding
+    ///
+    /// A call dropped by a stopping worker is embedded inline instead.
+    async fn embed_queued(&self, text: &str, document: bool) -> Result<Vec<f32>> {
+        let (reply, response) = oneshot::channel();
+        self.enqueue(PendingEmbed {
+            text: text.to_string(),
+            document,
+            reply,
+        })?;
+
+        match response.await {
+            Ok(result) => result,
+            Err(_) if document => self.inner.embed_document(text).await,
+            Err(_) => self.inner.embed(text).await,
+        }
+    }
+}
+
+/// Embed a batch and hand each caller its embedding
+///
+/// Plain texts and documents are embedded with separate inner calls.
+async fn flush(inner: Arc<dyn EmbedderBase>, batch: Vec<PendingEmbed>) {
+    let (documents, plain): (Vec<_>, Vec<_>) = batch.into_iter().partition(|p| p.document);
+    for (group, document) in [(plain, false), (documents, true)] {
+        if !group.is_empty() {
+            flush_group(inner.as_ref(), group, document).await;
+        }
+    }
+}
+
+/// Embed one group of a batch in a single inner call
+async fn flush_group(inner: &dyn EmbedderBase, batch: Vec<PendingEmbed>, document: bool) {
+    let texts: Vec<&str> = batch.iter().map(|p| p.text.as_str()).collect();
+    let result = if document {
+        inner.embed_documents(texts).await
+    } else {
+        inner.embed_batch(texts).await
+    };
+    match result {
+        Ok(embeddings) if embeddings.len() == batch.len() => {
+            for (pending, embedding) in batch.into_iter().zip(embeddings) {
+                let _ = pending.reply.send(Ok(embedding));
+            }
+        }
+        Ok(embeddings) => {
+            let message = format!(
+                "Batch returned {} embeddings for {} texts",
+                embeddings.len(),
+                batch.len()
+            );
+            for pending in batch {
+                let _ = pending.reply.send(Err(Error::embedding(message.clone())));
+            }
+        }
+        Err(e) => {
+            let message = e.to_string();
+            for pending in batch {
+                let _ = pending.reply.send(Err(Error::embedding(message.clone())));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for BatchingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_queued(text, false).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed_batch(texts).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.inner.embed_query(text).await
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_queued(text, true).await
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed_documents(texts).await
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embedder that counts `embed_batch` calls
+    #[derive(Default)]
+    struct CountingEmbedder {
+        batches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for CountingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            Ok(vec![text.len() as f32])
+        }
+
+        async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+
+        async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            Ok(texts.iter().map(|t| vec![-(t.len() as f32)]).collect())
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_coalesce() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = BatchingEmbedder::new(inner.clone(), 10, Duration::from_millis(50));
+
+        let texts = ["a", "bb", "ccc", "dddd", "eeeee"];
+        let embeddings =
+            futures::future::try_join_all(texts.iter().map(|t| embedder.embed(t))).await.unwrap();
+
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 1);
+        let expected: Vec<Vec<f32>> = texts.iter().map(|t| vec![t.len() as f32]).collect();
+        assert_eq!(embeddings, expected);
+    }
+
+    #[tokio::test]
+    async fn test_full_batch_flushes_early() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = BatchingEmbedder::new(inner.clone(), 2, Duration::from_secs(10));
+
+        let started = std::time::Instant::now();
+        futures::future::try_join_all(["a", "b", "c", "d"].iter().map(|t| embedder.embed(t)))
+            .await
+            .unwrap();
+
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_worker_restarts_after_runtime_shutdown() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = BatchingEmbedder::new(inner.clone(), 10, Duration::from_millis(1));
+
+        for _ in 0..2 {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let embedding = runtime.block_on(embedder.embed("abc")).unwrap();
+            assert_eq!(embedding, vec![3.0]);
+            drop(runtime);
+        }
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_restarts_stopped_worker() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = BatchingEmbedder::new(inner.clone(), 10, Duration::from_millis(1));
+
+        // A worker that has stopped leaves a closed queue behind
+        let (sender, receiver) = mpsc::unbounded_channel();
+        drop(receiver);
+        *embedder.queue.lock().unwrap() = Some(sender);
+
+        assert_eq!(embedder.embed("abc").await.unwrap(), vec![3.0]);
+        assert!(!embedder.queue.lock().unwrap().as_ref().unwrap().is_closed());
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_documents_batched_separately() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = BatchingEmbedder::new(inner.clone(), 10, Duration::from_millis(50));
+
+        let (plain, document, other) = tokio::join!(
+            embedder.embed("a"),
+            embedder.embed_document("bb"),
+            embedder.embed_document("ccc"),
+        );
+
+        assert_eq!(plain.unwrap(), vec![1.0]);
+        assert_eq!(document.unwrap(), vec![-2.0]);
+        assert_eq!(other.unwrap(), vec![-3.0]);
+        assert_eq!(inner.batches.load(Ordering::SeqCst), 2);
+    }
+}
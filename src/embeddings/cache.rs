@@ -1,7 +1,10 @@
 //! Embedding cache implementation
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use crate::error::{Error, Result};
 
 /// LRU cache for embeddings
 pub struct EmbeddingCache {
@@ -11,6 +14,18 @@ pub struct EmbeddingCache {
     access_order: Vec<String>,
     /// Maximum cache size
     max_size: usize,
+    /// Number of lookups served from the cache
+    hits: u64,
+    /// Number of lookups not found in the cache
+    misses: u64,
+    /// File the cache is saved to on drop
+    persist_path: Option<PathBuf>,
+}
+
+/// On-disk representation of the cache, least recently used first
+#[derive(Serialize, Deserialize)]
+struct CacheSnapshot {
+    entries: Vec<(String, Vec<f32>)>,
 }
 
 impl EmbeddingCache {
@@ -20,9 +35,57 @@ impl EmbeddingCache {
             cache: HashMap::new(),
             access_order: Vec::new(),
             max_size,
+            hits: 0,
+            misses: 0,
+            persist_path: None,
         }
     }
 
+    /// Create a cache backed by a file
+    ///
+    /// Entries are loaded from `path` if it exists and saved back when the
+    /// cache is dropped.
+    pub fn with_file(path: impl AsRef<Path>, max_size: usize) -> Result<Self> {
+        let path = path.as_ref();
+        let mut cache = if path.exists() {
+            Self::load(path, max_size)?
+        } else {
+            Self::new(max_size)
+        };
+        cache.persist_path = Some(path.to_path_buf());
+        Ok(cache)
+    }
+
+    /// Load a cache saved with `save`
+    ///
+    /// When the file holds more than `max_size` entries, the most recently
+    /// used ones are kept.
+    pub fn load(path: impl AsRef<Path>, max_size: usize) -> Result<Self> {
+        let json = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| Error::internal(format!("Failed to read embedding cache: {}", e)))?;
+        let snapshot: CacheSnapshot = serde_json::from_str(&json)?;
+
+        let mut cache = Self::new(max_size);
+        for (hash, embedding) in snapshot.entries {
+            cache.insert_hashed(hash, embedding);
+        }
+        Ok(cache)
+    }
+
+    /// Save the cache to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let snapshot = CacheSnapshot {
+            entries: self
+                .access_order
+                .iter()
+                .filter_map(|hash| self.cache.get(hash).map(|e| (hash.clone(), e.clone())))
+                .collect(),
+        };
+        let json = serde_json::to_string(&snapshot)?;
+        std::fs::write(path.as_ref(), json)
+            .map_err(|e| Error::internal(format!("Failed to write embedding cache: {}", e)))
+    }
+
     /// Compute hash of text
     fn compute_hash(text: &str) -> String {
         let mut hasher = Sha256::new();
@@ -39,16 +102,22 @@ impl EmbeddingCache {
             self.access_order.retain(|h| h != &hash);
             self.access_order.push(hash.clone());
 
+            self.hits += 1;
             return Some(embedding.clone());
         }
 
+        self.misses += 1;
         None
     }
 
     /// Put embedding in cache
     pub fn put(&mut self, text: &str, embedding: Vec<f32>) {
         let hash = Self::compute_hash(text);
+        self.insert_hashed(hash, embedding);
+    }
 
+    /// Insert an embedding under a precomputed hash
+    fn insert_hashed(&mut self, hash: String, embedding: Vec<f32>) {
         // If cache is full, remove least recently used
         if self.cache.len() >= self.max_size && !self.cache.contains_key(&hash) {
             if let Some(lru_hash) = self.access_order.first() {
@@ -89,6 +158,27 @@ impl EmbeddingCache {
         let hash = Self::compute_hash(text);
         self.cache.contains_key(&hash)
     }
+
+    /// Get number of cache hits
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Get number of cache misses
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+impl Drop for EmbeddingCache {
+    fn drop(&mut self) {
+        let Some(path) = &self.persist_path else {
+            return;
+        };
+        if let Err(e) = self.save(path) {
+            tracing::warn!("Failed to persist embedding cache: {}", e);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +229,38 @@ mod tests {
         assert!(!cache.contains("text2"));
     }
 
+    #[test]
+    fn test_save_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("embedding_cache_{}.json", uuid::Uuid::new_v4()));
+
+        let mut cache = EmbeddingCache::new(10);
+        cache.put("test", vec![0.1, 0.2, 0.3]);
+        cache.save(&path).unwrap();
+
+        let mut loaded = EmbeddingCache::load(&path, 10).unwrap();
+        assert_eq!(loaded.get("test"), Some(vec![0.1, 0.2, 0.3]));
+        assert_eq!(loaded.hits(), 1);
+        assert_eq!(loaded.misses(), 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_backed_cache_persists_on_drop() {
+        let path = std::env::temp_dir().join(format!("embedding_cache_{}.json", uuid::Uuid::new_v4()));
+
+        {
+            let mut cache = EmbeddingCache::with_file(&path, 10).unwrap();
+            cache.put("test", vec![0.5]);
+        }
+
+        let mut reopened = EmbeddingCache::with_file(&path, 10).unwrap();
+        assert_eq!(reopened.get("test"), Some(vec![0.5]));
+        reopened.persist_path = None;
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn test_cache_hit_rate() {
         let mut cache = EmbeddingCache::new(10);
@@ -0,0 +1,265 @@
+//! Embedder decorator that caches embeddings by text
+
+use async_trait::async_trait;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use crate::error::{Error, Result};
+use super::{EmbedderBase, EmbeddingCache};
+
+/// Default number of cached embeddings
+pub const DEFAULT_CACHE_SIZE: usize = 10_000;
+
+/// Embedding method a cache entry was computed with
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Plain,
+    Query,
+    Document,
+}
+
+impl Mode {
+    /// Cache key of `text` embedded in this mode
+    ///
+    /// Plain keys are the bare text, so existing cache files stay valid.
+    fn key(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Mode::Plain => Cow::Borrowed(text),
+            Mode::Query => Cow::Owned(format!("query\0{}", text)),
+            Mode::Document => Cow::Owned(format!("document\0{}", text)),
+        }
+    }
+}
+
+/// Embedder that serves repeated texts from an LRU cache
+///
+/// Entries are keyed by text and embedding mode, so a query and a
+/// document with the same text never share an embedding.
+pub struct CachingEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    cache: Mutex<EmbeddingCache>,
+}
+
+impl CachingEmbedder {
+    /// Create a caching embedder with the default cache size
+    pub fn new(inner: Arc<dyn EmbedderBase>) -> Self {
+        Self::with_cache(inner, EmbeddingCache::new(DEFAULT_CACHE_SIZE))
+    }
+
+    /// Create a caching embedder around an existing cache
+    pub fn with_cache(inner: Arc<dyn EmbedderBase>, cache: EmbeddingCache) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(cache),
+        }
+    }
+
+    /// Number of cache hits so far
+    pub fn hits(&self) -> u64 {
+        self.cache.lock().unwrap().hits()
+    }
+
+    /// Number of cache misses so far
+    pub fn misses(&self) -> u64 {
+        self.cache.lock().unwrap().misses()
+    }
+
+    /// Embed one text in `mode`, serving it from the cache when possible
+    async fn embed_cached(&self, mode: Mode, text: &str) -> Result<Vec<f32>> {
+        let key = mode.key(text);
+        if let Some(embedding) = self.cache.lock().unwrap().get(&key) {
+            return Ok(embedding);
+        }
+
+        let embedding = match mode {
+            Mode::Plain => self.inner.embed(text).await?,
+            Mode::Query => self.inner.embed_query(text).await?,
+            Mode::Document => self.inner.embed_document(text).await?,
+        };
+        self.cache.lock().unwrap().put(&key, embedding.clone());
+        Ok(embedding)
+    }
+
+    /// Embed texts in `mode`, sending only the cache misses to the inner embedder
+    ///
+    /// Document mode batches through `embed_documents`, every other mode
+    /// through `embed_batch`.
+    async fn embed_batch_cached(&self, mode: Mode, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let keys: Vec<Cow<'_, str>> = texts.iter().map(|text| mode.key(text)).collect();
+        let cached: Vec<Option<Vec<f32>>> = {
+            let mut cache = self.cache.lock().unwrap();
+            keys.iter().map(|key| cache.get(key)).collect()
+        };
+
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| cached[i].is_none()).collect();
+        let missing_texts: Vec<&str> = missing.iter().map(|&i| texts[i]).collect();
+        let computed = if missing.is_empty() {
+            Vec::new()
+        } else if matches!(mode, Mode::Document) {
+            self.inner.embed_documents(missing_texts).await?
+        } else {
+            self.inner.embed_batch(missing_texts).await?
+        };
+        if computed.len() != missing.len() {
+            return Err(Error::embedding(format!(
+                "Batch returned {} embeddings for {} texts",
+                computed.len(),
+                missing.len()
+            )));
+        }
+        let mut computed = computed.into_iter();
+
+        {
+            let mut cache = self.cache.lock().unwrap();
+            for (&i, embedding) in missing.iter().zip(computed.as_slice()) {
+                cache.put(&keys[i], embedding.clone());
+            }
+        }
+
+        // Every miss has exactly one computed embedding, in order
+        Ok(cached
+            .into_iter()
+            .flat_map(|c| c.or_else(|| computed.next()))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for CachingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_cached(Mode::Plain, text).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch_cached(Mode::Plain, texts).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_cached(Mode::Query, text).await
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_cached(Mode::Document, text).await
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch_cached(Mode::Document, texts).await
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embedder that counts the texts it embeds
+    #[derive(Default)]
+    struct CountingEmbedder {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for CountingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![text.len() as f32])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warmup_populates_cache() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = CachingEmbedder::new(inner.clone());
+
+        embedder.warmup(&["coffee", "tea"]).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        assert_eq!(embedder.embed("coffee").await.unwrap(), vec![6.0]);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(embedder.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_embeds_only_misses() {
+        let inner = Arc::new(CountingEmbedder::default());
+        let embedder = CachingEmbedder::new(inner.clone());
+
+        embedder.embed("tea").await.unwrap();
+        let embeddings = embedder.embed_batch(vec!["coffee", "tea", "milk"]).await.unwrap();
+
+        assert_eq!(embeddings, vec![vec![6.0], vec![3.0], vec![4.0]]);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    /// Embedder whose query and document embeddings differ
+    struct AsymmetricEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for AsymmetricEmbedder {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![0.0])
+        }
+
+        async fn embed_query(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![1.0])
+        }
+
+        async fn embed_document(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![2.0])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    /// Embedder whose batches come back one embedding short
+    struct ShortBatchEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for ShortBatchEmbedder {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![0.0])
+        }
+
+        async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+            Ok(vec![vec![0.0]; texts.len().saturating_sub(1)])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_short_inner_batch_is_an_error() {
+        let embedder = CachingEmbedder::new(Arc::new(ShortBatchEmbedder));
+
+        let result = embedder.embed_batch(vec!["coffee", "tea"]).await;
+        assert!(matches!(result, Err(Error::EmbeddingError(_))));
+        assert_eq!(embedder.embed("coffee").await.unwrap(), vec![0.0]);
+        assert_eq!(embedder.hits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_keyed_by_mode() {
+        let embedder = CachingEmbedder::new(Arc::new(AsymmetricEmbedder));
+
+        assert_eq!(embedder.embed_document("tea").await.unwrap(), vec![2.0]);
+        assert_eq!(embedder.embed_query("tea").await.unwrap(), vec![1.0]);
+        assert_eq!(embedder.embed("tea").await.unwrap(), vec![0.0]);
+        assert_eq!(embedder.embed_documents(vec!["tea"]).await.unwrap(), vec![vec![2.0]]);
+        assert_eq!(embedder.hits(), 1);
+    }
+}
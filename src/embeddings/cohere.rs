@@ -0,0 +1,222 @@
+//! Cohere embedder implementation
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::context::OperationContext;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+const COHERE_EMBED_URL: &str = "https://api.cohere.ai/v1/embed";
+
+/// Cohere input type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CohereInputType {
+    /// Texts being stored
+    #[default]
+    SearchDocument,
+    /// Texts used as search queries
+    SearchQuery,
+}
+
+/// Embedder using the Cohere embed API
+pub struct CohereEmbedder {
+    api_key: String,
+    model: String,
+    input_type: CohereInputType,
+    dimension: usize,
+    client: Client,
+}
+
+impl CohereEmbedder {
+    /// Create a new Cohere embedder
+    pub fn new(
+        api_key: String,
+        model: String,
+        input_type: CohereInputType,
+        dimension: usize,
+    ) -> Self {
+        Self {
+            api_key,
+            model,
+            input_type,
+            dimension,
+            client: Client::new(),
+        }
+    }
+
+    /// Create with default settings (`embed-english-v3.0`, documents)
+    pub fn with_defaults(api_key: String) -> Self {
+        Self::new(
+            api_key,
+            "embed-english-v3.0".to_string(),
+            CohereInputType::SearchDocument,
+            1024,
+        )
+    }
+
+    /// Get the configured input type
+    pub fn input_type(&self) -> CohereInputType {
+        self.input_type
+    }
+
+    /// Build the request body for the embed endpoint
+    fn request_body(&self, texts: &[&str], input_type: CohereInputType) -> serde_json::Value {
+        json!({
+            "texts": texts,
+            "model": self.model,
+            "input_type": input_type,
+        })
+    }
+
+    async fn request(&self, texts: Vec<&str>, input_type: CohereInputType) -> Result<Vec<Vec<f32>>> {
+        let body = self.request_body(&texts, input_type);
+        let expected = texts.len();
+
+        let response = self
+            .client
+            .post(COHERE_EMBED_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .headers(OperationContext::current_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::embedding(format!("Request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(Error::embedding(format!(
+                "API error: {}",
+                response.status()
+            )));
+        }
+
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::embedding(format!("Failed to parse response: {}", e)))?;
+
+        parse_embeddings(&result, expected)
+    }
+}
+
+/// Parse `embeddings`, one per input text
+///
+/// A malformed embedding or a count other than `expected` is an error
+/// rather than a silently shorter batch.
+fn parse_embeddings(result: &serde_json::Value, expected: usize) -> Result<Vec<Vec<f32>>> {
+    let embeddings = result
+        .get("embeddings")
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| Error::embedding("Invalid response format"))?
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            item.as_array()
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_f64().map(|v| v as f32))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| Error::embedding(format!("Malformed embedding at index {}", i)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if embeddings.len() != expected {
+        return Err(Error::embedding(format!(
+            "Response returned {} embeddings for {} texts",
+            embeddings.len(),
+            expected
+        )));
+    }
+    Ok(embeddings)
+}
+
+#[async_trait]
+impl EmbedderBase for CohereEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.request(vec![text], self.input_type)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::embedding("Invalid response format"))
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.request(texts, self.input_type).await
+    }
+
+    /// Embed as `search_query` regardless of the configured input type
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.request(vec![text], CohereInputType::SearchQuery)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::embedding("Invalid response format"))
+    }
+
+    /// Embed as `search_document` regardless of the configured input type
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.request(vec![text], CohereInputType::SearchDocument)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::embedding("Invalid response format"))
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.request(texts, CohereInputType::SearchDocument).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedder_creation() {
+        let embedder = CohereEmbedder::with_defaults("test-key".to_string());
+
+        assert_eq!(embedder.dimension(), 1024);
+        assert_eq!(embedder.input_type(), CohereInputType::SearchDocument);
+    }
+
+    #[test]
+    fn test_request_body_input_types() {
+        let embedder = CohereEmbedder::with_defaults("test-key".to_string());
+
+        let body = embedder.request_body(&["I like tea"], CohereInputType::SearchDocument);
+        assert_eq!(
+            body,
+            json!({
+                "texts": ["I like tea"],
+                "model": "embed-english-v3.0",
+                "input_type": "search_document",
+            })
+        );
+
+        let body = embedder.request_body(&["tea", "coffee"], CohereInputType::SearchQuery);
+        assert_eq!(body["input_type"], "search_query");
+        assert_eq!(body["texts"], json!(["tea", "coffee"]));
+    }
+
+    #[test]
+    fn test_parse_embeddings_rejects_malformed() {
+        let response = json!({ "embeddings": [[0.1, 0.2], [0.3, 0.4]] });
+        assert_eq!(
+            parse_embeddings(&response, 2).unwrap(),
+            vec![vec![0.1, 0.2], vec![0.3, 0.4]]
+        );
+
+        assert!(matches!(parse_embeddings(&response, 3), Err(Error::EmbeddingError(_))));
+        for malformed in [json!({ "embeddings": [[0.1], "oops"] }), json!({ "embeddings": [[0.1], [null]] })] {
+            assert!(matches!(parse_embeddings(&malformed, 2), Err(Error::EmbeddingError(_))));
+        }
+    }
+}
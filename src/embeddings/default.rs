@@ -3,6 +3,7 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::json;
+use crate::context::OperationContext;
 use crate::error::{Error, Result};
 use super::EmbedderBase;
 
@@ -56,6 +57,7 @@ impl EmbedderBase for DefaultEmbedder {
             .client
             .post("https://api.watsonx.ai/v1/embeddings")
             .header("Authorization", format!("Bearer {}", self.api_key))
+            .headers(OperationContext::current_headers())
             .json(&body)
             .send()
             .await
@@ -98,6 +100,7 @@ impl EmbedderBase for DefaultEmbedder {
             .client
             .post("https://api.watsonx.ai/v1/embeddings")
             .header("Authorization", format!("Bearer {}", self.api_key))
+            .headers(OperationContext::current_headers())
             .json(&body)
             .send()
             .await
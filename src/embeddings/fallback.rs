@@ -0,0 +1,189 @@
+//! Fallback embedder that tries a chain of embedders in order
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+/// Embedding method retried down the chain
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Plain,
+    Query,
+    Document,
+}
+
+/// Embedder that falls back to secondary embedders when the primary fails
+pub struct FallbackEmbedder {
+    primary: Arc<dyn EmbedderBase>,
+    fallbacks: Vec<Arc<dyn EmbedderBase>>,
+}
+
+impl FallbackEmbedder {
+    /// Create a new fallback embedder
+    ///
+    /// Returns a configuration error if the embedders report different dimensions.
+    pub fn new(
+        primary: Arc<dyn EmbedderBase>,
+        fallbacks: Vec<Arc<dyn EmbedderBase>>,
+    ) -> Result<Self> {
+        let dimension = primary.dimension();
+        if let Some(mismatch) = fallbacks.iter().find(|f| f.dimension() != dimension) {
+            return Err(Error::config(format!(
+                "Fallback embedder dimension {} does not match primary dimension {}",
+                mismatch.dimension(),
+                dimension
+            )));
+        }
+
+        Ok(Self { primary, fallbacks })
+    }
+
+    /// Iterate over the primary and fallback embedders in order
+    fn chain(&self) -> impl Iterator<Item = &Arc<dyn EmbedderBase>> {
+        std::iter::once(&self.primary).chain(self.fallbacks.iter())
+    }
+
+    /// Embed one text in `mode` with the first embedder that succeeds
+    async fn embed_one(&self, mode: Mode, text: &str) -> Result<Vec<f32>> {
+        let mut last_error = None;
+        for embedder in self.chain() {
+            let result = match mode {
+                Mode::Plain => embedder.embed(text).await,
+                Mode::Query => embedder.embed_query(text).await,
+                Mode::Document => embedder.embed_document(text).await,
+            };
+            match result {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) => {
+                    tracing::warn!("Embedder failed, trying next fallback: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::embedding("No embedders configured")))
+    }
+
+    /// Embed texts in `mode` with the first embedder that succeeds
+    ///
+    /// Document mode batches through `embed_documents`, every other mode
+    /// through `embed_batch`.
+    async fn embed_many(&self, mode: Mode, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let mut last_error = None;
+        for embedder in self.chain() {
+            let result = match mode {
+                Mode::Document => embedder.embed_documents(texts.clone()).await,
+                Mode::Plain | Mode::Query => embedder.embed_batch(texts.clone()).await,
+            };
+            match result {
+                Ok(embeddings) => return Ok(embeddings),
+                Err(e) => {
+                    tracing::warn!("Embedder failed, trying next fallback: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::embedding("No embedders configured")))
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for FallbackEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_one(Mode::Plain, text).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed_many(Mode::Plain, texts).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_one(Mode::Query, text).await
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_one(Mode::Document, text).await
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed_many(Mode::Document, texts).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.primary.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::LocalEmbedder;
+
+    struct FailingEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for FailingEmbedder {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            Err(Error::embedding("primary unavailable"))
+        }
+
+        fn dimension(&self) -> usize {
+            384
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_secondary() {
+        let embedder = FallbackEmbedder::new(
+            Arc::new(FailingEmbedder),
+            vec![Arc::new(LocalEmbedder::with_defaults())],
+        )
+        .unwrap();
+
+        let embedding = embedder.embed("hello").await.unwrap();
+        assert_eq!(embedding.len(), 384);
+
+        let embeddings = embedder.embed_batch(vec!["a", "b"]).await.unwrap();
+        assert_eq!(embeddings.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_in_query_and_document_modes() {
+        let local = Arc::new(LocalEmbedder::with_defaults());
+        let prefixing = crate::embeddings::PrefixingEmbedder::new(local.clone())
+            .with_query_prefix("search_query: ")
+            .with_document_prefix("search_document: ");
+        let embedder =
+            FallbackEmbedder::new(Arc::new(FailingEmbedder), vec![Arc::new(prefixing)]).unwrap();
+
+        assert_eq!(
+            embedder.embed_query("coffee").await.unwrap(),
+            local.embed("search_query: coffee").await.unwrap()
+        );
+        assert_eq!(
+            embedder.embed_documents(vec!["coffee"]).await.unwrap(),
+            vec![local.embed("search_document: coffee").await.unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_fail_returns_last_error() {
+        let embedder =
+            FallbackEmbedder::new(Arc::new(FailingEmbedder), vec![Arc::new(FailingEmbedder)])
+                .unwrap();
+
+        assert!(matches!(
+            embedder.embed("hello").await,
+            Err(Error::EmbeddingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        let result = FallbackEmbedder::new(
+            Arc::new(FailingEmbedder),
+            vec![Arc::new(LocalEmbedder::new(128))],
+        );
+        assert!(matches!(result, Err(Error::ConfigError(_))));
+    }
+}
@@ -1,15 +1,36 @@
 //! Embeddings abstraction and implementations
 
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use crate::Result;
 
 pub mod default;
+pub mod batching;
 pub mod cache;
+pub mod caching;
+pub mod cohere;
+pub mod fallback;
 pub mod local;
+pub mod openai;
+pub mod prefixing;
+pub mod projecting;
+pub mod registry;
+pub mod timeout;
+pub mod truncating;
 
 pub use default::DefaultEmbedder;
+pub use batching::BatchingEmbedder;
 pub use cache::EmbeddingCache;
+pub use caching::CachingEmbedder;
+pub use cohere::{CohereEmbedder, CohereInputType};
+pub use fallback::FallbackEmbedder;
 pub use local::LocalEmbedder;
+pub use openai::OpenAIEmbedder;
+pub use prefixing::PrefixingEmbedder;
+pub use projecting::ProjectingEmbedder;
+pub use registry::{ModelInfo, ModelRegistry};
+pub use timeout::TimeoutEmbedder;
+pub use truncating::{TruncatingEmbedder, TruncationStrategy};
 
 /// Base trait for embedding implementations
 #[async_trait]
@@ -18,14 +39,115 @@ pub trait EmbedderBase: Send + Sync {
     async fn embed(&self, text: &str) -> Result<Vec<f32>>;
 
     /// Generate embeddings for multiple texts
+    ///
+    /// The default implementation runs up to `max_batch_concurrency` `embed`
+    /// calls concurrently and returns embeddings in input order.
     async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
-        let mut embeddings = Vec::new();
-        for text in texts {
-            embeddings.push(self.embed(text).await?);
+        // Owned texts keep the stream's futures free of borrowed lifetimes
+        let texts: Vec<String> = texts.into_iter().map(str::to_string).collect();
+        let mut indexed: Vec<(usize, Vec<f32>)> = stream::iter(texts.into_iter().enumerate())
+            .map(|(i, text)| async move { self.embed(&text).await.map(|e| (i, e)) })
+            .buffer_unordered(self.max_batch_concurrency().max(1))
+            .try_collect()
+            .await?;
+
+        indexed.sort_by_key(|(i, _)| *i);
+        Ok(indexed.into_iter().map(|(_, e)| e).collect())
+    }
+
+    /// Generate the embedding of a search query
+    ///
+    /// Models that embed queries and documents differently override this
+    /// and the document methods. The default is `embed`.
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(text).await
+    }
+
+    /// Generate the embedding of a document to be stored
+    ///
+    /// The default is `embed`.
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(text).await
+    }
+
+    /// Generate embeddings for multiple documents to be stored
+    ///
+    /// The default is `embed_batch`.
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch(texts).await
+    }
+
+    /// Prepare the embedder for the given texts before serving queries
+    ///
+    /// The default embeds the texts and discards the results, which fills
+    /// caches and triggers any lazy setup. Override for embedders that need
+    /// a different warmup.
+    async fn warmup(&self, texts: &[&str]) -> Result<()> {
+        if texts.is_empty() {
+            return Ok(());
         }
-        Ok(embeddings)
+        self.embed_batch(texts.to_vec()).await.map(|_| ())
+    }
+
+    /// Maximum concurrent `embed` calls used by the default `embed_batch`
+    fn max_batch_concurrency(&self) -> usize {
+        8
     }
 
     /// Get embedding dimension
     fn dimension(&self) -> usize;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Embedder that records how many `embed` calls overlap
+    struct SlowEmbedder {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for SlowEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+            // Later texts finish first to exercise reordering
+            tokio::time::sleep(Duration::from_millis(50 - text.len() as u64)).await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(vec![text.len() as f32])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+
+        fn max_batch_concurrency(&self) -> usize {
+            4
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_embed_batch_concurrent_and_ordered() {
+        let embedder = SlowEmbedder {
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        };
+
+        let embeddings = embedder
+            .embed_batch(vec!["a", "bb", "ccc", "dddd", "eeeee", "ffffff"])
+            .await
+            .unwrap();
+
+        let lengths: Vec<f32> = embeddings.into_iter().map(|e| e[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        let max = embedder.max_in_flight.load(Ordering::SeqCst);
+        assert!(max > 1 && max <= 4, "max in flight was {}", max);
+    }
+}
@@ -0,0 +1,207 @@
+//! OpenAI embedder implementation
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use crate::context::OperationContext;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+
+/// Embedder using the OpenAI embeddings API
+pub struct OpenAIEmbedder {
+    api_key: String,
+    model: String,
+    dimension: usize,
+    /// Requested output dimensions (v3 models only)
+    dimensions: Option<usize>,
+    base_url: String,
+    client: Client,
+}
+
+impl OpenAIEmbedder {
+    /// Create a new OpenAI embedder
+    pub fn new(api_key: String, model: String, dimension: usize) -> Self {
+        Self {
+            api_key,
+            model,
+            dimension,
+            dimensions: None,
+            base_url: OPENAI_API_BASE.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Create with default settings (`text-embedding-3-small`, 1536 dimensions)
+    pub fn with_defaults(api_key: String) -> Self {
+        Self::new(api_key, "text-embedding-3-small".to_string(), 1536)
+    }
+
+    /// Request shortened embeddings via the `dimensions` parameter
+    pub fn with_dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Send requests to an OpenAI-compatible API at `base_url`, e.g. `http://localhost:8080/v1`
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Build the request body for the embeddings endpoint
+    fn request_body(&self, input: &[&str]) -> serde_json::Value {
+        let mut body = json!({
+            "input": input,
+            "model": self.model,
+        });
+        if let Some(dimensions) = self.dimensions {
+            body["dimensions"] = json!(dimensions);
+        }
+        body
+    }
+
+    async fn request(&self, input: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let body = self.request_body(&input);
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .headers(OperationContext::current_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::embedding(format!("Request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(Error::embedding(format!(
+                "API error: {}",
+                response.status()
+            )));
+        }
+
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::embedding(format!("Failed to parse response: {}", e)))?;
+
+        parse_embeddings(&result, input.len())
+    }
+}
+
+/// Parse `data[].embedding`, ordered by each item's `index`
+///
+/// Fails unless there is exactly one well-formed embedding per input.
+fn parse_embeddings(result: &serde_json::Value, expected: usize) -> Result<Vec<Vec<f32>>> {
+    let data = result
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| Error::embedding("Invalid response format"))?;
+
+    let mut indexed = data
+        .iter()
+        .map(|item| {
+            let index = item
+                .get("index")
+                .and_then(|i| i.as_u64())
+                .ok_or_else(|| Error::embedding("Missing embedding index"))?;
+            let embedding = item
+                .get("embedding")
+                .and_then(|e| e.as_array())
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_f64().map(|v| v as f32))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| Error::embedding(format!("Malformed embedding at index {}", index)))?;
+            Ok((index, embedding))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if indexed.len() != expected {
+        return Err(Error::embedding(format!(
+            "Response returned {} embeddings for {} texts",
+            indexed.len(),
+            expected
+        )));
+    }
+    indexed.sort_by_key(|(i, _)| *i);
+    if indexed.iter().enumerate().any(|(position, (i, _))| *i != position as u64) {
+        return Err(Error::embedding("Embedding indices do not cover the inputs"));
+    }
+    Ok(indexed.into_iter().map(|(_, e)| e).collect())
+}
+
+#[async_trait]
+impl EmbedderBase for OpenAIEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.request(vec![text])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::embedding("Invalid response format"))
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.request(texts).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimensions.unwrap_or(self.dimension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedder_dimension() {
+        let embedder = OpenAIEmbedder::with_defaults("test-key".to_string());
+        assert_eq!(embedder.dimension(), 1536);
+        assert!(embedder.request_body(&["tea"]).get("dimensions").is_none());
+
+        let embedder = embedder.with_dimensions(256);
+        assert_eq!(embedder.dimension(), 256);
+        assert_eq!(embedder.request_body(&["tea"])["dimensions"], 256);
+    }
+
+    #[test]
+    fn test_parse_embeddings_ordered_by_index() {
+        let response = json!({
+            "data": [
+                { "index": 2, "embedding": [0.3] },
+                { "index": 0, "embedding": [0.1] },
+                { "index": 1, "embedding": [0.2] },
+            ]
+        });
+
+        let embeddings = parse_embeddings(&response, 3).unwrap();
+        assert_eq!(embeddings, vec![vec![0.1], vec![0.2], vec![0.3]]);
+    }
+
+    #[test]
+    fn test_parse_embeddings_rejects_malformed() {
+        let non_numeric = json!({
+            "data": [
+                { "index": 0, "embedding": [0.1, "x"] },
+                { "index": 1, "embedding": [0.2, 0.3] },
+            ]
+        });
+        assert!(matches!(parse_embeddings(&non_numeric, 2), Err(Error::EmbeddingError(_))));
+
+        let short = json!({ "data": [{ "index": 0, "embedding": [0.1] }] });
+        assert!(matches!(parse_embeddings(&short, 2), Err(Error::EmbeddingError(_))));
+
+        let duplicated = json!({
+            "data": [
+                { "index": 0, "embedding": [0.1] },
+                { "index": 0, "embedding": [0.2] },
+            ]
+        });
+        assert!(matches!(parse_embeddings(&duplicated, 2), Err(Error::EmbeddingError(_))));
+    }
+}
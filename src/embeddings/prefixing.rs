@@ -0,0 +1,123 @@
+//! Embedder decorator adding instruction prefixes to queries and documents
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use crate::error::Result;
+use super::EmbedderBase;
+
+/// Embedder that prepends mode-specific prefixes before embedding
+///
+/// Instruction-tuned models such as `nomic-embed-text` and E5 expect inputs
+/// like `"search_query: ..."` and `"search_document: ..."`; without them
+/// recall drops silently. `embed_query` and `embed_document` add the
+/// configured prefix, while plain `embed` passes text through unchanged.
+pub struct PrefixingEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    query_prefix: String,
+    document_prefix: String,
+}
+
+impl PrefixingEmbedder {
+    /// Create a prefixing embedder with empty prefixes
+    pub fn new(inner: Arc<dyn EmbedderBase>) -> Self {
+        Self {
+            inner,
+            query_prefix: String::new(),
+            document_prefix: String::new(),
+        }
+    }
+
+    /// Set the prefix prepended to search queries
+    pub fn with_query_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.query_prefix = prefix.into();
+        self
+    }
+
+    /// Set the prefix prepended to stored documents
+    pub fn with_document_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.document_prefix = prefix.into();
+        self
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for PrefixingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.inner.embed(text).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed_batch(texts).await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.inner
+            .embed_query(&format!("{}{}", self.query_prefix, text))
+            .await
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        self.inner
+            .embed_document(&format!("{}{}", self.document_prefix, text))
+            .await
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let prefixed: Vec<String> = texts
+            .iter()
+            .map(|text| format!("{}{}", self.document_prefix, text))
+            .collect();
+        self.inner
+            .embed_documents(prefixed.iter().map(String::as_str).collect())
+            .await
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Embedder that records the texts it receives
+    #[derive(Default)]
+    struct RecordingEmbedder {
+        received: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for RecordingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.received.lock().unwrap().push(text.to_string());
+            Ok(vec![0.0])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefixes_by_mode() {
+        let inner = Arc::new(RecordingEmbedder::default());
+        let embedder = PrefixingEmbedder::new(inner.clone())
+            .with_query_prefix("search_query: ")
+            .with_document_prefix("search_document: ");
+
+        embedder.embed_query("tea").await.unwrap();
+        embedder.embed_document("I like tea").await.unwrap();
+        embedder.embed("plain").await.unwrap();
+
+        assert_eq!(
+            inner.received.lock().unwrap().as_slice(),
+            &["search_query: tea", "search_document: I like tea", "plain"]
+        );
+    }
+}
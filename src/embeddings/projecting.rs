@@ -0,0 +1,184 @@
+//! Embedder decorator that reduces dimension by random projection
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+/// Default seed for the projection matrix
+pub const DEFAULT_PROJECTION_SEED: u64 = 0x5EED_1DEA;
+
+/// Embedder that projects the inner embedder's output to a lower dimension
+///
+/// Uses a fixed random matrix of `±1/sqrt(target)` entries derived from a
+/// seed, so the same seed always yields the same projection and pairwise
+/// cosine similarities are approximately preserved (Johnson–Lindenstrauss).
+pub struct ProjectingEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    target_dimension: usize,
+    /// Row-major `target_dimension x inner.dimension()` matrix
+    matrix: Vec<f32>,
+}
+
+impl ProjectingEmbedder {
+    /// Create a projecting embedder with the default seed
+    pub fn new(inner: Arc<dyn EmbedderBase>, target_dimension: usize) -> Self {
+        Self::with_seed(inner, target_dimension, DEFAULT_PROJECTION_SEED)
+    }
+
+    /// Create a projecting embedder with an explicit seed
+    pub fn with_seed(inner: Arc<dyn EmbedderBase>, target_dimension: usize, seed: u64) -> Self {
+        let source_dimension = inner.dimension();
+        let scale = 1.0 / (target_dimension.max(1) as f32).sqrt();
+
+        let mut state = seed;
+        let matrix = (0..target_dimension * source_dimension)
+            .map(|_| if splitmix64(&mut state) & 1 == 0 { scale } else { -scale })
+            .collect();
+
+        Self {
+            inner,
+            target_dimension,
+            matrix,
+        }
+    }
+
+    /// Project a full-dimension embedding
+    fn project(&self, embedding: &[f32]) -> Result<Vec<f32>> {
+        let source_dimension = self.inner.dimension();
+        if embedding.len() != source_dimension {
+            return Err(Error::embedding(format!(
+                "Cannot project embedding of dim {}, expected {}",
+                embedding.len(),
+                source_dimension
+            )));
+        }
+
+        Ok(self
+            .matrix
+            .chunks(source_dimension.max(1))
+            .take(self.target_dimension)
+            .map(|row| row.iter().zip(embedding).map(|(m, v)| m * v).sum())
+            .collect())
+    }
+}
+
+/// Advance a SplitMix64 generator
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[async_trait]
+impl EmbedderBase for ProjectingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let embedding = self.inner.embed(text).await?;
+        self.project(&embedding)
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.inner
+            .embed_batch(texts)
+            .await?
+            .iter()
+            .map(|e| self.project(e))
+            .collect()
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        let embedding = self.inner.embed_query(text).await?;
+        self.project(&embedding)
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        let embedding = self.inner.embed_document(text).await?;
+        self.project(&embedding)
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.inner
+            .embed_documents(texts)
+            .await?
+            .iter()
+            .map(|e| self.project(e))
+            .collect()
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.target_dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::LocalEmbedder;
+    use crate::utils::cosine_similarity;
+
+    /// Uniform value in `[-1, 1)` from the generator
+    fn uniform(state: &mut u64) -> f32 {
+        (splitmix64(state) >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+
+    #[test]
+    fn test_preserves_cosine_similarity() {
+        let embedder = ProjectingEmbedder::new(Arc::new(LocalEmbedder::with_defaults()), 256);
+        let source_dimension = LocalEmbedder::with_defaults().dimension();
+
+        // Pairs of a random base vector with varying amounts of noise
+        let mut state = 42;
+        for noise in [0.0, 0.25, 0.5, 1.0, 2.0, 4.0] {
+            let a: Vec<f32> = (0..source_dimension).map(|_| uniform(&mut state)).collect();
+            let b: Vec<f32> = a.iter().map(|x| x + noise * uniform(&mut state)).collect();
+
+            let original = cosine_similarity(&a, &b);
+            let projected = cosine_similarity(
+                &embedder.project(&a).unwrap(),
+                &embedder.project(&b).unwrap(),
+            );
+            assert!(
+                (original - projected).abs() < 0.2,
+                "noise {}: {} vs {}",
+                noise,
+                original,
+                projected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reduced_dimension_is_deterministic() {
+        let inner = Arc::new(LocalEmbedder::with_defaults());
+        let a = ProjectingEmbedder::new(inner.clone(), 64);
+        let b = ProjectingEmbedder::new(inner.clone(), 64);
+
+        assert_eq!(a.dimension(), 64);
+        let embedding = a.embed("I like coffee").await.unwrap();
+        assert_eq!(embedding.len(), 64);
+        assert_eq!(embedding, b.embed("I like coffee").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_forwards_query_and_document_modes() {
+        let local = Arc::new(LocalEmbedder::with_defaults());
+        let prefixing = crate::embeddings::PrefixingEmbedder::new(local.clone())
+            .with_query_prefix("search_query: ")
+            .with_document_prefix("search_document: ");
+        let embedder = ProjectingEmbedder::new(Arc::new(prefixing), 64);
+
+        let query = local.embed("search_query: coffee").await.unwrap();
+        let document = local.embed("search_document: coffee").await.unwrap();
+        assert_eq!(embedder.embed_query("coffee").await.unwrap(), embedder.project(&query).unwrap());
+        assert_eq!(
+            embedder.embed_documents(vec!["coffee"]).await.unwrap(),
+            vec![embedder.project(&document).unwrap()]
+        );
+    }
+}
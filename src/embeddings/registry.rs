@@ -0,0 +1,91 @@
+//! Known embedding models and their dimensions
+
+/// An embedding model with a known output dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// Model name as passed to the provider
+    pub name: &'static str,
+    /// Provider serving the model
+    pub provider: &'static str,
+    /// Embedding dimension
+    pub dimension: usize,
+}
+
+/// Known models; names are matched case-insensitively
+const KNOWN_MODELS: &[ModelInfo] = &[
+    ModelInfo { name: "text-embedding-3-small", provider: "openai", dimension: 1536 },
+    ModelInfo { name: "text-embedding-3-large", provider: "openai", dimension: 3072 },
+    ModelInfo { name: "text-embedding-ada-002", provider: "openai", dimension: 1536 },
+    ModelInfo { name: "embed-english-v3.0", provider: "cohere", dimension: 1024 },
+    ModelInfo { name: "embed-multilingual-v3.0", provider: "cohere", dimension: 1024 },
+    ModelInfo { name: "embed-english-light-v3.0", provider: "cohere", dimension: 384 },
+    ModelInfo { name: "embed-multilingual-light-v3.0", provider: "cohere", dimension: 384 },
+    ModelInfo { name: "ibm/slate-30m-english-rtrvr", provider: "watsonx", dimension: 384 },
+    ModelInfo { name: "ibm/slate-125m-english-rtrvr", provider: "watsonx", dimension: 768 },
+    ModelInfo { name: "ibm/granite-embedding-107m-multilingual", provider: "watsonx", dimension: 384 },
+    ModelInfo { name: "ibm/granite-embedding-278m-multilingual", provider: "watsonx", dimension: 768 },
+    ModelInfo { name: "all-minilm-l6-v2", provider: "sentence-transformers", dimension: 384 },
+    ModelInfo { name: "all-minilm-l12-v2", provider: "sentence-transformers", dimension: 384 },
+    ModelInfo { name: "all-mpnet-base-v2", provider: "sentence-transformers", dimension: 768 },
+    ModelInfo { name: "bge-small-en-v1.5", provider: "baai", dimension: 384 },
+    ModelInfo { name: "bge-base-en-v1.5", provider: "baai", dimension: 768 },
+    ModelInfo { name: "bge-large-en-v1.5", provider: "baai", dimension: 1024 },
+    ModelInfo { name: "nomic-embed-text", provider: "nomic", dimension: 768 },
+];
+
+/// Lookup of known embedding models
+pub struct ModelRegistry;
+
+impl ModelRegistry {
+    /// Find a known model by name
+    ///
+    /// Names match case-insensitively, either in full or after an
+    /// organization prefix such as `sentence-transformers/`.
+    pub fn lookup(model: &str) -> Option<&'static ModelInfo> {
+        let model = model.trim();
+        KNOWN_MODELS
+            .iter()
+            .find(|info| info.name.eq_ignore_ascii_case(model))
+            .or_else(|| {
+                let (_, bare) = model.rsplit_once('/')?;
+                KNOWN_MODELS
+                    .iter()
+                    .find(|info| info.name.eq_ignore_ascii_case(bare))
+            })
+    }
+
+    /// Dimension of a known model
+    pub fn dimension_of(model: &str) -> Option<usize> {
+        Self::lookup(model).map(|info| info.dimension)
+    }
+
+    /// All known models
+    pub fn models() -> &'static [ModelInfo] {
+        KNOWN_MODELS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_dimensions() {
+        assert_eq!(ModelRegistry::dimension_of("text-embedding-3-small"), Some(1536));
+        assert_eq!(ModelRegistry::dimension_of("text-embedding-3-large"), Some(3072));
+        assert_eq!(ModelRegistry::dimension_of("embed-english-v3.0"), Some(1024));
+        assert_eq!(ModelRegistry::dimension_of("ibm/slate-125m-english-rtrvr"), Some(768));
+        assert_eq!(
+            ModelRegistry::dimension_of("sentence-transformers/all-MiniLM-L6-v2"),
+            Some(384)
+        );
+        assert_eq!(ModelRegistry::lookup("All-MiniLM-L6-v2").unwrap().provider, "sentence-transformers");
+    }
+
+    #[test]
+    fn test_unknown_model() {
+        assert_eq!(ModelRegistry::dimension_of("my-custom-model"), None);
+        assert_eq!(ModelRegistry::dimension_of("org/my-custom-model"), None);
+        assert_eq!(ModelRegistry::dimension_of(""), None);
+    }
+}
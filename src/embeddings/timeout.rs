@@ -0,0 +1,101 @@
+//! Embedder decorator bounding the duration of each call
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+/// Embedder that fails calls exceeding a timeout
+pub struct TimeoutEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    timeout: Duration,
+}
+
+impl TimeoutEmbedder {
+    /// Wrap an embedder, bounding each `embed` and `embed_batch` call by `timeout`
+    pub fn new(inner: Arc<dyn EmbedderBase>, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for TimeoutEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        tokio::time::timeout(self.timeout, self.inner.embed(text))
+            .await
+            .map_err(|_| Error::timeout(format!("embedding timed out after {:?}", self.timeout)))?
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        tokio::time::timeout(self.timeout, self.inner.embed_batch(texts))
+            .await
+            .map_err(|_| Error::timeout(format!("embedding timed out after {:?}", self.timeout)))?
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        tokio::time::timeout(self.timeout, self.inner.embed_query(text))
+            .await
+            .map_err(|_| Error::timeout(format!("embedding timed out after {:?}", self.timeout)))?
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        tokio::time::timeout(self.timeout, self.inner.embed_document(text))
+            .await
+            .map_err(|_| Error::timeout(format!("embedding timed out after {:?}", self.timeout)))?
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        tokio::time::timeout(self.timeout, self.inner.embed_documents(texts))
+            .await
+            .map_err(|_| Error::timeout(format!("embedding timed out after {:?}", self.timeout)))?
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::LocalEmbedder;
+
+    struct SlowEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for SlowEmbedder {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(vec![0.0; 4])
+        }
+
+        fn dimension(&self) -> usize {
+            4
+        }
+    }
+
+    #[tokio::test]
+    async fn test_times_out_slow_calls() {
+        let embedder = TimeoutEmbedder::new(Arc::new(SlowEmbedder), Duration::from_millis(20));
+        assert!(matches!(embedder.embed("hello").await, Err(Error::Timeout(_))));
+        assert!(matches!(
+            embedder.embed_batch(vec!["a", "b"]).await,
+            Err(Error::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_passes_fast_calls_through() {
+        let embedder = TimeoutEmbedder::new(
+            Arc::new(LocalEmbedder::with_defaults()),
+            Duration::from_secs(5),
+        );
+        assert_eq!(embedder.embed("hello").await.unwrap().len(), 384);
+        assert_eq!(embedder.dimension(), 384);
+    }
+}
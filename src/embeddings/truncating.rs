@@ -0,0 +1,190 @@
+//! Embedder decorator that bounds input length
+
+use async_trait::async_trait;
+use std::borrow::Cow;
+use std::sync::Arc;
+use crate::error::{Error, Result};
+use super::EmbedderBase;
+
+/// How to handle inputs longer than the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// Keep the first `max_input_chars` characters
+    #[default]
+    Head,
+    /// Keep the last `max_input_chars` characters
+    Tail,
+    /// Reject the input with an invalid argument error
+    Error,
+}
+
+/// Embedder that truncates or rejects over-long inputs before embedding
+pub struct TruncatingEmbedder {
+    inner: Arc<dyn EmbedderBase>,
+    max_input_chars: Option<usize>,
+    strategy: TruncationStrategy,
+}
+
+impl TruncatingEmbedder {
+    /// Create a new truncating embedder without a limit
+    pub fn new(inner: Arc<dyn EmbedderBase>) -> Self {
+        Self {
+            inner,
+            max_input_chars: None,
+            strategy: TruncationStrategy::default(),
+        }
+    }
+
+    /// Set the maximum input length in characters
+    pub fn with_max_input_chars(mut self, max_input_chars: usize) -> Self {
+        self.max_input_chars = Some(max_input_chars);
+        self
+    }
+
+    /// Set the truncation strategy
+    pub fn with_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Apply the limit to a single input
+    fn apply<'a>(&self, text: &'a str) -> Result<Cow<'a, str>> {
+        let Some(max) = self.max_input_chars else {
+            return Ok(Cow::Borrowed(text));
+        };
+
+        let len = text.chars().count();
+        if len <= max {
+            return Ok(Cow::Borrowed(text));
+        }
+
+        match self.strategy {
+            TruncationStrategy::Head => Ok(Cow::Owned(text.chars().take(max).collect())),
+            TruncationStrategy::Tail => Ok(Cow::Owned(text.chars().skip(len - max).collect())),
+            TruncationStrategy::Error => Err(Error::invalid_arg(format!(
+                "Input of {} characters exceeds limit of {}",
+                len, max
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbedderBase for TruncatingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let text = self.apply(text)?;
+        self.inner.embed(&text).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let texts = texts
+            .into_iter()
+            .map(|t| self.apply(t))
+            .collect::<Result<Vec<_>>>()?;
+        self.inner
+            .embed_batch(texts.iter().map(|t| t.as_ref()).collect())
+            .await
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        let text = self.apply(text)?;
+        self.inner.embed_query(&text).await
+    }
+
+    async fn embed_document(&self, text: &str) -> Result<Vec<f32>> {
+        let text = self.apply(text)?;
+        self.inner.embed_document(&text).await
+    }
+
+    async fn embed_documents(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let texts = texts
+            .into_iter()
+            .map(|t| self.apply(t))
+            .collect::<Result<Vec<_>>>()?;
+        self.inner
+            .embed_documents(texts.iter().map(|t| t.as_ref()).collect())
+            .await
+    }
+
+    fn max_batch_concurrency(&self) -> usize {
+        self.inner.max_batch_concurrency()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Embedder that records the texts it receives
+    #[derive(Default)]
+    struct RecordingEmbedder {
+        received: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for RecordingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.received.lock().unwrap().push(text.to_string());
+            Ok(vec![0.0])
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncates_before_request() {
+        let inner = Arc::new(RecordingEmbedder::default());
+        let head = TruncatingEmbedder::new(inner.clone()).with_max_input_chars(5);
+        let tail = TruncatingEmbedder::new(inner.clone())
+            .with_max_input_chars(5)
+            .with_strategy(TruncationStrategy::Tail);
+
+        head.embed("hello world").await.unwrap();
+        tail.embed("hello world").await.unwrap();
+        head.embed_batch(vec!["short", "much longer"]).await.unwrap();
+
+        let mut received = inner.received.lock().unwrap().clone();
+        received[2..].sort();
+        assert_eq!(received, vec!["hello", "world", "much ", "short"]);
+    }
+
+    #[tokio::test]
+    async fn test_truncates_query_and_document_modes() {
+        let inner = Arc::new(RecordingEmbedder::default());
+        let embedder = TruncatingEmbedder::new(inner.clone()).with_max_input_chars(5);
+
+        embedder.embed_query("hello world").await.unwrap();
+        embedder.embed_document("hello world").await.unwrap();
+        embedder.embed_documents(vec!["hello world"]).await.unwrap();
+
+        assert_eq!(*inner.received.lock().unwrap(), vec!["hello"; 3]);
+    }
+
+    #[tokio::test]
+    async fn test_error_strategy() {
+        let inner = Arc::new(RecordingEmbedder::default());
+        let embedder = TruncatingEmbedder::new(inner.clone())
+            .with_max_input_chars(5)
+            .with_strategy(TruncationStrategy::Error);
+
+        let result = embedder.embed("hello world").await;
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+        assert!(inner.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_limit_passes_through() {
+        let inner = Arc::new(RecordingEmbedder::default());
+        let embedder = TruncatingEmbedder::new(inner.clone());
+
+        embedder.embed("hello world").await.unwrap();
+        assert_eq!(*inner.received.lock().unwrap(), vec!["hello world"]);
+    }
+}
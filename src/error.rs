@@ -98,4 +98,23 @@ impl Error {
     pub fn auth(msg: impl Into<String>) -> Self {
         Error::AuthenticationError(msg.into())
     }
+
+    /// Stable snake_case name of the error variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::ConfigError(_) => "config",
+            Error::VectorStoreError(_) => "vector_store",
+            Error::LlmError(_) => "llm",
+            Error::EmbeddingError(_) => "embedding",
+            Error::MemoryError(_) => "memory",
+            Error::SerializationError(_) => "serialization",
+            Error::RequestError(_) => "request",
+            Error::QdrantError(_) => "qdrant",
+            Error::InvalidArgument(_) => "invalid_argument",
+            Error::NotFound(_) => "not_found",
+            Error::InternalError(_) => "internal",
+            Error::Timeout(_) => "timeout",
+            Error::AuthenticationError(_) => "authentication",
+        }
+    }
 }
@@ -1,6 +1,10 @@
 //! Advanced filtering and query DSL
 
 use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::memory::MemoryItem;
 
 /// Filter operator
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,6 +67,52 @@ pub enum FilterValue {
     Date(DateTime<Utc>),
 }
 
+impl FilterValue {
+    /// Compare a memory field value against this value
+    fn compare(&self, field: &str) -> Option<Ordering> {
+        match self {
+            Self::String(s) => Some(field.cmp(s.as_str())),
+            Self::Number(n) => field.parse::<f64>().ok()?.partial_cmp(n),
+            Self::Bool(b) => Some(field.parse::<bool>().ok()?.cmp(b)),
+            Self::Date(d) => {
+                let field = DateTime::parse_from_rfc3339(field).ok()?.with_timezone(&Utc);
+                Some(field.cmp(d))
+            }
+            Self::List(_) => None,
+        }
+    }
+
+    /// Check a memory field value for equality with this value
+    fn matches(&self, field: &str) -> bool {
+        self.compare(field) == Some(Ordering::Equal)
+    }
+}
+
+/// Resolve a field of a memory, falling back to its custom metadata
+fn field_value(item: &MemoryItem, field: &str) -> Option<String> {
+    match field {
+        "id" => Some(item.id.clone()),
+        "user_id" => Some(item.user_id.clone()),
+        "agent_id" => item.agent_id.clone(),
+        "run_id" => item.run_id.clone(),
+        "content" => Some(item.content.clone()),
+        "memory_type" | "type" => Some(item.memory_type.clone()),
+        "hash" => Some(item.hash.clone()),
+        "created_at" => Some(item.created_at.clone()),
+        "updated_at" => Some(item.updated_at.clone()),
+        "tags" => (!item.tags.is_empty()).then(|| item.tags.join(",")),
+        _ => item.metadata.get(field).cloned(),
+    }
+}
+
+/// Resolve the values of a memory field for matching; `tags` yields one value per tag
+fn field_values(item: &MemoryItem, field: &str) -> Vec<String> {
+    match field {
+        "tags" => item.tags.clone(),
+        _ => field_value(item, field).into_iter().collect(),
+    }
+}
+
 /// Single filter condition
 #[derive(Debug, Clone)]
 pub struct FilterCondition {
@@ -113,6 +163,66 @@ impl FilterCondition {
             value: FilterValue::Bool(true),
         }
     }
+
+    /// Check whether a memory satisfies this condition
+    ///
+    /// Conditions on a missing field never match, except `Exists`. For
+    /// multi-valued fields (`tags`), `Ne` and `NotIn` require every value to
+    /// match and the other operators any value.
+    pub fn matches(&self, item: &MemoryItem) -> bool {
+        let values = field_values(item, &self.field);
+        if self.operator == FilterOperator::Exists {
+            let expected = !matches!(self.value, FilterValue::Bool(false));
+            return values.is_empty() != expected;
+        }
+        if values.is_empty() {
+            return false;
+        }
+
+        match self.operator {
+            FilterOperator::Ne | FilterOperator::NotIn => {
+                values.iter().all(|v| self.matches_value(v))
+            }
+            _ => values.iter().any(|v| self.matches_value(v)),
+        }
+    }
+
+    /// Check a single field value against this condition
+    fn matches_value(&self, field: &str) -> bool {
+        let list = match &self.value {
+            FilterValue::List(values) => values.as_slice(),
+            _ => &[],
+        };
+
+        match self.operator {
+            FilterOperator::Eq => self.value.matches(field),
+            FilterOperator::Ne => !self.value.matches(field),
+            FilterOperator::Gt => self.value.compare(field) == Some(Ordering::Greater),
+            FilterOperator::Gte => matches!(
+                self.value.compare(field),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            FilterOperator::Lt => self.value.compare(field) == Some(Ordering::Less),
+            FilterOperator::Lte => matches!(
+                self.value.compare(field),
+                Some(Ordering::Less | Ordering::Equal)
+            ),
+            FilterOperator::Contains => match &self.value {
+                FilterValue::String(s) => field.contains(s.as_str()),
+                _ => false,
+            },
+            FilterOperator::In => list.iter().any(|v| v.matches(field)),
+            FilterOperator::NotIn => !list.iter().any(|v| v.matches(field)),
+            FilterOperator::Between => match list {
+                [min, max] => {
+                    matches!(min.compare(field), Some(Ordering::Greater | Ordering::Equal))
+                        && matches!(max.compare(field), Some(Ordering::Less | Ordering::Equal))
+                }
+                _ => false,
+            },
+            FilterOperator::Exists => unreachable!(),
+        }
+    }
 }
 
 /// Logical operator for combining filters
@@ -159,6 +269,23 @@ impl FilterQuery {
         self
     }
 
+    /// Check whether a memory satisfies this query
+    ///
+    /// `Not` matches when none of its conditions or nested queries match.
+    pub fn matches(&self, item: &MemoryItem) -> bool {
+        let mut results = self
+            .conditions
+            .iter()
+            .map(|c| c.matches(item))
+            .chain(self.nested.iter().map(|q| q.matches(item)));
+
+        match self.logical_op {
+            LogicalOperator::And => results.all(|r| r),
+            LogicalOperator::Or => results.any(|r| r),
+            LogicalOperator::Not => !results.any(|r| r),
+        }
+    }
+
     /// Convert to string representation
     pub fn to_string(&self) -> String {
         let mut parts = Vec::new();
@@ -266,6 +393,55 @@ impl AggregationQuery {
         self.filter = Some(filter);
         self
     }
+
+    /// Result key: `function(field)`, suffixed with `:group` when grouped
+    fn key(&self, group: Option<&str>) -> String {
+        let base = format!("{}({})", self.function.name(), self.field);
+        match group {
+            Some(group) => format!("{}:{}", base, group),
+            None => base,
+        }
+    }
+
+    /// Evaluate over memories, returning one value per group
+    ///
+    /// Numeric functions skip non-numeric values and groups without any.
+    pub fn evaluate(&self, items: &[MemoryItem]) -> HashMap<String, f64> {
+        let mut groups: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+        for item in items {
+            if self.filter.as_ref().is_some_and(|f| !f.matches(item)) {
+                continue;
+            }
+            let group = self
+                .group_by
+                .as_ref()
+                .map(|field| field_value(item, field).unwrap_or_default());
+            let values = groups.entry(group).or_default();
+            if let Some(value) = field_value(item, &self.field) {
+                values.push(value);
+            }
+        }
+
+        let mut results = HashMap::new();
+        for (group, values) in groups {
+            let numbers: Vec<f64> = values.iter().filter_map(|v| v.parse().ok()).collect();
+            let value = match self.function {
+                AggregationFunction::Count => Some(values.len() as f64),
+                AggregationFunction::Distinct => {
+                    Some(values.iter().collect::<HashSet<_>>().len() as f64)
+                }
+                AggregationFunction::Sum => Some(numbers.iter().sum()),
+                AggregationFunction::Avg => (!numbers.is_empty())
+                    .then(|| numbers.iter().sum::<f64>() / numbers.len() as f64),
+                AggregationFunction::Min => numbers.iter().copied().reduce(f64::min),
+                AggregationFunction::Max => numbers.iter().copied().reduce(f64::max),
+            };
+            if let Some(value) = value {
+                results.insert(self.key(group.as_deref()), value);
+            }
+        }
+        results
+    }
 }
 
 /// Time-based filter
@@ -301,6 +477,17 @@ impl TimeFilter {
 
         Self { start, end, field }
     }
+
+    /// Check whether a memory's timestamp field falls within the range
+    pub fn matches(&self, item: &MemoryItem) -> bool {
+        field_value(item, &self.field)
+            .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+            .map(|t| {
+                let t = t.with_timezone(&Utc);
+                self.start <= t && t <= self.end
+            })
+            .unwrap_or(false)
+    }
 }
 
 /// Query builder for complex queries
@@ -387,6 +574,46 @@ pub struct Query {
     pub offset: Option<usize>,
 }
 
+/// Result of executing a query
+#[derive(Debug, Clone)]
+pub enum QueryResult {
+    /// Matching memories, ordered by creation time and paginated
+    Rows(Vec<MemoryItem>),
+    /// Aggregation values keyed by `function(field)[:group]`
+    Aggregations(HashMap<String, f64>),
+}
+
+impl Query {
+    /// Execute the query over a set of memories
+    ///
+    /// Applies time filters, then filters; returns aggregations when any are
+    /// present, otherwise the paginated rows.
+    pub fn execute(&self, items: Vec<MemoryItem>) -> QueryResult {
+        let mut items: Vec<MemoryItem> = items
+            .into_iter()
+            .filter(|item| self.time_filters.iter().all(|f| f.matches(item)))
+            .filter(|item| self.filters.iter().all(|f| f.matches(item)))
+            .collect();
+
+        if !self.aggregations.is_empty() {
+            let results = self
+                .aggregations
+                .iter()
+                .flat_map(|agg| agg.evaluate(&items))
+                .collect();
+            return QueryResult::Aggregations(results);
+        }
+
+        items.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        let rows = items
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        QueryResult::Rows(rows)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -452,4 +679,72 @@ mod tests {
 
         assert_eq!(filter.field, "created_at");
     }
+
+    fn item(content: &str, memory_type: &str, created_at: DateTime<Utc>) -> MemoryItem {
+        let mut item = MemoryItem::new(
+            "user_1".to_string(),
+            content.to_string(),
+            memory_type.to_string(),
+        );
+        item.created_at = created_at.to_rfc3339();
+        item
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let memory = item("I like coffee", "preference", Utc::now())
+            .with_metadata("priority".to_string(), "3".to_string());
+
+        let query = FilterQuery::new(LogicalOperator::And)
+            .add_condition(FilterCondition::contains("content".to_string(), "coffee".to_string()))
+            .add_condition(FilterCondition::between("priority".to_string(), 1.0, 5.0));
+        assert!(query.matches(&memory));
+
+        let not = FilterQuery::new(LogicalOperator::Not).add_condition(FilterCondition::eq(
+            "memory_type".to_string(),
+            FilterValue::String("preference".to_string()),
+        ));
+        assert!(!not.matches(&memory));
+        assert!(!FilterCondition::exists("missing".to_string()).matches(&memory));
+    }
+
+    #[test]
+    fn test_tags_in_filter() {
+        let memory = item("Coffee before my run", "general", Utc::now())
+            .with_tag("coffee".to_string())
+            .with_tag("health".to_string());
+        let tags_in = |tags: &[&str]| FilterCondition {
+            field: "tags".to_string(),
+            operator: FilterOperator::In,
+            value: FilterValue::List(
+                tags.iter().map(|t| FilterValue::String(t.to_string())).collect(),
+            ),
+        };
+
+        assert!(tags_in(&["health", "sleep"]).matches(&memory));
+        assert!(!tags_in(&["sleep"]).matches(&memory));
+    }
+
+    #[test]
+    fn test_query_execute_aggregations() {
+        let now = Utc::now();
+        let items = vec![
+            item("a", "fact", now),
+            item("b", "fact", now),
+            item("c", "preference", now),
+        ];
+
+        let query = QueryBuilder::new()
+            .aggregate(
+                AggregationQuery::new(AggregationFunction::Count, "id".to_string())
+                    .group_by("memory_type".to_string()),
+            )
+            .build();
+
+        let QueryResult::Aggregations(results) = query.execute(items) else {
+            panic!("expected aggregations");
+        };
+        assert_eq!(results["count(id):fact"], 2.0);
+        assert_eq!(results["count(id):preference"], 1.0);
+    }
 }
@@ -0,0 +1,389 @@
+//! In-memory graph store implementation
+
+use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::Result;
+use super::{GraphStoreBase, GraphNode, GraphRelationship, RelationType, DEFAULT_EDGE_WEIGHT};
+
+/// Maximum label propagation rounds before communities are taken as-is
+pub const MAX_LABEL_PROPAGATION_ROUNDS: usize = 20;
+
+/// In-memory graph store implementation
+pub struct InMemoryGraphStore {
+    nodes: Arc<RwLock<HashMap<String, GraphNode>>>,
+    relationships: Arc<RwLock<Vec<GraphRelationship>>>,
+}
+
+impl InMemoryGraphStore {
+    /// Create a new in-memory graph store
+    pub fn new() -> Self {
+        Self {
+            nodes: Arc::new(RwLock::new(HashMap::new())),
+            relationships: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+}
+
+impl Default for InMemoryGraphStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl GraphStoreBase for InMemoryGraphStore {
+    async fn create_node(&self, node: GraphNode) -> Result<()> {
+        let mut nodes = self.nodes.write().await;
+        nodes.insert(node.id.clone(), node);
+        Ok(())
+    }
+
+    async fn get_node(&self, id: &str) -> Result<Option<GraphNode>> {
+        let nodes = self.nodes.read().await;
+        Ok(nodes.get(id).cloned())
+    }
+
+    async fn update_node(&self, id: &str, properties: HashMap<String, String>) -> Result<()> {
+        let mut nodes = self.nodes.write().await;
+        if let Some(node) = nodes.get_mut(id) {
+            node.properties.extend(properties);
+        }
+        Ok(())
+    }
+
+    async fn delete_node(&self, id: &str) -> Result<()> {
+        self.nodes.write().await.remove(id);
+        self.relationships
+            .write()
+            .await
+            .retain(|r| r.source_id != id && r.target_id != id);
+        Ok(())
+    }
+
+    async fn create_relationship(&self, relationship: GraphRelationship) -> Result<()> {
+        self.relationships.write().await.push(relationship);
+        Ok(())
+    }
+
+    async fn create_relationships(&self, relationships: Vec<GraphRelationship>) -> Result<()> {
+        self.relationships.write().await.extend(relationships);
+        Ok(())
+    }
+
+    async fn get_relationships(&self, node_id: &str) -> Result<Vec<GraphRelationship>> {
+        let relationships = self.relationships.read().await;
+        Ok(relationships
+            .iter()
+            .filter(|r| r.source_id == node_id || r.target_id == node_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_relationship(&self, source_id: &str, target_id: &str, rel_type: RelationType) -> Result<()> {
+        self.relationships.write().await.retain(|r| {
+            !(r.source_id == source_id && r.target_id == target_id && r.rel_type == rel_type)
+        });
+        Ok(())
+    }
+
+    async fn find_nodes_by_label(&self, label: &str) -> Result<Vec<GraphNode>> {
+        let nodes = self.nodes.read().await;
+        Ok(nodes
+            .values()
+            .filter(|n| n.labels.iter().any(|l| l == label))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<Vec<String>> {
+        let relationships = self.relationships.read().await;
+
+        // Breadth-first search over undirected edges
+        let mut previous: HashMap<String, String> = HashMap::new();
+        let mut visited: HashSet<String> = HashSet::from([source_id.to_string()]);
+        let mut queue = VecDeque::from([(source_id.to_string(), 0usize)]);
+
+        while let Some((current, depth)) = queue.pop_front() {
+            if current == target_id {
+                let mut path = vec![current.clone()];
+                let mut node = current;
+                while let Some(prev) = previous.get(&node) {
+                    path.push(prev.clone());
+                    node = prev.clone();
+                }
+                path.reverse();
+                return Ok(path);
+            }
+
+            if depth >= max_depth {
+                continue;
+            }
+
+            for rel in relationships.iter() {
+                let neighbor = if rel.source_id == current {
+                    &rel.target_id
+                } else if rel.target_id == current {
+                    &rel.source_id
+                } else {
+                    continue;
+                };
+
+                if visited.insert(neighbor.clone()) {
+                    previous.insert(neighbor.clone(), current.clone());
+                    queue.push_back((neighbor.clone(), depth + 1));
+                }
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    async fn find_weighted_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<(Vec<String>, f32)> {
+        let relationships = self.relationships.read().await;
+
+        // Hop-bounded relaxation over undirected edges: after round `d`, `best`
+        // holds the cheapest known path of at most `d` hops to each node
+        let mut best: HashMap<String, (f32, Vec<String>)> =
+            HashMap::from([(source_id.to_string(), (0.0, vec![source_id.to_string()]))]);
+        let mut frontier = best.clone();
+
+        for _ in 0..max_depth {
+            let mut next: HashMap<String, (f32, Vec<String>)> = HashMap::new();
+            for (current, (cost, path)) in &frontier {
+                for rel in relationships.iter() {
+                    let neighbor = if &rel.source_id == current {
+                        &rel.target_id
+                    } else if &rel.target_id == current {
+                        &rel.source_id
+                    } else {
+                        continue;
+                    };
+
+                    let total = cost + rel.weight.unwrap_or(DEFAULT_EDGE_WEIGHT);
+                    if path.contains(neighbor) || best.get(neighbor).is_some_and(|(b, _)| *b <= total) {
+                        continue;
+                    }
+
+                    let mut extended = path.clone();
+                    extended.push(neighbor.clone());
+                    best.insert(neighbor.clone(), (total, extended.clone()));
+                    next.insert(neighbor.clone(), (total, extended));
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        Ok(best
+            .remove(target_id)
+            .map(|(cost, path)| (path, cost))
+            .unwrap_or((vec![], 0.0)))
+    }
+
+    async fn detect_communities(&self) -> Result<Vec<Vec<String>>> {
+        let nodes = self.nodes.read().await;
+        let relationships = self.relationships.read().await;
+
+        // Undirected weighted adjacency, including nodes only known from edges
+        let mut neighbors: BTreeMap<&str, Vec<(&str, f32)>> =
+            nodes.keys().map(|id| (id.as_str(), Vec::new())).collect();
+        for rel in relationships.iter() {
+            let weight = rel.weight.unwrap_or(DEFAULT_EDGE_WEIGHT);
+            neighbors
+                .entry(&rel.source_id)
+                .or_default()
+                .push((&rel.target_id, weight));
+            neighbors
+                .entry(&rel.target_id)
+                .or_default()
+                .push((&rel.source_id, weight));
+        }
+
+        // Label propagation in ID order: each node adopts the label with the
+        // highest neighbor weight, keeping its own label on ties and otherwise
+        // breaking ties by the smallest label
+        let mut labels: HashMap<&str, &str> = neighbors.keys().map(|id| (*id, *id)).collect();
+        for _ in 0..MAX_LABEL_PROPAGATION_ROUNDS {
+            let mut changed = false;
+
+            for (id, adjacent) in &neighbors {
+                let mut weights: BTreeMap<&str, f32> = BTreeMap::new();
+                for (neighbor, weight) in adjacent {
+                    if neighbor != id {
+                        *weights.entry(labels[neighbor]).or_default() += weight;
+                    }
+                }
+
+                let Some(best) = weights.values().copied().reduce(f32::max) else {
+                    continue;
+                };
+                let current = labels[id];
+                if weights.get(&current) == Some(&best) {
+                    continue;
+                }
+                if let Some((label, _)) = weights.iter().find(|(_, w)| **w == best) {
+                    labels.insert(*id, *label);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let mut communities: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (id, label) in labels {
+            communities.entry(label).or_default().insert(id);
+        }
+
+        let mut communities: Vec<Vec<String>> = communities
+            .into_values()
+            .map(|ids| ids.into_iter().map(str::to_string).collect())
+            .collect();
+        communities.sort();
+        Ok(communities)
+    }
+
+    async fn node_count(&self) -> Result<usize> {
+        Ok(self.nodes.read().await.len())
+    }
+
+    async fn relationship_count(&self) -> Result<usize> {
+        Ok(self.relationships.read().await.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str) -> GraphNode {
+        GraphNode {
+            id: id.to_string(),
+            content: format!("content {}", id),
+            labels: vec!["Memory".to_string()],
+            properties: HashMap::new(),
+        }
+    }
+
+    fn relationship(source: &str, target: &str, rel_type: RelationType) -> GraphRelationship {
+        GraphRelationship {
+            source_id: source.to_string(),
+            target_id: target.to_string(),
+            rel_type,
+            properties: HashMap::new(),
+            weight: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nodes_and_relationships() {
+        let store = InMemoryGraphStore::new();
+        store.create_node(node("a")).await.unwrap();
+        store.create_node(node("b")).await.unwrap();
+        store
+            .create_relationship(relationship("a", "b", RelationType::Supports))
+            .await
+            .unwrap();
+
+        assert_eq!(store.node_count().await.unwrap(), 2);
+        assert_eq!(store.relationship_count().await.unwrap(), 1);
+        assert_eq!(store.get_relationships("b").await.unwrap().len(), 1);
+        assert_eq!(store.find_nodes_by_label("Memory").await.unwrap().len(), 2);
+
+        store.delete_node("a").await.unwrap();
+        assert_eq!(store.relationship_count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_relationships_bulk() {
+        let store = InMemoryGraphStore::new();
+        store
+            .create_relationships(vec![
+                relationship("a", "b", RelationType::Supports),
+                relationship("a", "c", RelationType::Supports),
+                relationship("a", "d", RelationType::PartOf),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(store.relationship_count().await.unwrap(), 3);
+        assert_eq!(store.get_relationships("a").await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_find_path() {
+        let store = InMemoryGraphStore::new();
+        store
+            .create_relationship(relationship("a", "b", RelationType::RelatedTo))
+            .await
+            .unwrap();
+        store
+            .create_relationship(relationship("b", "c", RelationType::PartOf))
+            .await
+            .unwrap();
+
+        assert_eq!(store.find_path("a", "c", 3).await.unwrap(), vec!["a", "b", "c"]);
+        assert!(store.find_path("a", "c", 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_weighted_path() {
+        let store = InMemoryGraphStore::new();
+        let weighted = |source: &str, target: &str, weight: f32| GraphRelationship {
+            weight: Some(weight),
+            ..relationship(source, target, RelationType::Supports)
+        };
+        store
+            .create_relationships(vec![
+                weighted("a", "b", 10.0),
+                weighted("a", "c", 1.0),
+                weighted("c", "d", 1.0),
+                weighted("d", "b", 1.0),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(store.find_path("a", "b", 5).await.unwrap(), vec!["a", "b"]);
+
+        let (path, total) = store.find_weighted_path("a", "b", 5).await.unwrap();
+        assert_eq!(path, vec!["a", "c", "d", "b"]);
+        assert_eq!(total, 3.0);
+
+        let (path, total) = store.find_weighted_path("a", "b", 2).await.unwrap();
+        assert_eq!(path, vec!["a", "b"]);
+        assert_eq!(total, 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_detect_communities_two_cliques() {
+        let store = InMemoryGraphStore::new();
+        for id in ["a", "b", "c", "d", "e", "f", "g"] {
+            store.create_node(node(id)).await.unwrap();
+        }
+        store
+            .create_relationships(vec![
+                relationship("a", "b", RelationType::RelatedTo),
+                relationship("b", "c", RelationType::RelatedTo),
+                relationship("c", "a", RelationType::RelatedTo),
+                relationship("d", "e", RelationType::Supports),
+                relationship("e", "f", RelationType::Supports),
+                relationship("f", "d", RelationType::Supports),
+            ])
+            .await
+            .unwrap();
+
+        let communities = store.detect_communities().await.unwrap();
+        assert_eq!(
+            communities,
+            vec![vec!["a", "b", "c"], vec!["d", "e", "f"], vec!["g"]]
+        );
+    }
+}
@@ -3,10 +3,15 @@
 use async_trait::async_trait;
 use crate::Result;
 
+pub mod memory;
 pub mod neo4j;
 
+pub use memory::InMemoryGraphStore;
 pub use neo4j::Neo4jStore;
 
+/// Weight assumed for relationships without an explicit weight
+pub const DEFAULT_EDGE_WEIGHT: f32 = 1.0;
+
 /// Graph relationship type
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RelationType {
@@ -62,6 +67,8 @@ pub struct GraphRelationship {
     pub rel_type: RelationType,
     /// Relationship properties
     pub properties: std::collections::HashMap<String, String>,
+    /// Edge weight used by weighted path finding (default: `DEFAULT_EDGE_WEIGHT`)
+    pub weight: Option<f32>,
 }
 
 /// Base trait for graph store implementations
@@ -82,6 +89,16 @@ pub trait GraphStoreBase: Send + Sync {
     /// Create a relationship
     async fn create_relationship(&self, relationship: GraphRelationship) -> Result<()>;
 
+    /// Create several relationships
+    ///
+    /// The default implementation creates them one at a time.
+    async fn create_relationships(&self, relationships: Vec<GraphRelationship>) -> Result<()> {
+        for relationship in relationships {
+            self.create_relationship(relationship).await?;
+        }
+        Ok(())
+    }
+
     /// Get relationships for a node
     async fn get_relationships(&self, node_id: &str) -> Result<Vec<GraphRelationship>>;
 
@@ -94,6 +111,18 @@ pub trait GraphStoreBase: Send + Sync {
     /// Find path between two nodes
     async fn find_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<Vec<String>>;
 
+    /// Find the lowest-total-weight path of at most `max_depth` hops
+    ///
+    /// Returns the node IDs along the path and its total weight, or an empty
+    /// path when the target is unreachable.
+    async fn find_weighted_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<(Vec<String>, f32)>;
+
+    /// Group nodes into densely-connected communities
+    ///
+    /// Returns the node IDs of each community; every node belongs to exactly
+    /// one community.
+    async fn detect_communities(&self) -> Result<Vec<Vec<String>>>;
+
     /// Get node count
     async fn node_count(&self) -> Result<usize>;
 
@@ -140,6 +169,7 @@ mod tests {
             target_id: "node_2".to_string(),
             rel_type: RelationType::RelatedTo,
             properties: std::collections::HashMap::new(),
+            weight: None,
         };
 
         assert_eq!(rel.source_id, "node_1");
@@ -3,8 +3,9 @@
 use async_trait::async_trait;
 use std::collections::HashMap;
 
-use crate::Result;
-use super::{GraphStoreBase, GraphNode, GraphRelationship, RelationType};
+use crate::{Error, Result};
+use crate::context::OperationContext;
+use super::{GraphStoreBase, GraphNode, GraphRelationship, RelationType, DEFAULT_EDGE_WEIGHT};
 
 /// Neo4j graph store
 pub struct Neo4jStore {
@@ -58,18 +59,28 @@ impl Neo4jStore {
 
     /// Execute a Cypher query
     async fn execute_query(&self, query: &str, params: &HashMap<String, serde_json::Value>) -> Result<serde_json::Value> {
+        self.execute_statements(&[(query.to_string(), params.clone())]).await
+    }
+
+    /// Execute several Cypher statements in a single request
+    async fn execute_statements(&self, statements: &[(String, HashMap<String, serde_json::Value>)]) -> Result<serde_json::Value> {
         let url = format!("{}/db/neo4j/exec", self.uri);
-        
-        let request_body = serde_json::json!({
-            "statements": [{
-                "statement": query,
-                "parameters": params
-            }]
-        });
+
+        let statements: Vec<serde_json::Value> = statements
+            .iter()
+            .map(|(query, params)| {
+                serde_json::json!({
+                    "statement": query,
+                    "parameters": params
+                })
+            })
+            .collect();
+        let request_body = serde_json::json!({ "statements": statements });
 
         let response = self.client
             .post(&url)
             .basic_auth(&self.username, Some(&self.password))
+            .headers(OperationContext::current_headers())
             .json(&request_body)
             .send()
             .await?;
@@ -90,6 +101,49 @@ impl Neo4jStore {
         Ok(None)
     }
 
+    /// Build one `UNWIND` statement per relationship type
+    ///
+    /// Cypher cannot parameterize relationship types, so relationships are
+    /// grouped by type.
+    pub fn create_relationships_statements(relationships: &[GraphRelationship]) -> Vec<(String, HashMap<String, serde_json::Value>)> {
+        let mut grouped: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+        for relationship in relationships {
+            let rel_name = relationship.rel_type.name();
+            let entry = serde_json::json!({
+                "source": relationship.source_id,
+                "target": relationship.target_id,
+                "weight": relationship.weight,
+            });
+            match grouped.iter_mut().find(|(name, _)| *name == rel_name) {
+                Some((_, rels)) => rels.push(entry),
+                None => grouped.push((rel_name, vec![entry])),
+            }
+        }
+
+        grouped
+            .into_iter()
+            .map(|(rel_name, rels)| {
+                let query = format!(
+                    "UNWIND $rels AS rel MATCH (s {{id: rel.source}}), (t {{id: rel.target}}) CREATE (s)-[r:{} {{weight: rel.weight}}]->(t)",
+                    rel_name
+                );
+                let mut params = HashMap::new();
+                params.insert("rels".to_string(), serde_json::Value::Array(rels));
+                (query, params)
+            })
+            .collect()
+    }
+
+    /// Cypher for the lowest-total-weight path, treating missing weights as the default
+    pub fn weighted_path_query(max_depth: usize) -> String {
+        format!(
+            "MATCH path = (s {{id: $source}}) -[*..{}]- (t {{id: $target}}) \
+             WITH path, reduce(total = 0.0, r IN relationships(path) | total + coalesce(r.weight, {:?})) AS total \
+             RETURN [n IN nodes(path) | n.id] AS ids, total ORDER BY total ASC LIMIT 1",
+            max_depth, DEFAULT_EDGE_WEIGHT
+        )
+    }
+
     /// Find shortest path
     pub async fn shortest_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<Vec<String>> {
         let query = format!(
@@ -161,18 +215,30 @@ impl GraphStoreBase for Neo4jStore {
     async fn create_relationship(&self, relationship: GraphRelationship) -> Result<()> {
         let rel_name = relationship.rel_type.name();
         let query = format!(
-            "MATCH (s {{id: $source}}), (t {{id: $target}}) CREATE (s)-[r:{}]->(t) RETURN r",
+            "MATCH (s {{id: $source}}), (t {{id: $target}}) CREATE (s)-[r:{} {{weight: $weight}}]->(t) RETURN r",
             rel_name
         );
 
         let mut params = HashMap::new();
         params.insert("source".to_string(), serde_json::Value::String(relationship.source_id));
         params.insert("target".to_string(), serde_json::Value::String(relationship.target_id));
+        params.insert("weight".to_string(), serde_json::json!(relationship.weight));
 
         self.execute_query(&query, &params).await?;
         Ok(())
     }
 
+    /// Create several relationships in a single request
+    async fn create_relationships(&self, relationships: Vec<GraphRelationship>) -> Result<()> {
+        if relationships.is_empty() {
+            return Ok(());
+        }
+
+        let statements = Self::create_relationships_statements(&relationships);
+        self.execute_statements(&statements).await?;
+        Ok(())
+    }
+
     /// Get relationships for a node
     async fn get_relationships(&self, node_id: &str) -> Result<Vec<GraphRelationship>> {
         let query = "MATCH (n {id: $id})-[r]-(m) RETURN type(r) as type, m.id as target";
@@ -217,6 +283,29 @@ impl GraphStoreBase for Neo4jStore {
         self.shortest_path(source_id, target_id, max_depth).await
     }
 
+    /// Find lowest-total-weight path between two nodes
+    async fn find_weighted_path(&self, source_id: &str, target_id: &str, max_depth: usize) -> Result<(Vec<String>, f32)> {
+        let query = Self::weighted_path_query(max_depth);
+
+        let mut params = HashMap::new();
+        params.insert("source".to_string(), serde_json::Value::String(source_id.to_string()));
+        params.insert("target".to_string(), serde_json::Value::String(target_id.to_string()));
+
+        let result = self.execute_query(&query, &params).await?;
+        parse_weighted_path(&result)
+    }
+
+    /// Group nodes into communities with GDS label propagation
+    async fn detect_communities(&self) -> Result<Vec<Vec<String>>> {
+        let query = "CALL gds.labelPropagation.stream({nodeProjection: '*', relationshipProjection: {all: {type: '*', orientation: 'UNDIRECTED'}}}) \
+                     YIELD nodeId, communityId \
+                     RETURN communityId, collect(gds.util.asNode(nodeId).id) AS ids";
+        let params = HashMap::new();
+
+        let result = self.execute_query(query, &params).await?;
+        parse_communities(&result)
+    }
+
     /// Get node count
     async fn node_count(&self) -> Result<usize> {
         let query = "MATCH (n) RETURN count(n) as count";
@@ -240,6 +329,67 @@ impl GraphStoreBase for Neo4jStore {
     }
 }
 
+/// Rows of the first statement in a transactional endpoint response
+fn result_rows(result: &serde_json::Value) -> Result<Vec<&Vec<serde_json::Value>>> {
+    if let Some(error) = result
+        .get("errors")
+        .and_then(|e| e.as_array())
+        .and_then(|errors| errors.first())
+    {
+        let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+        return Err(Error::internal(format!("Neo4j error: {}", message)));
+    }
+
+    result
+        .get("results")
+        .and_then(|r| r.get(0))
+        .and_then(|r| r.get("data"))
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| Error::internal("Malformed Neo4j response"))?
+        .iter()
+        .map(|item| {
+            item.get("row")
+                .and_then(|row| row.as_array())
+                .ok_or_else(|| Error::internal("Malformed Neo4j result row"))
+        })
+        .collect()
+}
+
+/// Parse a list of node IDs from a result column
+fn parse_ids(value: Option<&serde_json::Value>) -> Result<Vec<String>> {
+    value
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| Error::internal("Malformed Neo4j node ID list"))?
+        .iter()
+        .map(|id| {
+            id.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Error::internal("Malformed Neo4j node ID"))
+        })
+        .collect()
+}
+
+/// Parse the `ids, total` row of `weighted_path_query`; no row means unreachable
+fn parse_weighted_path(result: &serde_json::Value) -> Result<(Vec<String>, f32)> {
+    let Some(row) = result_rows(result)?.into_iter().next() else {
+        return Ok((vec![], 0.0));
+    };
+    let ids = parse_ids(row.first())?;
+    let total = row
+        .get(1)
+        .and_then(|t| t.as_f64())
+        .ok_or_else(|| Error::internal("Malformed Neo4j path weight"))?;
+    Ok((ids, total as f32))
+}
+
+/// Parse the `communityId, ids` rows of the label propagation query
+fn parse_communities(result: &serde_json::Value) -> Result<Vec<Vec<String>>> {
+    result_rows(result)?
+        .into_iter()
+        .map(|row| parse_ids(row.get(1)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,6 +416,32 @@ mod tests {
         assert!(store.is_ok());
     }
 
+    #[test]
+    fn test_create_relationships_statements() {
+        let rel = |target: &str, rel_type: RelationType| GraphRelationship {
+            source_id: "node_1".to_string(),
+            target_id: target.to_string(),
+            rel_type,
+            properties: HashMap::new(),
+            weight: None,
+        };
+
+        let statements = Neo4jStore::create_relationships_statements(&[
+            rel("node_2", RelationType::Supports),
+            rel("node_3", RelationType::Supports),
+        ]);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].0.starts_with("UNWIND $rels AS rel"));
+        assert!(statements[0].0.contains("[r:SUPPORTS {weight: rel.weight}]"));
+        assert_eq!(statements[0].1["rels"].as_array().unwrap().len(), 2);
+
+        let mixed = Neo4jStore::create_relationships_statements(&[
+            rel("node_2", RelationType::Supports),
+            rel("node_3", RelationType::PartOf),
+        ]);
+        assert_eq!(mixed.len(), 2);
+    }
+
     #[test]
     fn test_graph_node_creation() {
         let node = GraphNode {
@@ -285,8 +461,66 @@ mod tests {
             target_id: "node_2".to_string(),
             rel_type: RelationType::RelatedTo,
             properties: HashMap::new(),
+            weight: None,
         };
 
         assert_eq!(rel.source_id, "node_1");
     }
+
+    #[test]
+    fn test_weighted_path_query() {
+        let query = Neo4jStore::weighted_path_query(4);
+
+        assert!(query.contains("-[*..4]-"));
+        assert!(query.contains("coalesce(r.weight, 1.0)"));
+        assert!(query.contains("ORDER BY total ASC LIMIT 1"));
+    }
+
+    #[test]
+    fn test_parse_weighted_path() {
+        let found = serde_json::json!({
+            "results": [{
+                "columns": ["ids", "total"],
+                "data": [{ "row": [["a", "b", "c"], 2.5] }]
+            }],
+            "errors": []
+        });
+        assert_eq!(
+            parse_weighted_path(&found).unwrap(),
+            (vec!["a".to_string(), "b".to_string(), "c".to_string()], 2.5)
+        );
+
+        let unreachable = serde_json::json!({
+            "results": [{ "columns": ["ids", "total"], "data": [] }],
+            "errors": []
+        });
+        assert_eq!(parse_weighted_path(&unreachable).unwrap(), (vec![], 0.0));
+
+        let failed = serde_json::json!({
+            "results": [],
+            "errors": [{ "code": "Neo.ClientError.Statement.SyntaxError", "message": "bad query" }]
+        });
+        assert!(parse_weighted_path(&failed).is_err());
+    }
+
+    #[test]
+    fn test_parse_communities() {
+        let result = serde_json::json!({
+            "results": [{
+                "columns": ["communityId", "ids"],
+                "data": [{ "row": [7, ["a", "b"]] }, { "row": [9, ["c"]] }]
+            }],
+            "errors": []
+        });
+        assert_eq!(
+            parse_communities(&result).unwrap(),
+            vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]]
+        );
+
+        let malformed = serde_json::json!({
+            "results": [{ "columns": ["communityId", "ids"], "data": [{ "row": [7, "a"] }] }],
+            "errors": []
+        });
+        assert!(parse_communities(&malformed).is_err());
+    }
 }
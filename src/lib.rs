@@ -13,24 +13,30 @@
 //! ```
 
 pub mod config;
+pub mod context;
 pub mod distributed;
 pub mod embeddings;
 pub mod error;
 pub mod filtering;
 pub mod graph;
+pub mod llm;
 pub mod mcp;
 pub mod memory;
+pub mod rerank;
 pub mod utils;
 pub mod vector_store;
 
-pub use config::MemoryConfig;
-pub use distributed::{DistributedConfig, DistributedStoreBase, NodeRole, ShardingStrategy};
+pub use config::{CollectionStrategy, IdStrategy, MemoryConfig};
+pub use context::OperationContext;
+pub use distributed::{ConflictResolution, Consistency, DistributedConfig, DistributedStoreBase, NodeRole, ShardingStrategy};
 pub use embeddings::EmbedderBase;
 pub use error::{Error, Result};
-pub use filtering::{AggregationQuery, FilterQuery, QueryBuilder, TimeFilter};
+pub use filtering::{AggregationQuery, FilterQuery, QueryBuilder, QueryResult, TimeFilter};
 pub use graph::GraphStoreBase;
+pub use llm::LlmBase;
 pub use mcp::MemoryMcpServer;
 pub use memory::{Memory, MemoryBase};
+pub use rerank::Reranker;
 pub use vector_store::VectorStoreBase;
 
 /// Library version
@@ -0,0 +1,171 @@
+//! Structured memory classification parsed from LLM output
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use crate::{Error, Result};
+
+/// Memory types the `classify_memory` prompt asks for
+pub const CLASSIFICATION_TYPES: [&str; 4] = ["fact", "preference", "insight", "event"];
+
+/// Confidence assigned when the type is recovered from free text
+pub const TEXT_FALLBACK_CONFIDENCE: f32 = 0.5;
+
+/// Memory type chosen by an LLM with its confidence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    /// Memory type, lowercased
+    pub memory_type: String,
+    /// Confidence in `[0, 1]`
+    pub confidence: f32,
+}
+
+impl Classification {
+    /// Parse an LLM classification response
+    ///
+    /// Expects a JSON object such as `{"memory_type": "fact", "confidence": 0.9}`,
+    /// possibly surrounded by other text. When no valid object is found, the
+    /// first known memory type mentioned in the text is used with
+    /// [`TEXT_FALLBACK_CONFIDENCE`].
+    pub fn parse(response: &str) -> Result<Self> {
+        Self::parse_json(response)
+            .or_else(|| Self::parse_text(response))
+            .ok_or_else(|| {
+                Error::llm(format!("Could not parse classification: {}", response.trim()))
+            })
+    }
+
+    /// Parse the first JSON object in the response
+    fn parse_json(response: &str) -> Option<Self> {
+        let start = response.find('{')?;
+        let end = response.rfind('}')?;
+        if end < start {
+            return None;
+        }
+
+        let parsed: Self = serde_json::from_str(&response[start..=end]).ok()?;
+        let memory_type = parsed.memory_type.trim().to_lowercase();
+        if memory_type.is_empty() || !parsed.confidence.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            memory_type,
+            confidence: parsed.confidence.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Find the earliest known memory type mentioned in the response
+    fn parse_text(response: &str) -> Option<Self> {
+        let lower = response.to_lowercase();
+        CLASSIFICATION_TYPES
+            .iter()
+            .filter_map(|t| lower.find(t).map(|pos| (pos, *t)))
+            .min_by_key(|(pos, _)| *pos)
+            .map(|(_, memory_type)| Self {
+                memory_type: memory_type.to_string(),
+                confidence: TEXT_FALLBACK_CONFIDENCE,
+            })
+    }
+}
+
+/// Fact extracted from a conversation by an LLM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExtractedFact {
+    /// Fact content
+    pub content: String,
+    /// Memory type, lowercased (empty when the model gave none)
+    #[serde(default)]
+    pub memory_type: String,
+    /// Confidence in `[0, 1]` (0 when the model gave none)
+    #[serde(default)]
+    pub confidence: f32,
+}
+
+/// Shape of the `extract_facts` JSON response
+///
+/// Also usable as the input of a structured-output tool, whose input must
+/// be a JSON object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExtractedFacts {
+    /// Extracted facts
+    pub facts: Vec<ExtractedFact>,
+}
+
+impl ExtractedFact {
+    /// Parse an `extract_facts` response
+    ///
+    /// Expects a JSON object such as
+    /// `{"facts": [{"content": "...", "memory_type": "fact", "confidence": 0.9}]}`,
+    /// possibly surrounded by other text. Facts with empty content are
+    /// dropped; returns `None` when no valid object is found.
+    pub fn parse_all(response: &str) -> Option<Vec<Self>> {
+        let start = response.find('{')?;
+        let end = response.rfind('}')?;
+        if end < start {
+            return None;
+        }
+
+        let parsed: ExtractedFacts = serde_json::from_str(&response[start..=end]).ok()?;
+        Some(
+            parsed
+                .facts
+                .into_iter()
+                .filter(|f| !f.content.trim().is_empty())
+                .map(|f| Self {
+                    content: f.content.trim().to_string(),
+                    memory_type: f.memory_type.trim().to_lowercase(),
+                    confidence: if f.confidence.is_finite() {
+                        f.confidence.clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let classification =
+            Classification::parse("```json\n{\"memory_type\": \"Preference\", \"confidence\": 0.92}\n```")
+                .unwrap();
+        assert_eq!(classification.memory_type, "preference");
+        assert_eq!(classification.confidence, 0.92);
+    }
+
+    #[test]
+    fn test_parse_text_fallback() {
+        let classification = Classification::parse("Category: preference.").unwrap();
+        assert_eq!(classification.memory_type, "preference");
+        assert_eq!(classification.confidence, TEXT_FALLBACK_CONFIDENCE);
+
+        // Malformed JSON falls back to the text
+        let classification = Classification::parse("{\"memory_type\": \"event\"").unwrap();
+        assert_eq!(classification.memory_type, "event");
+    }
+
+    #[test]
+    fn test_parse_extracted_facts() {
+        let facts = ExtractedFact::parse_all(
+            "{\"facts\": [{\"content\": \" I live in Paris \", \"memory_type\": \"Fact\", \"confidence\": 1.4}, \
+             {\"content\": \"\", \"memory_type\": \"fact\", \"confidence\": 0.9}]}",
+        )
+        .unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].content, "I live in Paris");
+        assert_eq!(facts[0].memory_type, "fact");
+        assert_eq!(facts[0].confidence, 1.0);
+
+        assert!(ExtractedFact::parse_all("I live in Paris").is_none());
+    }
+
+    #[test]
+    fn test_parse_unrecognized() {
+        assert!(Classification::parse("I am not sure").is_err());
+    }
+}
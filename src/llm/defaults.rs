@@ -0,0 +1,111 @@
+//! LLM decorator applying provider-specific default generation parameters
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::Result;
+use super::{GenerationParams, LlmBase};
+
+/// LLM that fills unset generation parameters with its own defaults
+///
+/// Different providers and models work best with different settings; wrap
+/// each one with the parameters that suit it. Per-call parameters still
+/// take precedence field by field.
+pub struct DefaultParamsLlm {
+    inner: Arc<dyn LlmBase>,
+    default_params: GenerationParams,
+}
+
+impl DefaultParamsLlm {
+    /// Wrap an LLM with empty default parameters
+    pub fn new(inner: Arc<dyn LlmBase>) -> Self {
+        Self {
+            inner,
+            default_params: GenerationParams::default(),
+        }
+    }
+
+    /// Set the default generation parameters
+    pub fn with_default_params(mut self, params: GenerationParams) -> Self {
+        self.default_params = params;
+        self
+    }
+
+    /// Get the default generation parameters
+    pub fn default_params(&self) -> &GenerationParams {
+        &self.default_params
+    }
+
+    /// Merge per-call parameters over the defaults
+    fn params(&self, params: Option<GenerationParams>) -> GenerationParams {
+        params.unwrap_or_default().or_defaults(&self.default_params)
+    }
+}
+
+#[async_trait]
+impl LlmBase for DefaultParamsLlm {
+    async fn generate(&self, prompt: &str, params: Option<GenerationParams>) -> Result<String> {
+        self.inner.generate(prompt, Some(self.params(params))).await
+    }
+
+    async fn generate_stream_with(
+        &self,
+        prompt: &str,
+        params: Option<GenerationParams>,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        self.inner
+            .generate_stream_with(prompt, Some(self.params(params)), on_token)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// LLM that records the parameters of each request
+    #[derive(Default)]
+    struct RecordingLlm {
+        requests: Mutex<Vec<Option<GenerationParams>>>,
+    }
+
+    #[async_trait]
+    impl LlmBase for RecordingLlm {
+        async fn generate(&self, _prompt: &str, params: Option<GenerationParams>) -> Result<String> {
+            self.requests.lock().unwrap().push(params);
+            Ok("ok".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_params_used_without_params() {
+        let inner = Arc::new(RecordingLlm::default());
+        let llm = DefaultParamsLlm::new(inner.clone()).with_default_params(GenerationParams {
+            temperature: Some(0.2),
+            max_tokens: Some(256),
+            ..Default::default()
+        });
+
+        llm.generate("hi", None).await.unwrap();
+        llm.generate(
+            "hi",
+            Some(GenerationParams {
+                temperature: Some(0.9),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        let requests = inner.requests.lock().unwrap();
+        let first = requests[0].as_ref().unwrap();
+        assert_eq!(first.temperature, Some(0.2));
+        assert_eq!(first.max_tokens, Some(256));
+
+        let second = requests[1].as_ref().unwrap();
+        assert_eq!(second.temperature, Some(0.9));
+        assert_eq!(second.max_tokens, Some(256));
+    }
+}
@@ -0,0 +1,143 @@
+//! LLM abstraction and prompt management
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::Result;
+
+pub mod classification;
+pub mod defaults;
+pub mod prompts;
+pub mod structured;
+
+pub use classification::{Classification, ExtractedFact, ExtractedFacts};
+pub use defaults::DefaultParamsLlm;
+pub use prompts::{PromptManager, PromptTemplate};
+pub use structured::{parse_tool_use, ToolDefinition};
+
+/// Output format requested from the model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free-form text
+    #[default]
+    Text,
+    /// A single JSON object (e.g. OpenAI's `json_object` mode)
+    JsonObject,
+}
+
+/// Generation parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationParams {
+    /// Maximum number of tokens to generate
+    pub max_tokens: Option<usize>,
+    /// Sampling temperature
+    pub temperature: Option<f32>,
+    /// Nucleus sampling threshold
+    pub top_p: Option<f32>,
+    /// Top-k sampling
+    pub top_k: Option<usize>,
+    /// Sequences that stop generation
+    pub stop_sequences: Option<Vec<String>>,
+    /// Requested output format; providers without a JSON mode may ignore it
+    pub response_format: Option<ResponseFormat>,
+}
+
+impl GenerationParams {
+    /// Fill fields left unset with the values from `defaults`
+    pub fn or_defaults(self, defaults: &GenerationParams) -> Self {
+        Self {
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            top_k: self.top_k.or(defaults.top_k),
+            stop_sequences: self.stop_sequences.or_else(|| defaults.stop_sequences.clone()),
+            response_format: self.response_format.or(defaults.response_format),
+        }
+    }
+}
+
+/// Base trait for LLM implementations
+#[async_trait]
+pub trait LlmBase: Send + Sync {
+    /// Generate text for a prompt
+    async fn generate(&self, prompt: &str, params: Option<GenerationParams>) -> Result<String>;
+
+    /// Generate text, passing each token to `on_token` as it arrives
+    ///
+    /// Returns the full text. The default calls `generate` and emits the
+    /// whole response as a single token; providers with streaming APIs
+    /// override it to emit tokens incrementally.
+    async fn generate_stream_with(
+        &self,
+        prompt: &str,
+        params: Option<GenerationParams>,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        let text = self.generate(prompt, params).await?;
+        on_token(&text);
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedLlm;
+
+    #[async_trait]
+    impl LlmBase for FixedLlm {
+        async fn generate(&self, _prompt: &str, _params: Option<GenerationParams>) -> Result<String> {
+            Ok("Hello there".to_string())
+        }
+    }
+
+    /// LLM that streams its response word by word
+    struct StreamingLlm;
+
+    #[async_trait]
+    impl LlmBase for StreamingLlm {
+        async fn generate(&self, _prompt: &str, _params: Option<GenerationParams>) -> Result<String> {
+            Ok("Hello there".to_string())
+        }
+
+        async fn generate_stream_with(
+            &self,
+            _prompt: &str,
+            _params: Option<GenerationParams>,
+            on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+        ) -> Result<String> {
+            let mut text = String::new();
+            for token in ["Hello", " there"] {
+                on_token(token);
+                text.push_str(token);
+            }
+            Ok(text)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_stream_emits_once() {
+        let mut tokens = Vec::new();
+        let text = FixedLlm
+            .generate_stream_with("hi", None, &mut |t| tokens.push(t.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(text, "Hello there");
+        assert_eq!(tokens, vec!["Hello there"]);
+    }
+
+    #[tokio::test]
+    async fn test_overridden_stream_emits_per_token() {
+        let llm: &dyn LlmBase = &StreamingLlm;
+        let mut tokens = Vec::new();
+        let text = llm
+            .generate_stream_with("hi", None, &mut |t| tokens.push(t.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(text, "Hello there");
+        assert_eq!(tokens, vec!["Hello", " there"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! Prompt templates and management
+
+use std::collections::HashMap;
+use crate::{Error, Result};
+
+/// Prompt template with `{variable}` placeholders
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    /// Template name
+    pub name: String,
+    /// Template text
+    pub template: String,
+}
+
+impl PromptTemplate {
+    /// Create a new prompt template
+    pub fn new(name: String, template: String) -> Self {
+        Self { name, template }
+    }
+
+    /// Extract variable names from the template
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            let after = &rest[start + 1..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            let name = &after[..end];
+            if !name.is_empty() && !variables.iter().any(|v| v == name) {
+                variables.push(name.to_string());
+            }
+            rest = &after[end + 1..];
+        }
+
+        variables
+    }
+
+    /// Render the template with the given variables
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        let mut rendered = self.template.clone();
+        for variable in self.variables() {
+            let value = vars.get(&variable).ok_or_else(|| {
+                Error::invalid_arg(format!("Missing prompt variable: {}", variable))
+            })?;
+            rendered = rendered.replace(&format!("{{{}}}", variable), value);
+        }
+        Ok(rendered)
+    }
+}
+
+/// Prompt manager holding named templates
+pub struct PromptManager {
+    templates: HashMap<String, PromptTemplate>,
+}
+
+impl PromptManager {
+    /// Create a new prompt manager with the built-in templates
+    pub fn new() -> Self {
+        let mut manager = Self {
+            templates: HashMap::new(),
+        };
+
+        manager.register(PromptTemplate::new(
+            "extract_facts".to_string(),
+            "Extract the key facts about the user from the following conversation. \
+             Respond with only a JSON object with an array field facts. Each element has \
+             a string field content, a string field memory_type (one of: fact, preference, \
+             insight, event) and a number field confidence between 0 and 1.\n\n\
+             Conversation:\n{conversation}"
+                .to_string(),
+        ));
+        manager.register(PromptTemplate::new(
+            "generate_insights".to_string(),
+            "Based on the following facts about the user, generate higher-level insights. \
+             Return one insight per line.\n\nFacts:\n{facts}"
+                .to_string(),
+        ));
+        manager.register(PromptTemplate::new(
+            "summarize_memories".to_string(),
+            "Summarize the following memories into a concise user profile.\n\nMemories:\n{memories}"
+                .to_string(),
+        ));
+        manager.register(PromptTemplate::new(
+            "answer_with_context".to_string(),
+            "Answer the question using the context below.\n\nContext:\n{context}\n\nQuestion: {question}"
+                .to_string(),
+        ));
+        manager.register(PromptTemplate::new(
+            "classify_memory".to_string(),
+            "Classify the following memory as one of: fact, preference, insight, event. \
+             Respond with only a JSON object with a string field memory_type and \
+             a number field confidence between 0 and 1.\n\nMemory: {memory}"
+                .to_string(),
+        ));
+        manager.register(PromptTemplate::new(
+            "merge_memories".to_string(),
+            "Merge the following related memories into a single consolidated statement. \
+             Keep every distinct detail and return only the statement.\n\nMemories:\n{memories}"
+                .to_string(),
+        ));
+
+        manager
+    }
+
+    /// Register a template, replacing any template with the same name
+    pub fn register(&mut self, template: PromptTemplate) {
+        self.templates.insert(template.name.clone(), template);
+    }
+
+    /// Get a template by name
+    pub fn get(&self, name: &str) -> Option<&PromptTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Render a named template
+    pub fn render(&self, name: &str, vars: &HashMap<String, String>) -> Result<String> {
+        self.get(name)
+            .ok_or_else(|| Error::not_found(format!("Prompt template not found: {}", name)))?
+            .render(vars)
+    }
+
+    /// List template names
+    pub fn template_names(&self) -> Vec<String> {
+        self.templates.keys().cloned().collect()
+    }
+}
+
+impl Default for PromptManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_variables() {
+        let template = PromptTemplate::new(
+            "test".to_string(),
+            "Context: {context} Question: {question} Again: {context}".to_string(),
+        );
+        assert_eq!(template.variables(), vec!["context", "question"]);
+    }
+
+    #[test]
+    fn test_template_render() {
+        let template = PromptTemplate::new("test".to_string(), "Analyze: {text}".to_string());
+        let mut vars = HashMap::new();
+        vars.insert("text".to_string(), "I like coffee".to_string());
+
+        assert_eq!(template.render(&vars).unwrap(), "Analyze: I like coffee");
+    }
+
+    #[test]
+    fn test_template_missing_variable() {
+        let template = PromptTemplate::new("test".to_string(), "Analyze: {text}".to_string());
+        assert!(template.render(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_builtin_templates() {
+        let manager = PromptManager::new();
+        for name in [
+            "extract_facts",
+            "generate_insights",
+            "summarize_memories",
+            "answer_with_context",
+            "classify_memory",
+            "merge_memories",
+        ] {
+            assert!(manager.get(name).is_some(), "missing template {}", name);
+        }
+    }
+
+    #[test]
+    fn test_render_merge_memories() {
+        let manager = PromptManager::new();
+        let mut vars = HashMap::new();
+        vars.insert("memories".to_string(), "- likes coffee".to_string());
+
+        let prompt = manager.render("merge_memories", &vars).unwrap();
+        assert!(prompt.contains("- likes coffee"));
+    }
+
+    #[test]
+    fn test_render_classify_memory() {
+        let manager = PromptManager::new();
+        let mut vars = HashMap::new();
+        vars.insert("memory".to_string(), "I like coffee".to_string());
+
+        let prompt = manager.render("classify_memory", &vars).unwrap();
+        assert!(prompt.contains("JSON"));
+        assert!(prompt.ends_with("Memory: I like coffee"));
+    }
+
+    #[test]
+    fn test_render_unknown_template() {
+        let manager = PromptManager::new();
+        assert!(manager.render("unknown", &HashMap::new()).is_err());
+    }
+}
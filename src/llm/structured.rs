@@ -0,0 +1,132 @@
+//! Structured output through forced tool use
+//!
+//! Providers such as Anthropic's Messages API return reliable structured
+//! output when the model is forced to call a tool whose input schema is the
+//! desired type. These helpers build the tool definition from a type's JSON
+//! schema and read the tool input back from the response.
+
+use rmcp::schemars::{self, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{Error, Result};
+
+/// Tool whose input schema is the structured output type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Tool name
+    pub name: String,
+    /// What the tool is for, shown to the model
+    pub description: String,
+    /// JSON schema of the tool input
+    pub input_schema: Value,
+}
+
+impl ToolDefinition {
+    /// Define a tool whose input is a `T`
+    ///
+    /// `T` should serialize to a JSON object; providers reject tools with
+    /// other input types.
+    pub fn for_type<T: JsonSchema>(name: impl Into<String>, description: impl Into<String>) -> Self {
+        let schema = schemars::schema_for!(T);
+        Self {
+            name: name.into(),
+            description: description.into(),
+            input_schema: serde_json::to_value(schema).unwrap_or(Value::Null),
+        }
+    }
+
+    /// `tools` and `tool_choice` request fields forcing the model to call this tool
+    pub fn forced_request_fields(&self) -> Value {
+        json!({
+            "tools": [self],
+            "tool_choice": { "type": "tool", "name": self.name },
+        })
+    }
+}
+
+/// Content block of a Messages API response
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    ToolUse { name: String, input: Value },
+    #[serde(other)]
+    Other,
+}
+
+/// Messages API response body
+#[derive(Deserialize)]
+struct MessageResponse {
+    content: Vec<ContentBlock>,
+}
+
+/// Read the input of the `tool_name` call from a Messages API response body
+///
+/// Returns an LLM error when the response has no such `tool_use` block and
+/// a serialization error when its input does not match `T`.
+pub fn parse_tool_use<T: DeserializeOwned>(response: &Value, tool_name: &str) -> Result<T> {
+    let response: MessageResponse = serde_json::from_value(response.clone())?;
+    let input = response
+        .content
+        .into_iter()
+        .find_map(|block| match block {
+            ContentBlock::ToolUse { name, input } if name == tool_name => Some(input),
+            _ => None,
+        })
+        .ok_or_else(|| Error::llm(format!("Response has no '{}' tool call", tool_name)))?;
+    Ok(serde_json::from_value(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::classification::{ExtractedFact, ExtractedFacts};
+
+    #[test]
+    fn test_tool_definition_from_schema() {
+        let tool = ToolDefinition::for_type::<ExtractedFacts>("record_facts", "Record extracted facts");
+        assert_eq!(tool.input_schema["type"], "object");
+        assert!(tool.input_schema["properties"]["facts"].is_object());
+
+        let fields = tool.forced_request_fields();
+        assert_eq!(fields["tool_choice"]["name"], "record_facts");
+        assert_eq!(fields["tools"][0]["name"], "record_facts");
+    }
+
+    #[test]
+    fn test_parse_tool_use() {
+        let response = json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                { "type": "text", "text": "Recording the facts." },
+                {
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "record_facts",
+                    "input": {
+                        "facts": [{ "content": "I live in Paris", "memory_type": "fact", "confidence": 0.9 }]
+                    }
+                }
+            ],
+            "stop_reason": "tool_use"
+        });
+
+        let parsed: ExtractedFacts = parse_tool_use(&response, "record_facts").unwrap();
+        assert_eq!(
+            parsed.facts,
+            vec![ExtractedFact {
+                content: "I live in Paris".to_string(),
+                memory_type: "fact".to_string(),
+                confidence: 0.9,
+            }]
+        );
+
+        assert!(matches!(
+            parse_tool_use::<ExtractedFacts>(&response, "other_tool"),
+            Err(Error::LlmError(_))
+        ));
+    }
+}
@@ -2,8 +2,10 @@
 //!
 //! This module provides an MCP server that exposes memory operations as tools.
 
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 
 use rmcp::{
     handler::server::tool::ToolRouter,
@@ -16,17 +18,151 @@ use rmcp::{
 use serde::{Deserialize, Serialize};
 
 use crate::config::MemoryConfig;
+use crate::Error;
 use crate::embeddings::LocalEmbedder;
 use crate::memory::{Memory, MemoryBase, MemoryItem, SearchResultItem};
 use crate::vector_store::InMemoryStore;
 
+/// How long a repeated idempotency key returns the cached response
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(300);
+
+/// Maximum number of idempotency keys remembered
+const IDEMPOTENCY_CAPACITY: usize = 1024;
+
+/// Default cap on the number of results a tool call may request
+const DEFAULT_MAX_LIMIT: usize = 100;
+
 /// MCP Memory Server
 #[derive(Clone)]
 pub struct MemoryMcpServer {
     memory: Arc<RwLock<Memory>>,
+    idempotency: Arc<Mutex<IdempotencyCache>>,
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    max_limit: usize,
     tool_router: ToolRouter<Self>,
 }
 
+/// Idempotency keys are scoped to a user: (user ID, caller's key)
+type IdempotencyKey = (String, String);
+
+/// Short-lived LRU of idempotency keys and the responses they produced
+struct IdempotencyCache {
+    entries: HashMap<IdempotencyKey, (Instant, MemoryResponse)>,
+    order: VecDeque<IdempotencyKey>,
+    /// Locks serializing in-progress calls that share a key
+    in_flight: HashMap<IdempotencyKey, Arc<Mutex<()>>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl IdempotencyCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            in_flight: HashMap::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Get the response recorded for a key if it has not expired
+    fn get(&mut self, key: &IdempotencyKey) -> Option<MemoryResponse> {
+        let (recorded_at, response) = self.entries.get(key)?;
+        if recorded_at.elapsed() > self.ttl {
+            self.entries.remove(key);
+            self.order.retain(|k| k != key);
+            return None;
+        }
+        Some(response.clone())
+    }
+
+    /// Lock shared by the in-progress calls with a key
+    fn in_flight_lock(&mut self, key: &IdempotencyKey) -> Arc<Mutex<()>> {
+        self.in_flight.entry(key.clone()).or_default().clone()
+    }
+
+    /// Release a call's in-flight lock, forgetting it once no call holds it
+    fn release(&mut self, key: &IdempotencyKey, lock: Arc<Mutex<()>>) {
+        drop(lock);
+        if self.in_flight.get(key).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            self.in_flight.remove(key);
+        }
+    }
+
+    /// Record the response for a key, evicting the oldest key when full
+    fn insert(&mut self, key: IdempotencyKey, response: MemoryResponse) {
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.order.retain(|k| k != &key);
+        self.order.push_back(key.clone());
+        self.entries.insert(key, (Instant::now(), response));
+    }
+}
+
+/// Per-user token buckets limiting the rate of tool calls
+struct RateLimiter {
+    /// Tokens added per second
+    rate: f64,
+    /// Bucket capacity
+    burst: f64,
+    /// User ID -> (available tokens, last refill)
+    buckets: HashMap<String, (f64, Instant)>,
+    /// Last time full buckets were dropped
+    pruned_at: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            rate,
+            burst: f64::from(burst.max(1)),
+            buckets: HashMap::new(),
+            pruned_at: Instant::now(),
+        }
+    }
+
+    /// Drop buckets that have refilled to `burst`
+    ///
+    /// A full bucket behaves like a missing one, so this only bounds the
+    /// map to users active within one refill period. It scans at most once
+    /// per refill period.
+    fn prune(&mut self, now: Instant) {
+        if now.duration_since(self.pruned_at).as_secs_f64() < self.burst / self.rate {
+            return;
+        }
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, (tokens, refilled_at)| {
+            *tokens + now.duration_since(*refilled_at).as_secs_f64() * rate < burst
+        });
+        self.pruned_at = now;
+    }
+
+    /// Take a token from the user's bucket; false when it is empty
+    fn try_acquire(&mut self, user_id: &str) -> bool {
+        let now = Instant::now();
+        self.prune(now);
+        let (tokens, refilled_at) = self
+            .buckets
+            .entry(user_id.to_string())
+            .or_insert((self.burst, now));
+        let elapsed = now.duration_since(*refilled_at).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.rate).min(self.burst);
+        *refilled_at = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Input for adding a memory
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct AddMemoryInput {
@@ -39,6 +175,9 @@ pub struct AddMemoryInput {
     /// Optional memory type (e.g., "fact", "preference", "insight")
     #[schemars(description = "Optional memory type (e.g., 'fact', 'preference', 'insight')")]
     pub memory_type: Option<String>,
+    /// Optional idempotency key; repeated calls with the same key return the first result
+    #[schemars(description = "Optional idempotency key; retried calls with the same key return the original memory instead of adding a duplicate")]
+    pub idempotency_key: Option<String>,
 }
 
 /// Input for searching memories
@@ -53,6 +192,17 @@ pub struct SearchMemoryInput {
     /// Maximum number of results (default: 5)
     #[schemars(description = "Maximum number of results to return (default: 5)")]
     pub limit: Option<usize>,
+    /// Output format: "json" (default) or "ndjson"
+    #[schemars(description = "Output format: 'json' (default) for a JSON array or 'ndjson' for one JSON object per line")]
+    pub format: Option<String>,
+    /// Minimum similarity score of returned memories (default: the server's configured floor)
+    ///
+    /// Scores are cosine similarities in [-1, 1]. The default hash-based
+    /// embedder gives unrelated texts scores up to about 0.6 and exact
+    /// matches 1.0, so use 0.8 or more with it; with model embedders 0.3-0.5
+    /// drops most unrelated hits.
+    #[schemars(description = "Optional minimum similarity score in [-1, 1]. With the default hash-based embedder only exact matches are reliably relevant (use >= 0.8); with model embedders such as OpenAI, 0.3-0.5 filters out most unrelated memories")]
+    pub min_score: Option<f32>,
 }
 
 /// Input for updating a memory
@@ -74,12 +224,67 @@ pub struct DeleteMemoryInput {
     pub memory_id: String,
 }
 
+/// Input for getting a single memory
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetMemoryInput {
+    /// Memory ID to get
+    #[schemars(description = "Memory ID to get")]
+    pub memory_id: String,
+}
+
 /// Input for getting all memories
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct GetAllMemoriesInput {
     /// User ID to get memories for
     #[schemars(description = "User ID to get all memories for")]
     pub user_id: String,
+    /// Output format: "json" (default) or "ndjson"
+    #[schemars(description = "Output format: 'json' (default) for a JSON array or 'ndjson' for one JSON object per line")]
+    pub format: Option<String>,
+}
+
+/// Input for memory statistics
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MemoryStatsInput {
+    /// User ID to get statistics for
+    #[schemars(description = "User ID to get memory statistics for")]
+    pub user_id: String,
+}
+
+/// Map a crate error to an MCP error with its kind in `data`
+///
+/// Caller errors map to invalid params, missing memories to resource not
+/// found and authentication failures to invalid request; everything else
+/// is an internal error.
+pub fn to_mcp_error(err: Error) -> McpError {
+    let data = Some(serde_json::json!({ "kind": err.kind() }));
+    let message = err.to_string();
+    match err {
+        Error::InvalidArgument(_) => McpError::invalid_params(message, data),
+        Error::NotFound(_) => McpError::resource_not_found(message, data),
+        Error::AuthenticationError(_) => McpError::invalid_request(message, data),
+        _ => McpError::internal_error(message, data),
+    }
+}
+
+/// Serialize tool results as a pretty JSON array or as NDJSON
+fn render_results<T: Serialize>(items: &[T], format: Option<&str>) -> Result<String, McpError> {
+    let to_error = |e: serde_json::Error| McpError::internal_error(e.to_string(), None);
+    match format.unwrap_or("json") {
+        "json" => serde_json::to_string_pretty(items).map_err(to_error),
+        "ndjson" => {
+            let lines = items
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(to_error)?;
+            Ok(lines.join("\n"))
+        }
+        other => Err(McpError::invalid_params(
+            format!("Unsupported format '{}', expected 'json' or 'ndjson'", other),
+            None,
+        )),
+    }
 }
 
 /// Memory response for serialization
@@ -113,6 +318,14 @@ pub struct SearchResponse {
     pub score: f32,
 }
 
+/// Memory statistics response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStatsResponse {
+    pub user_id: String,
+    pub total: usize,
+    pub by_type: BTreeMap<String, usize>,
+}
+
 impl From<SearchResultItem> for SearchResponse {
     fn from(item: SearchResultItem) -> Self {
         Self {
@@ -126,44 +339,155 @@ impl From<SearchResultItem> for SearchResponse {
 impl MemoryMcpServer {
     /// Create a new MCP Memory Server with default configuration
     pub fn new() -> Self {
-        let config = MemoryConfig::new("memory.db".to_string());
+        Self::with_config(MemoryConfig::new("memory.db".to_string()))
+    }
+
+    /// Create with the default store and embedder and a custom configuration
+    pub fn with_config(config: MemoryConfig) -> Self {
         let vector_store = Arc::new(InMemoryStore::new());
         let embedder = Arc::new(LocalEmbedder::with_defaults());
         let memory = Memory::new(config, vector_store, embedder);
 
-        Self {
-            memory: Arc::new(RwLock::new(memory)),
-            tool_router: Self::tool_router(),
-        }
+        Self::with_memory(memory)
     }
 
     /// Create with custom memory instance
     pub fn with_memory(memory: Memory) -> Self {
         Self {
             memory: Arc::new(RwLock::new(memory)),
+            idempotency: Arc::new(Mutex::new(IdempotencyCache::new(
+                IDEMPOTENCY_CAPACITY,
+                IDEMPOTENCY_TTL,
+            ))),
+            rate_limiter: None,
+            max_limit: DEFAULT_MAX_LIMIT,
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Set the maximum number of results a tool call may request
+    pub fn with_max_limit(mut self, max_limit: usize) -> Self {
+        self.max_limit = max_limit;
+        self
+    }
+
+    /// Limit each user to `requests_per_second` tool calls, with bursts of up to `burst`
+    ///
+    /// Calls over the limit fail with an invalid-request error whose data
+    /// kind is `rate_limited`. Tools addressed by memory ID are not limited.
+    /// Fails with a config error unless `requests_per_second` is positive.
+    pub fn with_rate_limit(mut self, requests_per_second: f64, burst: u32) -> crate::Result<Self> {
+        if !(requests_per_second.is_finite() && requests_per_second > 0.0) {
+            return Err(Error::config("requests_per_second must be positive"));
+        }
+        self.rate_limiter = Some(Arc::new(Mutex::new(RateLimiter::new(
+            requests_per_second,
+            burst,
+        ))));
+        Ok(self)
+    }
+
+    /// Merge additional tools into the server's tool set
+    ///
+    /// Extra routes are served alongside the memory tools; a route with the
+    /// same name as an existing tool replaces it.
+    pub fn with_extra_router(mut self, router: ToolRouter<Self>) -> Self {
+        self.tool_router.merge(router);
+        self
+    }
+
+    /// Shared handle to the underlying memory
+    ///
+    /// Lets sibling handlers operate on the same store as this server.
+    pub fn memory(&self) -> Arc<RwLock<Memory>> {
+        self.memory.clone()
+    }
+
+    /// Clamp a requested result limit to the configured maximum
+    fn clamp_limit(&self, limit: usize) -> usize {
+        if limit > self.max_limit {
+            tracing::warn!("Requested limit {} clamped to {}", limit, self.max_limit);
+            self.max_limit
+        } else {
+            limit
+        }
+    }
+
+    /// Reject the call if the user has exceeded the rate limit
+    async fn check_rate_limit(&self, user_id: &str) -> Result<(), McpError> {
+        let Some(limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        if limiter.lock().await.try_acquire(user_id) {
+            Ok(())
+        } else {
+            Err(McpError::invalid_request(
+                format!("Rate limit exceeded for user {}", user_id),
+                Some(serde_json::json!({ "kind": "rate_limited" })),
+            ))
+        }
+    }
+
     /// Add a new memory for a user
     #[tool(description = "Add a new memory for a user. Stores content with semantic embedding for later retrieval.")]
     async fn add_memory(
         &self,
         input: Parameters<AddMemoryInput>,
     ) -> Result<CallToolResult, McpError> {
+        let input = input.0;
+        self.check_rate_limit(&input.user_id).await?;
+        let key = input
+            .idempotency_key
+            .clone()
+            .map(|key| (input.user_id.clone(), key));
+
+        // Concurrent retries of one key queue on a per-key lock; the shared
+        // cache is only locked briefly, so other adds are not serialized
+        let lock = match &key {
+            Some(key) => Some(self.idempotency.lock().await.in_flight_lock(key)),
+            None => None,
+        };
+        let response = {
+            let _guard = match &lock {
+                Some(lock) => Some(lock.lock().await),
+                None => None,
+            };
+            self.add_once(&input, key.as_ref()).await
+        };
+        if let (Some(key), Some(lock)) = (&key, lock) {
+            self.idempotency.lock().await.release(key, lock);
+        }
+
+        let json = serde_json::to_string_pretty(&response?)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Add a memory unless an earlier call with the same key already did
+    async fn add_once(
+        &self,
+        input: &AddMemoryInput,
+        key: Option<&IdempotencyKey>,
+    ) -> Result<MemoryResponse, McpError> {
+        if let Some(key) = key
+            && let Some(response) = self.idempotency.lock().await.get(key)
+        {
+            return Ok(response);
+        }
+
         let memory = self.memory.read().await;
-        match memory
-            .add(&input.0.user_id, &input.0.content, input.0.memory_type.as_deref())
+        let item = memory
+            .add(&input.user_id, &input.content, input.memory_type.as_deref())
             .await
-        {
-            Ok(item) => {
-                let response: MemoryResponse = item.into();
-                let json = serde_json::to_string_pretty(&response)
-                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-                Ok(CallToolResult::success(vec![Content::text(json)]))
-            }
-            Err(e) => Err(McpError::internal_error(e.to_string(), None)),
+            .map_err(to_mcp_error)?;
+        let response: MemoryResponse = item.into();
+        if let Some(key) = key {
+            self.idempotency
+                .lock()
+                .await
+                .insert(key.clone(), response.clone());
         }
+        Ok(response)
     }
 
     /// Search memories by semantic similarity
@@ -172,17 +496,25 @@ impl MemoryMcpServer {
         &self,
         input: Parameters<SearchMemoryInput>,
     ) -> Result<CallToolResult, McpError> {
+        self.check_rate_limit(&input.0.user_id).await?;
         let memory = self.memory.read().await;
-        let limit = input.0.limit.unwrap_or(5);
-        match memory.search(&input.0.user_id, &input.0.query, limit).await {
+        let limit = self.clamp_limit(input.0.limit.unwrap_or(5));
+        let results = match input.0.min_score {
+            Some(min_score) => {
+                memory
+                    .search_with_min_score(&input.0.user_id, &input.0.query, limit, min_score)
+                    .await
+            }
+            None => memory.search(&input.0.user_id, &input.0.query, limit).await,
+        };
+        match results {
             Ok(results) => {
                 let responses: Vec<SearchResponse> =
                     results.into_iter().map(|r| r.into()).collect();
-                let json = serde_json::to_string_pretty(&responses)
-                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                let json = render_results(&responses, input.0.format.as_deref())?;
                 Ok(CallToolResult::success(vec![Content::text(json)]))
             }
-            Err(e) => Err(McpError::internal_error(e.to_string(), None)),
+            Err(e) => Err(to_mcp_error(e)),
         }
     }
 
@@ -200,7 +532,7 @@ impl MemoryMcpServer {
                     .map_err(|e| McpError::internal_error(e.to_string(), None))?;
                 Ok(CallToolResult::success(vec![Content::text(json)]))
             }
-            Err(e) => Err(McpError::internal_error(e.to_string(), None)),
+            Err(e) => Err(to_mcp_error(e)),
         }
     }
 
@@ -216,7 +548,24 @@ impl MemoryMcpServer {
                 "Memory {} deleted successfully",
                 input.0.memory_id
             ))])),
-            Err(e) => Err(McpError::internal_error(e.to_string(), None)),
+            Err(e) => Err(to_mcp_error(e)),
+        }
+    }
+
+    /// Get a single memory by ID
+    #[tool(description = "Retrieve a single memory by its ID, including its metadata. Returns null if it does not exist.")]
+    async fn get_memory(
+        &self,
+        input: Parameters<GetMemoryInput>,
+    ) -> Result<CallToolResult, McpError> {
+        let memory = self.memory.read().await;
+        match memory.get(&input.0.memory_id).await {
+            Ok(item) => {
+                let json = serde_json::to_string_pretty(&item)
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json)]))
+            }
+            Err(e) => Err(to_mcp_error(e)),
         }
     }
 
@@ -226,16 +575,40 @@ impl MemoryMcpServer {
         &self,
         input: Parameters<GetAllMemoriesInput>,
     ) -> Result<CallToolResult, McpError> {
+        self.check_rate_limit(&input.0.user_id).await?;
         let memory = self.memory.read().await;
         match memory.get_all(&input.0.user_id).await {
             Ok(items) => {
                 let responses: Vec<MemoryResponse> =
                     items.into_iter().map(|i| i.into()).collect();
-                let json = serde_json::to_string_pretty(&responses)
+                let json = render_results(&responses, input.0.format.as_deref())?;
+                Ok(CallToolResult::success(vec![Content::text(json)]))
+            }
+            Err(e) => Err(to_mcp_error(e)),
+        }
+    }
+
+    /// Count a user's memories by type
+    #[tool(description = "Get memory statistics for a user: the total number of memories and the count per memory type.")]
+    async fn memory_stats(
+        &self,
+        input: Parameters<MemoryStatsInput>,
+    ) -> Result<CallToolResult, McpError> {
+        self.check_rate_limit(&input.0.user_id).await?;
+        let memory = self.memory.read().await;
+        match memory.count_by_type(&input.0.user_id).await {
+            Ok(counts) => {
+                let by_type: BTreeMap<String, usize> = counts.into_iter().collect();
+                let response = MemoryStatsResponse {
+                    user_id: input.0.user_id,
+                    total: by_type.values().sum(),
+                    by_type,
+                };
+                let json = serde_json::to_string_pretty(&response)
                     .map_err(|e| McpError::internal_error(e.to_string(), None))?;
                 Ok(CallToolResult::success(vec![Content::text(json)]))
             }
-            Err(e) => Err(McpError::internal_error(e.to_string(), None)),
+            Err(e) => Err(to_mcp_error(e)),
         }
     }
 }
@@ -267,10 +640,351 @@ mod tests {
     use super::*;
     use rmcp::ServerHandler;
 
+    /// Extract the text payload of a tool result
+    fn result_text(result: &CallToolResult) -> String {
+        let value = serde_json::to_value(result).unwrap();
+        value["content"][0]["text"].as_str().unwrap().to_string()
+    }
+
     #[tokio::test]
     async fn test_mcp_server_creation() {
         let server = MemoryMcpServer::new();
         let info = server.get_info();
         assert!(info.instructions.is_some());
     }
+
+    #[tokio::test]
+    async fn test_add_memory_idempotency_key() {
+        let server = MemoryMcpServer::new();
+        let input = AddMemoryInput {
+            user_id: "user_1".to_string(),
+            content: "I like coffee".to_string(),
+            memory_type: None,
+            idempotency_key: Some("request-1".to_string()),
+        };
+
+        let first = server.add_memory(Parameters(input.clone())).await.unwrap();
+        let second = server.add_memory(Parameters(input)).await.unwrap();
+
+        let first: MemoryResponse = serde_json::from_str(&result_text(&first)).unwrap();
+        let second: MemoryResponse = serde_json::from_str(&result_text(&second)).unwrap();
+        assert_eq!(first.id, second.id);
+
+        let memory = server.memory.read().await;
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_scoped_to_user() {
+        let server = MemoryMcpServer::new();
+        let add = |user_id: &str, content: &str| AddMemoryInput {
+            user_id: user_id.to_string(),
+            content: content.to_string(),
+            memory_type: None,
+            idempotency_key: Some("request-1".to_string()),
+        };
+
+        let first = server.add_memory(Parameters(add("user_1", "I like coffee"))).await.unwrap();
+        let other = server.add_memory(Parameters(add("user_2", "I like tea"))).await.unwrap();
+
+        let first: MemoryResponse = serde_json::from_str(&result_text(&first)).unwrap();
+        let other: MemoryResponse = serde_json::from_str(&result_text(&other)).unwrap();
+        assert_ne!(first.id, other.id);
+        assert_eq!(other.user_id, "user_2");
+        assert_eq!(other.content, "I like tea");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_idempotent_adds() {
+        let server = MemoryMcpServer::new();
+        let input = AddMemoryInput {
+            user_id: "user_1".to_string(),
+            content: "I like coffee".to_string(),
+            memory_type: None,
+            idempotency_key: Some("request-1".to_string()),
+        };
+
+        let (first, second) = tokio::join!(
+            server.add_memory(Parameters(input.clone())),
+            server.add_memory(Parameters(input)),
+        );
+        let first: MemoryResponse = serde_json::from_str(&result_text(&first.unwrap())).unwrap();
+        let second: MemoryResponse = serde_json::from_str(&result_text(&second.unwrap())).unwrap();
+        assert_eq!(first.id, second.id);
+
+        let memory = server.memory.read().await;
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 1);
+        assert!(server.idempotency.lock().await.in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let server = MemoryMcpServer::new();
+        let input = AddMemoryInput {
+            user_id: "user_1".to_string(),
+            content: "   ".to_string(),
+            memory_type: None,
+            idempotency_key: None,
+        };
+
+        let err = server.add_memory(Parameters(input)).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(err.data, Some(serde_json::json!({ "kind": "invalid_argument" })));
+
+        let err = to_mcp_error(Error::not_found("Memory not found: x"));
+        assert_eq!(err.code, ErrorCode::RESOURCE_NOT_FOUND);
+        let err = to_mcp_error(Error::vector_store("down"));
+        assert_eq!(err.code, ErrorCode::INTERNAL_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_user() {
+        let server = MemoryMcpServer::new().with_rate_limit(0.001, 2).unwrap();
+        let add = |user_id: &str, content: &str| AddMemoryInput {
+            user_id: user_id.to_string(),
+            content: content.to_string(),
+            memory_type: None,
+            idempotency_key: None,
+        };
+
+        server.add_memory(Parameters(add("user_1", "I like coffee"))).await.unwrap();
+        server.add_memory(Parameters(add("user_1", "I like tea"))).await.unwrap();
+        let err = server
+            .add_memory(Parameters(add("user_1", "I play chess")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(err.data, Some(serde_json::json!({ "kind": "rate_limited" })));
+
+        server.add_memory(Parameters(add("user_2", "I like coffee"))).await.unwrap();
+        let memory = server.memory.read().await;
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let mut limiter = RateLimiter::new(1000.0, 1);
+        assert!(limiter.try_acquire("user_1"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(limiter.try_acquire("user_1"));
+    }
+
+    #[test]
+    fn test_rate_limiter_prunes_full_buckets() {
+        let mut limiter = RateLimiter::new(1000.0, 1);
+        assert!(limiter.try_acquire("user_1"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(limiter.try_acquire("user_2"));
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key("user_2"));
+    }
+
+    #[test]
+    fn test_rate_limit_rejects_invalid_rate() {
+        for rps in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let result = MemoryMcpServer::new().with_rate_limit(rps, 1);
+            assert!(matches!(result, Err(Error::ConfigError(_))));
+        }
+    }
+
+    #[test]
+    fn test_idempotency_cache_expiry() {
+        let mut cache = IdempotencyCache::new(2, Duration::from_secs(0));
+        let response = MemoryResponse {
+            id: "id".to_string(),
+            user_id: "user_1".to_string(),
+            content: "content".to_string(),
+            memory_type: "general".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        let key = ("user_1".to_string(), "key".to_string());
+        cache.insert(key.clone(), response);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_memory_limit() {
+        let server = MemoryMcpServer::new().with_max_limit(3);
+        for i in 0..5 {
+            let input = AddMemoryInput {
+                user_id: "user_1".to_string(),
+                content: format!("I like coffee number {}", i),
+                memory_type: None,
+                idempotency_key: None,
+            };
+            server.add_memory(Parameters(input)).await.unwrap();
+        }
+
+        let search = |limit| SearchMemoryInput {
+            user_id: "user_1".to_string(),
+            query: "coffee".to_string(),
+            limit: Some(limit),
+            format: None,
+            min_score: None,
+        };
+
+        let result = server.search_memory(Parameters(search(10000))).await.unwrap();
+        let responses: Vec<SearchResponse> = serde_json::from_str(&result_text(&result)).unwrap();
+        assert_eq!(responses.len(), 3);
+
+        assert!(server.search_memory(Parameters(search(0))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_memory_min_score() {
+        let server = MemoryMcpServer::new();
+        for content in ["I like coffee", "I walk the dog", "I live in Paris"] {
+            let input = AddMemoryInput {
+                user_id: "user_1".to_string(),
+                content: content.to_string(),
+                memory_type: None,
+                idempotency_key: None,
+            };
+            server.add_memory(Parameters(input)).await.unwrap();
+        }
+
+        let search = |min_score| SearchMemoryInput {
+            user_id: "user_1".to_string(),
+            query: "I like coffee".to_string(),
+            limit: Some(10),
+            format: None,
+            min_score,
+        };
+
+        let result = server.search_memory(Parameters(search(None))).await.unwrap();
+        let unfiltered: Vec<SearchResponse> = serde_json::from_str(&result_text(&result)).unwrap();
+        let result = server.search_memory(Parameters(search(Some(0.8)))).await.unwrap();
+        let filtered: Vec<SearchResponse> = serde_json::from_str(&result_text(&result)).unwrap();
+
+        assert!(filtered.len() < unfiltered.len());
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].memory.content, "I like coffee");
+    }
+
+    #[tool_router(router = extra_router)]
+    impl MemoryMcpServer {
+        #[tool(description = "Count a user's memories")]
+        async fn count_memories(
+            &self,
+            Parameters(input): Parameters<GetAllMemoriesInput>,
+        ) -> Result<CallToolResult, McpError> {
+            let memory = self.memory.read().await;
+            let count = memory
+                .get_all(&input.user_id)
+                .await
+                .map_err(to_mcp_error)?
+                .len();
+            Ok(CallToolResult::success(vec![Content::text(count.to_string())]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_handle_and_extra_router() {
+        let server = MemoryMcpServer::new().with_extra_router(MemoryMcpServer::extra_router());
+
+        let handle = server.memory();
+        handle
+            .read()
+            .await
+            .add("user_1", "I like coffee", None)
+            .await
+            .unwrap();
+
+        let tools: Vec<String> = server
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert!(tools.contains(&"count_memories".to_string()));
+        assert!(tools.contains(&"add_memory".to_string()));
+
+        let input = GetAllMemoriesInput {
+            user_id: "user_1".to_string(),
+            format: None,
+        };
+        let result = server.count_memories(Parameters(input)).await.unwrap();
+        assert_eq!(result_text(&result), "1");
+    }
+
+    #[tokio::test]
+    async fn test_memory_stats() {
+        let server = MemoryMcpServer::new();
+        for (content, memory_type) in [
+            ("I live in Paris", "fact"),
+            ("I work remotely", "fact"),
+            ("I like tea", "preference"),
+        ] {
+            let input = AddMemoryInput {
+                user_id: "user_1".to_string(),
+                content: content.to_string(),
+                memory_type: Some(memory_type.to_string()),
+                idempotency_key: None,
+            };
+            server.add_memory(Parameters(input)).await.unwrap();
+        }
+
+        let input = MemoryStatsInput {
+            user_id: "user_1".to_string(),
+        };
+        let result = server.memory_stats(Parameters(input)).await.unwrap();
+        let stats: MemoryStatsResponse = serde_json::from_str(&result_text(&result)).unwrap();
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.by_type.get("fact"), Some(&2));
+        assert_eq!(stats.by_type.get("preference"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_get_memory() {
+        let server = MemoryMcpServer::new();
+        let input = AddMemoryInput {
+            user_id: "user_1".to_string(),
+            content: "I like coffee".to_string(),
+            memory_type: None,
+            idempotency_key: None,
+        };
+        let added = server.add_memory(Parameters(input)).await.unwrap();
+        let added: MemoryResponse = serde_json::from_str(&result_text(&added)).unwrap();
+
+        let get = |memory_id: &str| GetMemoryInput {
+            memory_id: memory_id.to_string(),
+        };
+        let result = server.get_memory(Parameters(get(&added.id))).await.unwrap();
+        let fetched: MemoryItem = serde_json::from_str(&result_text(&result)).unwrap();
+        assert_eq!(fetched.content, "I like coffee");
+
+        let result = server.get_memory(Parameters(get("missing"))).await.unwrap();
+        assert_eq!(result_text(&result), "null");
+    }
+
+    #[tokio::test]
+    async fn test_get_all_memories_ndjson() {
+        let server = MemoryMcpServer::new();
+        for content in ["I like coffee", "I live in Paris", "I have a cat"] {
+            let input = AddMemoryInput {
+                user_id: "user_1".to_string(),
+                content: content.to_string(),
+                memory_type: None,
+                idempotency_key: None,
+            };
+            server.add_memory(Parameters(input)).await.unwrap();
+        }
+
+        let get_all = |format: &str| GetAllMemoriesInput {
+            user_id: "user_1".to_string(),
+            format: Some(format.to_string()),
+        };
+        let result = server.get_all_memories(Parameters(get_all("ndjson"))).await.unwrap();
+        let text = result_text(&result);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            let response: MemoryResponse = serde_json::from_str(line).unwrap();
+            assert_eq!(response.user_id, "user_1");
+        }
+
+        assert!(server.get_all_memories(Parameters(get_all("xml"))).await.is_err());
+    }
 }
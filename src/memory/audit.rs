@@ -0,0 +1,154 @@
+//! Memory access audit trail
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::context::OperationContext;
+
+/// Audited memory operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    /// Memory added
+    Add,
+    /// Memories searched
+    Search,
+    /// Memory updated
+    Update,
+    /// Memory deleted
+    Delete,
+}
+
+/// Audit event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Operation performed
+    pub operation: AuditOperation,
+    /// Memory ID (absent for searches)
+    pub memory_id: Option<String>,
+    /// User ID
+    pub user_id: Option<String>,
+    /// Event timestamp (RFC 3339)
+    pub timestamp: String,
+    /// Trace ID of the current operation context
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+impl AuditEvent {
+    /// Create an event timestamped now, tagged with the current trace ID
+    pub fn new(
+        operation: AuditOperation,
+        memory_id: Option<String>,
+        user_id: Option<String>,
+    ) -> Self {
+        Self {
+            operation,
+            memory_id,
+            user_id,
+            timestamp: Utc::now().to_rfc3339(),
+            trace_id: OperationContext::current().and_then(|c| c.trace_id),
+        }
+    }
+}
+
+/// Destination for audit events
+///
+/// Sinks must not fail the audited operation; errors should be logged.
+pub trait AuditSink: Send + Sync {
+    /// Record an event
+    fn record(&self, event: AuditEvent);
+}
+
+/// Audit sink that discards events
+pub struct NoopAuditSink;
+
+impl AuditSink for NoopAuditSink {
+    fn record(&self, _event: AuditEvent) {}
+}
+
+/// Audit sink appending events as JSON lines to a file
+pub struct JsonlFileAuditSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonlFileAuditSink {
+    /// Create a sink writing to `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl AuditSink for JsonlFileAuditSink {
+    fn record(&self, event: AuditEvent) {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let result = serde_json::to_string(&event)
+            .map_err(|e| e.to_string())
+            .and_then(|line| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .and_then(|mut file| writeln!(file, "{}", line))
+                    .map_err(|e| e.to_string())
+            });
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to write audit event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_file_sink() {
+        let path = std::env::temp_dir().join(format!("audit_{}.jsonl", uuid::Uuid::new_v4()));
+        let sink = JsonlFileAuditSink::new(&path);
+
+        sink.record(AuditEvent::new(
+            AuditOperation::Add,
+            Some("id_1".to_string()),
+            Some("user_1".to_string()),
+        ));
+        sink.record(AuditEvent::new(
+            AuditOperation::Search,
+            None,
+            Some("user_1".to_string()),
+        ));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<AuditEvent> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].operation, AuditOperation::Add);
+        assert_eq!(events[1].operation, AuditOperation::Search);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_event_carries_trace_id() {
+        let context = OperationContext::new().with_trace_id("trace-123".to_string());
+        let event = context
+            .scope(async { AuditEvent::new(AuditOperation::Search, None, None) })
+            .await;
+        assert_eq!(event.trace_id.as_deref(), Some("trace-123"));
+
+        let event = AuditEvent::new(AuditOperation::Search, None, None);
+        assert!(event.trace_id.is_none());
+    }
+}
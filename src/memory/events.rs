@@ -0,0 +1,39 @@
+//! In-process notifications of memory changes
+
+use serde::{Deserialize, Serialize};
+
+/// Number of events buffered per subscriber before it starts missing events
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Change to a stored memory, emitted after the operation succeeds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MemoryEvent {
+    /// Memory added
+    Added {
+        memory_id: String,
+        user_id: Option<String>,
+    },
+    /// Memory content or metadata updated
+    Updated {
+        memory_id: String,
+        user_id: Option<String>,
+    },
+    /// Memory deleted
+    Deleted {
+        memory_id: String,
+        /// Owning user, absent for bulk deletes
+        user_id: Option<String>,
+    },
+}
+
+impl MemoryEvent {
+    /// ID of the changed memory
+    pub fn memory_id(&self) -> &str {
+        match self {
+            Self::Added { memory_id, .. }
+            | Self::Updated { memory_id, .. }
+            | Self::Deleted { memory_id, .. } => memory_id,
+        }
+    }
+}
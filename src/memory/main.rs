@@ -1,21 +1,119 @@
 //! Main Memory implementation
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
 
-use crate::config::MemoryConfig;
-use crate::Result;
-use crate::vector_store::VectorStoreBase;
-use crate::embeddings::EmbedderBase;
+use crate::config::{CollectionStrategy, MemoryConfig};
+use crate::{Error, Result};
+use crate::vector_store::{CollectionConfig, ScoreNormalizer, TimeoutStore, VectorStoreBase};
+use crate::embeddings::{EmbedderBase, PrefixingEmbedder, TimeoutEmbedder};
+use crate::filtering::{Query, QueryResult};
+use crate::graph::{GraphNode, GraphRelationship, GraphStoreBase, RelationType};
+use crate::llm::{
+    Classification, ExtractedFact, GenerationParams, LlmBase, PromptManager, ResponseFormat,
+};
+use crate::llm::classification::TEXT_FALLBACK_CONFIDENCE;
+use crate::rerank::Reranker;
+use crate::utils::{DefaultTokenizer, Tokenizer};
 
-use super::{MemoryBase, MemoryItem, SearchResultItem};
+use super::audit::{AuditEvent, AuditOperation, AuditSink, NoopAuditSink};
+use super::batch::BatchResult;
+use super::dedup::Deduplicator;
+use super::events::{MemoryEvent, EVENT_CHANNEL_CAPACITY};
+use super::postprocess::{NoopPostProcessor, ResultPostProcessor};
+use super::redact::Redactor;
+use super::scoring::{ScoringConfig, DEFAULT_FEEDBACK_WEIGHT};
+use super::taxonomy::TypeTaxonomy;
+use super::snapshot::MemorySnapshot;
+use super::{
+    AddOptions, ExtractionResult, MemoryBase, MemoryCluster, MemoryDiff, MemoryField, MemoryItem,
+    ProjectedResult, RankExplanation, SearchCursor, SearchResultItem,
+};
+
+/// Metadata key holding the accumulated relevance feedback of a memory
+pub const FEEDBACK_SCORE_KEY: &str = "feedback_score";
+
+/// Metadata key holding the importance of a memory (0-1)
+pub const IMPORTANCE_KEY: &str = "importance";
+
+/// Metadata key holding when a memory was last accessed (RFC 3339)
+pub const LAST_ACCESSED_KEY: &str = "last_accessed_at";
+
+/// Metadata key holding when forgetting was last applied to a memory (RFC 3339)
+pub const FORGOTTEN_AT_KEY: &str = "forgotten_at";
+
+/// Importance added to a memory each time it is accessed
+pub const ACCESS_REINFORCEMENT: f32 = 0.1;
+
+/// Metadata key holding the detected ISO 639-3 language of a memory
+pub const LANG_KEY: &str = "lang";
+
+/// Metadata key holding the LLM confidence of an LLM-derived memory
+pub const LLM_CONFIDENCE_KEY: &str = "llm_confidence";
+
+/// Memory type given to LLM-derived memories below `min_llm_confidence`
+/// when downgrading is enabled
+pub const UNVERIFIED_MEMORY_TYPE: &str = "unverified";
+
+/// Largest edit distance at which a collection is suggested for a missing one
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// Number of collections suggested for a missing one
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Importance assumed for memories without an explicit importance
+pub const DEFAULT_IMPORTANCE: f32 = 0.5;
+
+
+/// Metadata key flagging search results pulled in through graph relationships
+pub const GRAPH_DERIVED_KEY: &str = "graph_derived";
+
+/// Score multiplier applied per graph hop away from a vector search hit
+const GRAPH_SCORE_DECAY: f32 = 0.5;
+
+/// Candidate multiplier used by searches that filter or re-rank candidates
+const CANDIDATE_OVERFETCH: usize = 3;
+
+/// Rank offset of reciprocal rank fusion; damps the weight of top ranks
+const RRF_K: f32 = 60.0;
+
+/// Name prefix of the per-embedder collections used by `search_ensemble`
+const ENSEMBLE_PREFIX: &str = "ensemble_";
+
+/// Text embedded to fingerprint an ensemble embedder
+const ENSEMBLE_PROBE: &str = "memory-rs ensemble embedder probe";
+
+/// Memories read from the store per page by `export_to_writer`
+const EXPORT_PAGE_SIZE: usize = 100;
+
+/// Results inspected per sample query when suggesting a threshold
+const THRESHOLD_SAMPLE_SIZE: usize = 50;
 
 /// Main Memory implementation
 pub struct Memory {
     config: MemoryConfig,
     vector_store: Arc<dyn VectorStoreBase>,
     embedder: Arc<dyn EmbedderBase>,
+    /// Memory ID -> collection name index
+    id_index: RwLock<HashMap<String, String>>,
+    /// Memory type hierarchy
+    taxonomy: TypeTaxonomy,
+    /// Audit trail destination
+    audit: Arc<dyn AuditSink>,
+    /// LLM used to summarize memories evicted by the capacity limit
+    llm: Option<Arc<dyn LlmBase>>,
+    /// Redaction rules applied when `redact_pii` is enabled
+    redactor: Redactor,
+    /// Change notifications for subscribers
+    events: broadcast::Sender<MemoryEvent>,
+    /// Transformation applied to search results
+    post_processor: Arc<dyn ResultPostProcessor>,
+    /// Ensemble collection -> digest of the memories last synced into it
+    ensemble_synced: RwLock<HashMap<String, String>>,
 }
 
 impl Memory {
@@ -25,56 +123,482 @@ impl Memory {
         vector_store: Arc<dyn VectorStoreBase>,
         embedder: Arc<dyn EmbedderBase>,
     ) -> Self {
+        let vector_store: Arc<dyn VectorStoreBase> = match config.get_vector_store_timeout() {
+            Some(timeout) => Arc::new(TimeoutStore::new(vector_store, timeout)),
+            None => vector_store,
+        };
+        let embedder: Arc<dyn EmbedderBase> = match config.get_embedding_timeout() {
+            Some(timeout) => Arc::new(TimeoutEmbedder::new(embedder, timeout)),
+            None => embedder,
+        };
+        let embedder: Arc<dyn EmbedderBase> = match (
+            config.get_embedding_query_prefix(),
+            config.get_embedding_document_prefix(),
+        ) {
+            (None, None) => embedder,
+            (query_prefix, document_prefix) => Arc::new(
+                PrefixingEmbedder::new(embedder)
+                    .with_query_prefix(query_prefix.unwrap_or_default())
+                    .with_document_prefix(document_prefix.unwrap_or_default()),
+            ),
+        };
+
         Self {
             config,
             vector_store,
             embedder,
+            id_index: RwLock::new(HashMap::new()),
+            taxonomy: TypeTaxonomy::new(),
+            audit: Arc::new(NoopAuditSink),
+            llm: None,
+            redactor: Redactor::new(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            post_processor: Arc::new(NoopPostProcessor),
+            ensemble_synced: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the audit sink recording memory accesses
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Set the LLM used to consolidate memories over capacity
+    pub fn with_llm(mut self, llm: Arc<dyn LlmBase>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Set the redaction rules used when `redact_pii` is enabled
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Set the post-processor applied to search results
+    ///
+    /// It runs once on the final results of every search entry point, after
+    /// ranking. Searches derived from the base ranking (reranking, dedup,
+    /// answering) work on stored content and only process what they return.
+    pub fn with_post_processor(mut self, post_processor: Arc<dyn ResultPostProcessor>) -> Self {
+        self.post_processor = post_processor;
+        self
+    }
+
+    /// Redact content if PII redaction is enabled
+    fn redact<'a>(&self, content: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.config.is_pii_redaction_enabled() {
+            std::borrow::Cow::Owned(self.redactor.redact(content))
+        } else {
+            std::borrow::Cow::Borrowed(content)
         }
     }
 
+    /// Run an LLM call within the configured LLM timeout
+    async fn generate(
+        &self,
+        llm: &dyn LlmBase,
+        prompt: &str,
+        params: Option<GenerationParams>,
+    ) -> Result<String> {
+        match self.config.get_llm_timeout() {
+            Some(timeout) => tokio::time::timeout(timeout, llm.generate(prompt, params))
+                .await
+                .map_err(|_| Error::timeout(format!("LLM call timed out after {:?}", timeout)))?,
+            None => llm.generate(prompt, params).await,
+        }
+    }
+
+    /// Subscribe to memory change events
+    ///
+    /// Subscribers that fall more than `EVENT_CHANNEL_CAPACITY` events
+    /// behind miss the oldest ones (`RecvError::Lagged`).
+    pub fn subscribe(&self) -> broadcast::Receiver<MemoryEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record an audit event and notify subscribers of changes
+    fn audit(&self, operation: AuditOperation, memory_id: Option<&str>, user_id: Option<&str>) {
+        self.audit.record(AuditEvent::new(
+            operation,
+            memory_id.map(str::to_string),
+            user_id.map(str::to_string),
+        ));
+
+        let Some(memory_id) = memory_id.map(str::to_string) else {
+            return;
+        };
+        let user_id = user_id.map(str::to_string);
+        let event = match operation {
+            AuditOperation::Add => MemoryEvent::Added { memory_id, user_id },
+            AuditOperation::Update => MemoryEvent::Updated { memory_id, user_id },
+            AuditOperation::Delete => MemoryEvent::Deleted { memory_id, user_id },
+            AuditOperation::Search => return,
+        };
+        // Sending fails only when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Set the memory type taxonomy
+    pub fn with_taxonomy(mut self, taxonomy: TypeTaxonomy) -> Self {
+        self.taxonomy = taxonomy;
+        self
+    }
+
     /// Get collection name for user
     fn get_collection_name(&self, user_id: &str) -> String {
-        format!(
-            "{}_{}",
-            self.config.get_collection_prefix(),
-            user_id
-        )
+        self.get_scoped_collection_name(user_id, None, None)
     }
 
-    /// Initialize collection for user
-    async fn ensure_collection(&self, user_id: &str) -> Result<()> {
-        let collection_name = self.get_collection_name(user_id);
+    /// Get collection name for a user, agent and run according to the collection strategy
+    ///
+    /// Falls back to the per-user collection when the id required by the
+    /// strategy is not supplied.
+    fn get_scoped_collection_name(
+        &self,
+        user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
+    ) -> String {
+        let prefix = self.config.get_collection_prefix();
+        let scope = match self.config.get_collection_strategy() {
+            CollectionStrategy::PerUser => None,
+            CollectionStrategy::PerUserAgent => agent_id,
+            CollectionStrategy::PerUserRun => run_id,
+        };
+
+        match scope {
+            Some(scope) => format!("{}_{}_{}", prefix, user_id, scope),
+            None => format!("{}_{}", prefix, user_id),
+        }
+    }
+
+    /// Initialize a collection
+    ///
+    /// A configured distance metric is applied only when the collection is
+    /// created, so collections configured with `ensure_collection_with` keep
+    /// their settings.
+    async fn ensure_collection(&self, collection_name: &str) -> Result<()> {
         let dimension = self.config.get_vector_dimension();
 
+        let Some(metric) = self.config.get_distance_metric() else {
+            return self
+                .vector_store
+                .create_collection(collection_name, dimension)
+                .await;
+        };
+        if self.vector_store.collection_exists(collection_name).await? {
+            return Ok(());
+        }
         self.vector_store
-            .create_collection(&collection_name, dimension)
+            .create_collection_with(
+                collection_name,
+                CollectionConfig::new(dimension).with_distance(metric),
+            )
             .await
     }
-}
 
-#[async_trait]
-impl MemoryBase for Memory {
-    async fn add(
+    /// Prepare a collection for reading
+    ///
+    /// In strict mode a missing collection is a `NotFound` error naming the
+    /// closest existing collections, so a mistyped user ID is not silently
+    /// served from a new empty collection.
+    async fn ensure_readable_collection(&self, collection_name: &str) -> Result<()> {
+        if !self.config.is_strict_collections_enabled() {
+            return self.ensure_collection(collection_name).await;
+        }
+        if self.vector_store.collection_exists(collection_name).await? {
+            return Ok(());
+        }
+
+        // Stores without collection listing just get no suggestions
+        let mut candidates: Vec<(usize, String)> = self
+            .vector_store
+            .list_collections()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| (crate::utils::edit_distance(collection_name, &name), name))
+            .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .collect();
+        candidates.sort();
+
+        let mut message = format!("Collection not found: {}", collection_name);
+        if !candidates.is_empty() {
+            let names: Vec<String> = candidates
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|(_, name)| name)
+                .collect();
+            message.push_str(&format!(" (did you mean: {}?)", names.join(", ")));
+        }
+        Err(Error::not_found(message))
+    }
+
+    /// Normalizer for a collection's scores, when score normalization is enabled
+    async fn score_normalizer(&self, collection_name: &str) -> Option<ScoreNormalizer> {
+        if !self.config.is_score_normalization_enabled() {
+            return None;
+        }
+        Some(self.vector_store.score_normalizer(collection_name).await)
+    }
+
+    /// Reject vectors whose length differs from the embedder's dimension
+    ///
+    /// Catches mismatches before they reach a remote store as an opaque error.
+    fn check_dimension(&self, vector: &[f32], kind: &str) -> Result<()> {
+        let expected = self.embedder.dimension();
+        if vector.len() != expected {
+            return Err(Error::invalid_arg(format!(
+                "{} dim {} != expected {}",
+                kind,
+                vector.len(),
+                expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Initialize a user's collection with explicit configuration
+    pub async fn ensure_collection_with(
+        &self,
+        user_id: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        let collection_name = self.get_collection_name(user_id);
+        self.vector_store
+            .create_collection_with(&collection_name, config)
+            .await
+    }
+
+    /// Add a new memory scoped to an agent and/or run
+    pub async fn add_scoped(
         &self,
         user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
         content: &str,
         memory_type: Option<&str>,
     ) -> Result<MemoryItem> {
+        let options = AddOptions {
+            memory_type: memory_type.map(str::to_string),
+            agent_id: agent_id.map(str::to_string),
+            run_id: run_id.map(str::to_string),
+            attachments: Vec::new(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+            metadata: HashMap::new(),
+        };
+        self.add_with_options(user_id, content, options).await
+    }
+
+    /// Add a new memory with type, scope and attachments
+    ///
+    /// When `max_memories_per_user` is exceeded, the least important and
+    /// oldest memories are consolidated into one `insight` (see
+    /// `enforce_capacity`). A failed consolidation is logged; the memory
+    /// stays added.
+    pub async fn add_with_options(
+        &self,
+        user_id: &str,
+        content: &str,
+        options: AddOptions,
+    ) -> Result<MemoryItem> {
+        let agent_id = options.agent_id.clone();
+        let run_id = options.run_id.clone();
+        let memory = self.insert_memory(user_id, content, options).await?;
+
+        if let Some(max) = self.config.get_max_memories_per_user()
+            && let Err(e) = self
+                .enforce_capacity(
+                    user_id,
+                    agent_id.as_deref(),
+                    run_id.as_deref(),
+                    &memory.id,
+                    max,
+                )
+                .await
+        {
+            tracing::warn!("Failed to enforce memory capacity for user {}: {}", user_id, e);
+        }
+        Ok(memory)
+    }
+
+    /// Get a user's memories from every collection scoped to them
+    ///
+    /// Covers the per-agent and per-run collections of the collection
+    /// strategy as well as the per-user one. Stores that cannot list
+    /// collections only have the per-user collection read.
+    async fn get_all_user_scopes(&self, user_id: &str) -> Result<Vec<MemoryItem>> {
+        let base = self.get_collection_name(user_id);
+        let scoped = format!("{}_", base);
+
+        let collections = match self.vector_store.list_collections().await {
+            Ok(collections) => collections,
+            Err(Error::InvalidArgument(_)) if self.vector_store.collection_exists(&base).await? => {
+                vec![base.clone()]
+            }
+            Err(Error::InvalidArgument(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut memories = Vec::new();
+        for collection_name in collections {
+            if collection_name != base && !collection_name.starts_with(&scoped) {
+                continue;
+            }
+            // Another user's ID may extend this one, e.g. `user_1` and `user_1_b`
+            memories.extend(
+                self.vector_store
+                    .get_all(&collection_name)
+                    .await?
+                    .into_iter()
+                    .filter(|m| m.user_id == user_id)
+                    .map(MemoryItem::from),
+            );
+        }
+        Ok(memories)
+    }
+
+    /// Summarize and evict memories until the user holds at most `max`
+    ///
+    /// Memories are counted across all of the user's collections, or only
+    /// the per-user one on stores that cannot list collections.
+    /// Candidates are ranked by ascending importance, then age; the memory
+    /// just added is never evicted. The summary is stored in the new
+    /// memory's scope before the candidates are deleted, so a failure never
+    /// loses them unsummarized. Requires an LLM set with `with_llm`,
+    /// otherwise the limit is only logged.
+    async fn enforce_capacity(
+        &self,
+        user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
+        keep_id: &str,
+        max: usize,
+    ) -> Result<()> {
+        let mut candidates: Vec<MemoryItem> = self
+            .get_all_user_scopes(user_id)
+            .await?
+            .into_iter()
+            .filter(|m| m.id != keep_id)
+            .collect();
+
+        // The consolidated insight replaces the evicted memories, so one
+        // extra must go to land at the cap
+        let count = candidates.len() + 1;
+        if count <= max {
+            return Ok(());
+        }
+        let evict = (count - max + 1).min(candidates.len());
+        if evict < 2 {
+            tracing::warn!(
+                "Memory capacity {} too small to consolidate for user {}",
+                max,
+                user_id
+            );
+            return Ok(());
+        }
+        let Some(llm) = &self.llm else {
+            tracing::warn!(
+                "User {} exceeds memory capacity {} but no LLM is set",
+                user_id,
+                max
+            );
+            return Ok(());
+        };
+
+        candidates.sort_by(|a, b| {
+            let (a_weight, _) = weight_components(&a.metadata);
+            let (b_weight, _) = weight_components(&b.metadata);
+            a_weight
+                .total_cmp(&b_weight)
+                .then_with(|| a.created_at.cmp(&b.created_at))
+        });
+        candidates.truncate(evict);
+
+        let listing = candidates
+            .iter()
+            .map(|m| format!("- {}", m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut vars = HashMap::new();
+        vars.insert("memories".to_string(), listing);
+        let prompt = PromptManager::new().render("merge_memories", &vars)?;
+
+        let summary = self.generate(llm.as_ref(), &prompt, None).await?;
+        let summary = summary.trim();
+        if summary.is_empty() {
+            return Err(Error::llm("LLM returned an empty summary"));
+        }
+
+        let options = AddOptions {
+            memory_type: Some("insight".to_string()),
+            agent_id: agent_id.map(str::to_string),
+            run_id: run_id.map(str::to_string),
+            attachments: Vec::new(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+            metadata: HashMap::new(),
+        };
+        self.insert_memory(user_id, summary, options).await?;
+
+        for evicted in &candidates {
+            self.delete(&evicted.id).await?;
+        }
+        Ok(())
+    }
+
+    /// Store a memory without applying the capacity limit
+    async fn insert_memory(
+        &self,
+        user_id: &str,
+        content: &str,
+        options: AddOptions,
+    ) -> Result<MemoryItem> {
+        let agent_id = options.agent_id.as_deref();
+        let run_id = options.run_id.as_deref();
+
+        // Redact before embedding so vectors never encode the original
+        let redacted = self.redact(content);
+        let content = redacted.as_ref();
+
+        // Reject trivial content before paying for an embedding
+        let min_chars = self.config.get_min_content_chars();
+        if min_chars > 0 && content.trim().chars().count() < min_chars {
+            return Err(Error::invalid_arg("content too short"));
+        }
+
         // Ensure collection exists
-        self.ensure_collection(user_id).await?;
+        let collection_name = self.get_scoped_collection_name(user_id, agent_id, run_id);
+        self.ensure_collection(&collection_name).await?;
 
         // Create memory item
-        let memory = MemoryItem::new(
+        let mut memory = MemoryItem::new_with_strategy(
             user_id.to_string(),
             content.to_string(),
-            memory_type.unwrap_or("general").to_string(),
+            options.memory_type.unwrap_or_else(|| "general".to_string()),
+            self.config.get_id_strategy(),
         );
+        memory.agent_id = options.agent_id;
+        memory.run_id = options.run_id;
+        memory.attachments = options.attachments;
+        memory.tags = options.tags;
+        memory.acl = options.acl;
+        memory.metadata.extend(options.metadata);
+
+        if self.config.is_language_detection_enabled()
+            && let Some(lang) = crate::utils::detect_language(content)
+        {
+            memory.metadata.insert(LANG_KEY.to_string(), lang);
+        }
 
         // Generate embedding
-        let embedding = self.embedder.embed(content).await?;
+        let embedding = self.embedder.embed_document(content).await?;
+        self.check_dimension(&embedding, "vector")?;
 
         // Store in vector database
-        let collection_name = self.get_collection_name(user_id);
         self.vector_store
             .upsert(
                 &collection_name,
@@ -86,142 +610,4370 @@ impl MemoryBase for Memory {
             )
             .await?;
 
+        self.id_index
+            .write()
+            .await
+            .insert(memory.id.clone(), collection_name);
+
+        self.audit(AuditOperation::Add, Some(&memory.id), Some(user_id));
         Ok(memory)
     }
 
-    async fn search(
+    /// Search memories scoped to an agent and/or run
+    pub async fn search_scoped(
+        &self,
+        user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        let min_score = self.config.get_min_score();
+        let results = self
+            .search_above(user_id, agent_id, run_id, query, limit, min_score)
+            .await?;
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Search memories, dropping results whose raw score is below `min_score`
+    ///
+    /// `min_score` replaces the configured `min_score` for this query. It
+    /// applies to the store's raw similarity, before score normalization.
+    pub async fn search_with_min_score(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<SearchResultItem>> {
+        if !min_score.is_finite() {
+            return Err(Error::invalid_arg("min_score must be finite"));
+        }
+        let results = self
+            .search_above(user_id, None, None, query, limit, Some(min_score))
+            .await?;
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Search at `floor`, topping up with results down to `hard_floor`
+    ///
+    /// Returns up to `desired` results. Results at or above `floor` come
+    /// first; when there are fewer than `desired` of them, the remaining
+    /// slots are filled with the best candidates at or above `hard_floor`.
+    /// Both floors apply to raw scores, like `search_with_min_score`.
+    pub async fn search_with_fallback(
+        &self,
+        user_id: &str,
+        query: &str,
+        desired: usize,
+        floor: f32,
+        hard_floor: f32,
+    ) -> Result<Vec<SearchResultItem>> {
+        if !floor.is_finite() || !hard_floor.is_finite() {
+            return Err(Error::invalid_arg("floors must be finite"));
+        }
+        if hard_floor > floor {
+            return Err(Error::invalid_arg("hard_floor must not exceed floor"));
+        }
+
+        // Candidates are ranked by score, so the ones above `floor` lead and
+        // the rest of the page is the top-up
+        let results = self
+            .search_above(user_id, None, None, query, desired, Some(hard_floor))
+            .await?;
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Scoped search with an explicit score floor
+    async fn search_above(
         &self,
         user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
         query: &str,
         limit: usize,
+        min_score: Option<f32>,
     ) -> Result<Vec<SearchResultItem>> {
+        if limit == 0 {
+            return Err(Error::invalid_arg("limit must be greater than 0"));
+        }
+
         // Ensure collection exists
-        self.ensure_collection(user_id).await?;
+        let collection_name = self.get_scoped_collection_name(user_id, agent_id, run_id);
+        self.ensure_readable_collection(&collection_name).await?;
 
         // Generate query embedding
-        let query_embedding = self.embedder.embed(query).await?;
+        let query_embedding = self.embedder.embed_query(query).await?;
+        self.check_dimension(&query_embedding, "query")?;
 
-        // Search vector store
-        let collection_name = self.get_collection_name(user_id);
+        // Search vector store, dropping results below the score floor
         let results = self
             .vector_store
-            .search(&collection_name, query_embedding, limit, Some(0.0))
+            .search(&collection_name, query_embedding, limit, min_score)
             .await?;
 
         // Convert to SearchResultItem
+        let normalizer = self.score_normalizer(&collection_name).await;
         let search_results = results
             .into_iter()
-            .map(|result| {
-                let memory = MemoryItem {
-                    id: result.metadata.id,
-                    user_id: result.metadata.user_id,
-                    agent_id: result.metadata.agent_id,
-                    run_id: result.metadata.run_id,
-                    content: result.metadata.text,
-                    memory_type: result.metadata.memory_type,
-                    hash: String::new(), // Not needed for search results
-                    created_at: result.metadata.created_at,
-                    updated_at: result.metadata.updated_at,
-                    metadata: result.metadata.custom_metadata,
-                };
-
-                SearchResultItem {
-                    memory,
-                    score: result.score,
-                }
+            .map(|result| SearchResultItem {
+                memory: result.metadata.into(),
+                score: normalizer.map_or(result.score, |n| n.normalize(result.score)),
+                distance: result.distance,
             })
             .collect();
 
+        self.audit(AuditOperation::Search, None, Some(user_id));
         Ok(search_results)
     }
 
-    async fn update(
+    /// Search at the configured score floor, before post-processing
+    ///
+    /// Searches derived from the base ranking start here so they see
+    /// stored content, and process only their own final results.
+    async fn search_unprocessed(
         &self,
-        memory_id: &str,
-        content: &str,
-    ) -> Result<MemoryItem> {
-        // Find the memory across all collections by searching with the ID
-        // This is a simplified approach - in production you'd have an index
-        let collections = self.vector_store.count("").await; // Check if store is accessible
-        
-        // For now, we create a placeholder memory with updated content
-        // A proper implementation would need a memory_id -> collection mapping
-        let mut memory = MemoryItem::new(
-            "unknown".to_string(),
-            content.to_string(),
-            "general".to_string(),
-        );
-        memory.id = memory_id.to_string();
-        memory.updated_at = Utc::now().to_rfc3339();
+        user_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        let min_score = self.config.get_min_score();
+        self.search_above(user_id, None, None, query, limit, min_score)
+            .await
+    }
 
-        // Generate new embedding for updated content
-        let embedding = self.embedder.embed(content).await?;
+    /// Search until `want` results pass `keep`, or no candidates remain
+    ///
+    /// Fetches `want * CANDIDATE_OVERFETCH` candidates and doubles the fetch
+    /// only while too few pass, instead of ranking the whole collection.
+    /// Results are ordered by score, then ID.
+    async fn search_filtered(
+        &self,
+        user_id: &str,
+        query: &str,
+        want: usize,
+        keep: impl Fn(&SearchResultItem) -> bool,
+    ) -> Result<Vec<SearchResultItem>> {
+        let mut fetch = want.saturating_mul(CANDIDATE_OVERFETCH).max(1);
+        loop {
+            let mut results = self.search_unprocessed(user_id, query, fetch).await?;
+            let exhausted = results.len() < fetch;
+            sort_by_score_then_id(&mut results);
+            results.retain(|r| keep(r));
+            if results.len() >= want || exhausted {
+                results.truncate(want);
+                return Ok(results);
+            }
+            fetch = fetch.saturating_mul(2);
+        }
+    }
+
+    /// Search memories, returning only the requested fields
+    ///
+    /// Only the payload fields backing `projection` are requested from the
+    /// vector store, so remote backends transfer less data.
+    pub async fn search_projected(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        projection: &[MemoryField],
+    ) -> Result<Vec<ProjectedResult>> {
+        if limit == 0 {
+            return Err(Error::invalid_arg("limit must be greater than 0"));
+        }
 
-        // Note: Without knowing the collection, we can't update the vector store
-        // This would require maintaining an id -> collection index
-        let _ = embedding; // Suppress unused warning
-        let _ = collections;
+        let collection_name = self.get_collection_name(user_id);
+        self.ensure_readable_collection(&collection_name).await?;
 
-        Ok(memory)
-    }
+        let query_embedding = self.embedder.embed_query(query).await?;
+        self.check_dimension(&query_embedding, "query")?;
 
-    async fn delete(&self, memory_id: &str) -> Result<()> {
-        // Note: Without knowing the collection, we can't delete from vector store
-        // This would require maintaining an id -> collection index
-        // For now, we just acknowledge the request
-        let _ = memory_id;
-        Ok(())
+        let mut payload_fields: Vec<&str> =
+            projection.iter().filter_map(MemoryField::payload_field).collect();
+        payload_fields.sort_unstable();
+        payload_fields.dedup();
+
+        let min_score = self.config.get_min_score();
+        let results = self
+            .vector_store
+            .search_with_payload(
+                &collection_name,
+                query_embedding,
+                limit,
+                min_score,
+                &payload_fields,
+            )
+            .await?;
+
+        let normalizer = self.score_normalizer(&collection_name).await;
+        let hits = results
+            .into_iter()
+            .map(|result| {
+                let mut memory: MemoryItem = result.metadata.into();
+                memory.id = result.id;
+                SearchResultItem {
+                    memory,
+                    score: normalizer.map_or(result.score, |n| n.normalize(result.score)),
+                    distance: result.distance,
+                }
+            })
+            .collect();
+
+        // Project after processing so the processor sees whole results
+        let projected = self
+            .post_processor
+            .process(hits)
+            .into_iter()
+            .map(|hit| {
+                let metadata = hit.memory.to_vector_metadata();
+                ProjectedResult::project(hit.memory.id, hit.score, metadata, projection)
+            })
+            .collect();
+
+        self.audit(AuditOperation::Search, None, Some(user_id));
+        Ok(projected)
     }
 
-    async fn get_all(
+    /// Get all memories scoped to an agent and/or run
+    pub async fn get_all_scoped(
         &self,
         user_id: &str,
+        agent_id: Option<&str>,
+        run_id: Option<&str>,
     ) -> Result<Vec<MemoryItem>> {
         // Ensure collection exists
-        self.ensure_collection(user_id).await?;
+        let collection_name = self.get_scoped_collection_name(user_id, agent_id, run_id);
+        self.ensure_readable_collection(&collection_name).await?;
 
-        let collection_name = self.get_collection_name(user_id);
         let metadata_list = self.vector_store.get_all(&collection_name).await?;
 
-        let memories = metadata_list
-            .into_iter()
-            .map(|metadata| MemoryItem {
-                id: metadata.id,
-                user_id: metadata.user_id,
-                agent_id: metadata.agent_id,
-                run_id: metadata.run_id,
-                content: metadata.text,
-                memory_type: metadata.memory_type,
-                hash: String::new(),
-                created_at: metadata.created_at,
-                updated_at: metadata.updated_at,
-                metadata: metadata.custom_metadata,
-            })
-            .collect();
+        let memories = metadata_list.into_iter().map(MemoryItem::from).collect();
 
         Ok(memories)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::embeddings::EmbedderBase;
+    /// Get a user's memories whose custom metadata `key` equals `value`
+    ///
+    /// Exact match without an embedding call; the filter is pushed down to
+    /// the vector store.
+    pub async fn find_by_metadata(
+        &self,
+        user_id: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<MemoryItem>> {
+        let collection_name = self.get_collection_name(user_id);
+        self.ensure_collection(&collection_name).await?;
 
-    #[test]
-    fn test_collection_name() {
-        let config = MemoryConfig::new("memory.db".to_string());
+        let metadata_list = self
+            .vector_store
+            .find_by_metadata(&collection_name, key, value)
+            .await?;
 
-        let memory = Memory::new(
-            config,
-            Arc::new(MockVectorStore),
-            Arc::new(MockEmbedder),
-        );
+        Ok(metadata_list.into_iter().map(MemoryItem::from).collect())
+    }
 
-        assert_eq!(
-            memory.get_collection_name("user_123"),
-            "memory_user_123"
-        );
+    /// Wait until a user's earlier writes are visible to searches
+    ///
+    /// Calls `flush` on the user's collection. Use it after writing to a
+    /// store that buffers writes or is eventually consistent.
+    pub async fn await_consistency(&self, user_id: &str) -> Result<()> {
+        let collection_name = self.get_collection_name(user_id);
+        self.vector_store.flush(&collection_name).await
+    }
+
+    /// Write a user's memories to `writer` as JSON Lines
+    ///
+    /// Memories are read from the store one page at a time, so at most
+    /// `EXPORT_PAGE_SIZE` of them are held in memory. Returns the number of
+    /// memories written.
+    pub async fn export_to_writer<W: Write + Send>(&self, user_id: &str, mut writer: W) -> Result<usize> {
+        let collection_name = self.get_collection_name(user_id);
+        self.ensure_readable_collection(&collection_name).await?;
+
+        let write_error = |e: std::io::Error| Error::internal(format!("Failed to write export: {}", e));
+        let mut exported = 0;
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .vector_store
+                .scan(&collection_name, after.as_deref(), EXPORT_PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = Some(last.id.clone());
+
+            for metadata in page {
+                serde_json::to_writer(&mut writer, &MemoryItem::from(metadata))?;
+                writer.write_all(b"\n").map_err(write_error)?;
+                exported += 1;
+            }
+        }
+
+        writer.flush().map_err(write_error)?;
+        Ok(exported)
+    }
+
+    /// Snapshot the memories of every user in this instance's collections
+    pub async fn export_snapshot(&self) -> Result<MemorySnapshot> {
+        let prefix = format!("{}_", self.config.get_collection_prefix());
+        let mut snapshot = MemorySnapshot::new();
+
+        for collection_name in self.vector_store.list_collections().await? {
+            if !collection_name.starts_with(&prefix) {
+                continue;
+            }
+            let memories = self
+                .vector_store
+                .get_all(&collection_name)
+                .await?
+                .into_iter()
+                .map(MemoryItem::from)
+                .collect();
+            snapshot.collections.insert(collection_name, memories);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Restore a snapshot, re-embedding its memories into their original collections
+    ///
+    /// Existing memories with the same IDs are overwritten. Returns the
+    /// number of memories restored.
+    pub async fn import_snapshot(&self, snapshot: MemorySnapshot) -> Result<usize> {
+        let mut restored = 0;
+        for (collection_name, memories) in snapshot.collections {
+            self.ensure_collection(&collection_name).await?;
+            if memories.is_empty() {
+                continue;
+            }
+
+            let embeddings = self
+                .embedder
+                .embed_documents(memories.iter().map(|m| m.content.as_str()).collect())
+                .await?;
+            for embedding in &embeddings {
+                self.check_dimension(embedding, "vector")?;
+            }
+
+            let vectors = memories
+                .iter()
+                .zip(embeddings)
+                .map(|(m, embedding)| (m.id.clone(), embedding, m.to_vector_metadata()))
+                .collect();
+            self.vector_store.upsert(&collection_name, vectors).await?;
+
+            let mut index = self.id_index.write().await;
+            for memory in &memories {
+                index.insert(memory.id.clone(), collection_name.clone());
+            }
+            drop(index);
+            for memory in &memories {
+                self.audit(AuditOperation::Add, Some(&memory.id), Some(&memory.user_id));
+            }
+            restored += memories.len();
+        }
+
+        Ok(restored)
+    }
+
+    /// Move all of a user's memories to a new user ID
+    ///
+    /// Memories keep their IDs and are re-embedded into the new user's
+    /// collection, merging with any memories already there; the old
+    /// collection is then deleted. Only the per-user collection is migrated.
+    /// Returns the number of memories moved.
+    pub async fn rename_user(&self, old_id: &str, new_id: &str) -> Result<usize> {
+        if old_id == new_id {
+            return Err(Error::invalid_arg("new user ID must differ from the old one"));
+        }
+
+        let old_collection = self.get_collection_name(old_id);
+        if !self.vector_store.collection_exists(&old_collection).await? {
+            return Ok(0);
+        }
+        let memories = self.vector_store.get_all(&old_collection).await?;
+
+        let new_collection = self.get_collection_name(new_id);
+        self.ensure_collection(&new_collection).await?;
+
+        let ids: Vec<String> = memories.iter().map(|m| m.id.clone()).collect();
+        if !memories.is_empty() {
+            let embeddings = self
+                .embedder
+                .embed_documents(memories.iter().map(|m| m.text.as_str()).collect())
+                .await?;
+            for embedding in &embeddings {
+                self.check_dimension(embedding, "vector")?;
+            }
+
+            let vectors = memories
+                .into_iter()
+                .zip(embeddings)
+                .map(|(mut metadata, embedding)| {
+                    metadata.user_id = new_id.to_string();
+                    (metadata.id.clone(), embedding, metadata)
+                })
+                .collect();
+            self.vector_store.upsert(&new_collection, vectors).await?;
+        }
+
+        self.vector_store.delete_collection(&old_collection).await?;
+        self.drop_ensembles(&old_collection).await?;
+
+        let mut index = self.id_index.write().await;
+        for id in &ids {
+            index.insert(id.clone(), new_collection.clone());
+            self.audit(AuditOperation::Update, Some(id), Some(new_id));
+        }
+
+        Ok(ids.len())
+    }
+
+    /// Delete this instance's collections with no activity for `inactive_for`
+    ///
+    /// A collection's last activity is the latest `updated_at` or
+    /// `last_accessed_at` of its memories. Empty collections are kept, since
+    /// they carry no activity to judge by. Returns the number of collections
+    /// deleted.
+    pub async fn prune_inactive_collections(&self, inactive_for: std::time::Duration) -> Result<usize> {
+        let inactive_for = chrono::Duration::from_std(inactive_for)
+            .map_err(|e| Error::invalid_arg(format!("Invalid inactivity period: {}", e)))?;
+        let cutoff = Utc::now() - inactive_for;
+        let prefix = format!("{}_", self.config.get_collection_prefix());
+
+        let mut pruned = 0;
+        for collection_name in self.vector_store.list_collections().await? {
+            if !collection_name.starts_with(&prefix) {
+                continue;
+            }
+
+            let memories = self.vector_store.get_all(&collection_name).await?;
+            let last_activity = memories
+                .iter()
+                .flat_map(|m| [Some(&m.updated_at), m.custom_metadata.get(LAST_ACCESSED_KEY)])
+                .flatten()
+                .filter_map(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                .map(|ts| ts.with_timezone(&Utc))
+                .max();
+            let Some(last_activity) = last_activity else {
+                continue;
+            };
+            if last_activity >= cutoff {
+                continue;
+            }
+
+            self.vector_store.delete_collection(&collection_name).await?;
+            self.drop_ensembles(&collection_name).await?;
+            let mut index = self.id_index.write().await;
+            for memory in &memories {
+                index.remove(&memory.id);
+                self.audit(AuditOperation::Delete, Some(&memory.id), Some(&memory.user_id));
+            }
+            pruned += 1;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Get a user's memories carrying any (or, with `match_all`, all) of the tags
+    pub async fn search_by_tags(
+        &self,
+        user_id: &str,
+        tags: &[&str],
+        match_all: bool,
+    ) -> Result<Vec<MemoryItem>> {
+        if tags.is_empty() {
+            return Err(Error::invalid_arg("tags must not be empty"));
+        }
+
+        Ok(self
+            .get_all(user_id)
+            .await?
+            .into_iter()
+            .filter(|m| {
+                if match_all {
+                    tags.iter().all(|t| m.has_tag(t))
+                } else {
+                    tags.iter().any(|t| m.has_tag(t))
+                }
+            })
+            .collect())
+    }
+
+    /// Search one page of results, resuming after `cursor`
+    ///
+    /// Returns the page and a cursor for the next page, or `None` when no
+    /// results remain. Ties in score are ordered by ID, so pages neither
+    /// repeat nor skip results.
+    pub async fn search_paginated(
+        &self,
+        user_id: &str,
+        query: &str,
+        page_size: usize,
+        cursor: Option<SearchCursor>,
+    ) -> Result<(Vec<SearchResultItem>, Option<SearchCursor>)> {
+        if page_size == 0 {
+            return Err(Error::invalid_arg("page_size must be greater than 0"));
+        }
+
+        // One extra result tells whether another page follows
+        let mut page = self
+            .search_filtered(user_id, query, page_size + 1, |r| {
+                cursor.as_ref().is_none_or(|c| c.precedes(r))
+            })
+            .await?;
+
+        let next = if page.len() > page_size {
+            page.truncate(page_size);
+            page.last().map(|r| SearchCursor {
+                score: r.score,
+                id: r.memory.id.clone(),
+            })
+        } else {
+            None
+        };
+        Ok((self.post_processor.process(page), next))
+    }
+
+    /// Search another user's memories shared with the requesting user
+    ///
+    /// Returns the owner's memories matching the query that are readable
+    /// by `requesting_user`: all of them when it is the owner, otherwise
+    /// only those whose ACL lists it.
+    pub async fn search_shared(
+        &self,
+        requesting_user: &str,
+        owner_user: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        if requesting_user == owner_user {
+            let results = self.search_unprocessed(owner_user, query, limit).await?;
+            return Ok(self.post_processor.process(results));
+        }
+        if limit == 0 {
+            return Err(Error::invalid_arg("limit must be greater than 0"));
+        }
+
+        // Fetch more while unshared memories crowd out shared ones
+        let results = self
+            .search_filtered(owner_user, query, limit, |r| {
+                r.memory.is_readable_by(requesting_user)
+            })
+            .await?;
+
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Execute a filtering query over a user's memories
+    pub async fn run_query(&self, user_id: &str, query: Query) -> Result<QueryResult> {
+        let memories = self.get_all(user_id).await?;
+        Ok(query.execute(memories))
+    }
+
+    /// Count a user's memories per memory type
+    pub async fn count_by_type(&self, user_id: &str) -> Result<HashMap<String, usize>> {
+        let mut counts = HashMap::new();
+        for memory in self.get_all(user_id).await? {
+            *counts.entry(memory.memory_type).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Get a user's memories of a given type
+    ///
+    /// With `include_subtypes`, memories whose type descends from
+    /// `memory_type` in the taxonomy also match.
+    pub async fn search_by_type(
+        &self,
+        user_id: &str,
+        memory_type: &str,
+        include_subtypes: bool,
+    ) -> Result<Vec<MemoryItem>> {
+        let types = if include_subtypes {
+            self.taxonomy.descendants(memory_type)
+        } else {
+            HashSet::from([memory_type.to_string()])
+        };
+
+        let memories = self.get_all(user_id).await?;
+        Ok(memories
+            .into_iter()
+            .filter(|m| types.contains(&m.memory_type))
+            .collect())
+    }
+
+    /// Resolve the collection holding a memory
+    async fn locate(&self, memory_id: &str) -> Result<String> {
+        self.id_index
+            .read()
+            .await
+            .get(memory_id)
+            .cloned()
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))
+    }
+
+    /// Merge custom metadata into a stored memory without re-embedding it
+    pub async fn update_metadata(
+        &self,
+        memory_id: &str,
+        metadata: HashMap<String, String>,
+    ) -> Result<MemoryItem> {
+        self.merge_metadata(memory_id, metadata, true).await
+    }
+
+    /// Merge custom metadata into a stored memory
+    ///
+    /// `updated_at` is bumped only with `touch`. Background maintenance
+    /// leaves it alone, so it does not count as activity or change ETags.
+    async fn merge_metadata(
+        &self,
+        memory_id: &str,
+        metadata: HashMap<String, String>,
+        touch: bool,
+    ) -> Result<MemoryItem> {
+        let collection_name = self.locate(memory_id).await?;
+        let mut stored = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+
+        stored.custom_metadata.extend(metadata);
+        if touch {
+            stored.updated_at = Utc::now().to_rfc3339();
+        }
+
+        self.vector_store
+            .update_metadata(&collection_name, memory_id, stored.clone())
+            .await?;
+
+        self.audit(AuditOperation::Update, Some(memory_id), Some(&stored.user_id));
+        Ok(stored.into())
+    }
+
+    /// Record whether a retrieved memory was helpful
+    ///
+    /// Positive feedback increments the memory's `feedback_score`, negative
+    /// feedback decrements it. The score is used by `search_weighted`.
+    pub async fn record_feedback(&self, memory_id: &str, positive: bool) -> Result<MemoryItem> {
+        let collection_name = self.locate(memory_id).await?;
+        let stored = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+
+        let current = stored
+            .custom_metadata
+            .get(FEEDBACK_SCORE_KEY)
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(0);
+        let updated = if positive { current + 1 } else { current - 1 };
+
+        let mut metadata = HashMap::new();
+        metadata.insert(FEEDBACK_SCORE_KEY.to_string(), updated.to_string());
+        self.update_metadata(memory_id, metadata).await
+    }
+
+    /// Record that a memory was accessed, reinforcing its importance
+    ///
+    /// Sets `last_accessed_at` to now and raises the importance by
+    /// `ACCESS_REINFORCEMENT`, up to 1.
+    pub async fn record_access(&self, memory_id: &str) -> Result<MemoryItem> {
+        let collection_name = self.locate(memory_id).await?;
+        let stored = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+
+        let reinforced = (importance(&stored.custom_metadata) + ACCESS_REINFORCEMENT).min(1.0);
+
+        let mut metadata = HashMap::new();
+        metadata.insert(IMPORTANCE_KEY.to_string(), reinforced.to_string());
+        metadata.insert(LAST_ACCESSED_KEY.to_string(), Utc::now().to_rfc3339());
+        self.update_metadata(memory_id, metadata).await
+    }
+
+    /// Decay the importance of a user's memories by time since last access
+    ///
+    /// Each importance is multiplied by `exp(-rate * days)`, where `days` is
+    /// the time since the memory was last accessed or decayed, whichever is
+    /// later, or since its creation when neither happened. Repeated passes
+    /// therefore do not compound. `updated_at` is left unchanged. Returns
+    /// the number of memories decayed.
+    pub async fn apply_forgetting(&self, user_id: &str, rate: f64) -> Result<usize> {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(Error::invalid_arg("rate must be a non-negative number"));
+        }
+
+        let now = Utc::now();
+        let mut decayed = 0;
+        for memory in self.get_all(user_id).await? {
+            let since = [LAST_ACCESSED_KEY, FORGOTTEN_AT_KEY]
+                .into_iter()
+                .filter_map(|key| memory.metadata.get(key))
+                .filter_map(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                .max()
+                .or_else(|| DateTime::parse_from_rfc3339(&memory.created_at).ok());
+            let Some(since) = since.map(|ts| ts.with_timezone(&Utc)) else {
+                continue;
+            };
+
+            let days = (now - since).num_seconds().max(0) as f64 / 86_400.0;
+            let factor = (-rate * days).exp() as f32;
+            let decayed_importance = importance(&memory.metadata) * factor;
+
+            let mut metadata = HashMap::new();
+            metadata.insert(IMPORTANCE_KEY.to_string(), decayed_importance.to_string());
+            metadata.insert(FORGOTTEN_AT_KEY.to_string(), now.to_rfc3339());
+            self.merge_metadata(&memory.id, metadata, false).await?;
+            decayed += 1;
+        }
+
+        Ok(decayed)
+    }
+
+    /// Delete up to `max` memories whose importance is below `floor`
+    ///
+    /// Least recently accessed memories go first; memories never accessed
+    /// count from their creation. Returns the deleted memory IDs.
+    pub async fn evict_lru(&self, user_id: &str, floor: f32, max: usize) -> Result<Vec<String>> {
+        let mut candidates: Vec<(String, String)> = self
+            .get_all(user_id)
+            .await?
+            .into_iter()
+            .filter(|memory| importance(&memory.metadata) < floor)
+            .map(|memory| {
+                let last_used = memory
+                    .metadata
+                    .get(LAST_ACCESSED_KEY)
+                    .cloned()
+                    .unwrap_or(memory.created_at);
+                (last_used, memory.id)
+            })
+            .collect();
+        candidates.sort_by_key(|(last_used, _)| {
+            DateTime::parse_from_rfc3339(last_used)
+                .map(|ts| ts.with_timezone(&Utc))
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        });
+        candidates.truncate(max);
+
+        let mut evicted = Vec::with_capacity(candidates.len());
+        for (_, id) in candidates {
+            self.delete(&id).await?;
+            evicted.push(id);
+        }
+        Ok(evicted)
+    }
+
+    /// Search memories, ranking by similarity weighted with importance and feedback
+    pub async fn search_weighted(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        self.search_scored(user_id, query, limit, ScoringConfig::weighted())
+            .await
+    }
+
+    /// Search memories, ranking every candidate once by the enabled scoring components
+    ///
+    /// The `limit * CANDIDATE_OVERFETCH` most similar memories are
+    /// candidates; see `ScoringConfig` for how the components combine.
+    /// Result scores are the combined scores.
+    pub async fn search_scored(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        scoring: ScoringConfig,
+    ) -> Result<Vec<SearchResultItem>> {
+        scoring.validate()?;
+        let candidates = limit.saturating_mul(CANDIDATE_OVERFETCH);
+
+        let tokenizer = DefaultTokenizer::new();
+        let query_tokens: HashSet<String> = if scoring.uses_keywords() {
+            tokenizer.tokenize(query).into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+
+        let now = Utc::now();
+        let mut results = self.search_unprocessed(user_id, query, candidates).await?;
+        for result in &mut results {
+            let mut relevance = result.score;
+            if scoring.uses_keywords() && !query_tokens.is_empty() {
+                let memory_tokens: HashSet<String> =
+                    tokenizer.tokenize(&result.memory.content).into_iter().collect();
+                let overlap =
+                    query_tokens.intersection(&memory_tokens).count() as f32 / query_tokens.len() as f32;
+                relevance = (1.0 - scoring.keyword_alpha) * relevance + scoring.keyword_alpha * overlap;
+            }
+
+            let (importance_multiplier, _) = weight_components(&result.memory.metadata);
+            let importance_factor = 1.0 + scoring.importance_weight * (importance_multiplier - 1.0);
+            let recency = scoring
+                .recency_half_life_days
+                .map_or(1.0, |h| recency_factor(&result.memory.created_at, now, h) as f32);
+            let feedback = result
+                .memory
+                .metadata
+                .get(FEEDBACK_SCORE_KEY)
+                .and_then(|v| v.parse::<f32>().ok())
+                .unwrap_or(0.0);
+
+            result.score =
+                relevance * importance_factor * recency + scoring.feedback_weight * feedback;
+        }
+
+        sort_by_score(&mut results);
+        results.truncate(limit);
+
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Search memories, rescoring over-fetched candidates with a reranker
+    ///
+    /// Result scores are the reranker's scores.
+    pub async fn search_cross_encoder(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        reranker: &dyn Reranker,
+    ) -> Result<Vec<SearchResultItem>> {
+        let mut results = self
+            .search_unprocessed(user_id, query, limit.saturating_mul(CANDIDATE_OVERFETCH))
+            .await?;
+        if results.is_empty() {
+            return Ok(results);
+        }
+
+        let docs = results.iter().map(|r| r.memory.content.clone()).collect();
+        let scores = reranker.rerank(query, docs).await?;
+        if scores.len() != results.len() {
+            return Err(Error::internal(format!(
+                "Reranker returned {} scores for {} documents",
+                scores.len(),
+                results.len()
+            )));
+        }
+
+        for (result, score) in results.iter_mut().zip(scores) {
+            result.score = score;
+        }
+        sort_by_score(&mut results);
+        results.truncate(limit);
+
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Search with several embedders and fuse their rankings
+    ///
+    /// Each embedder searches its own collection
+    /// `ensemble_<fingerprint>_<user collection>`. The fingerprint hashes the
+    /// embedder's dimension and its embedding of a fixed probe text, so a
+    /// swapped or reordered embedder never queries another model's vectors.
+    /// Collections are re-synced only when the user's memories changed. The
+    /// per-embedder rankings are combined by weighted reciprocal rank fusion
+    /// and the fused score is returned as the result score.
+    pub async fn search_ensemble(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        embedders: Vec<(Arc<dyn EmbedderBase>, f32)>,
+    ) -> Result<Vec<SearchResultItem>> {
+        if limit == 0 {
+            return Err(Error::invalid_arg("limit must be greater than 0"));
+        }
+        if embedders.is_empty() {
+            return Err(Error::invalid_arg("search_ensemble requires at least one embedder"));
+        }
+
+        let memories: HashMap<String, MemoryItem> = self
+            .get_all(user_id)
+            .await?
+            .into_iter()
+            .map(|m| (m.id.clone(), m))
+            .collect();
+        if memories.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let base = self.get_collection_name(user_id);
+        let digest = ensemble_digest(&memories);
+        let mut rankings = Vec::with_capacity(embedders.len());
+        for (embedder, weight) in &embedders {
+            let fingerprint = embedder_fingerprint(embedder.as_ref()).await?;
+            let collection_name = format!("{}{}_{}", ENSEMBLE_PREFIX, fingerprint, base);
+            self.sync_ensemble_collection(&collection_name, embedder.as_ref(), &memories, &digest)
+                .await?;
+
+            let query_vector = embedder.embed_query(query).await?;
+            let ids = self
+                .vector_store
+                .search(&collection_name, query_vector, memories.len(), None)
+                .await?
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            rankings.push((ids, *weight));
+        }
+
+        let fused = reciprocal_rank_fusion(&rankings)
+            .into_iter()
+            .filter_map(|(id, score)| {
+                memories.get(&id).map(|memory| SearchResultItem {
+                    memory: memory.clone(),
+                    score,
+                    distance: None,
+                })
+            })
+            .take(limit)
+            .collect();
+        Ok(self.post_processor.process(fused))
+    }
+
+    /// Make an ensemble collection hold exactly `memories`, embedded with `embedder`
+    ///
+    /// Skipped when the collection was last synced with memories of the same
+    /// `digest`.
+    async fn sync_ensemble_collection(
+        &self,
+        collection_name: &str,
+        embedder: &dyn EmbedderBase,
+        memories: &HashMap<String, MemoryItem>,
+        digest: &str,
+    ) -> Result<()> {
+        if self
+            .ensemble_synced
+            .read()
+            .await
+            .get(collection_name)
+            .is_some_and(|synced| synced == digest)
+            && self.vector_store.collection_exists(collection_name).await?
+        {
+            return Ok(());
+        }
+
+        self.vector_store
+            .create_collection(collection_name, embedder.dimension())
+            .await?;
+
+        let indexed: HashMap<String, String> = self
+            .vector_store
+            .get_all(collection_name)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m.text))
+            .collect();
+
+        let removed: Vec<String> = indexed
+            .keys()
+            .filter(|id| !memories.contains_key(*id))
+            .cloned()
+            .collect();
+        if !removed.is_empty() {
+            self.vector_store.delete(collection_name, removed).await?;
+        }
+
+        let stale: Vec<&MemoryItem> = memories
+            .values()
+            .filter(|m| indexed.get(&m.id) != Some(&m.content))
+            .collect();
+        if !stale.is_empty() {
+            let embeddings = embedder
+                .embed_documents(stale.iter().map(|m| m.content.as_str()).collect())
+                .await?;
+            let vectors = stale
+                .into_iter()
+                .zip(embeddings)
+                .map(|(m, embedding)| (m.id.clone(), embedding, m.to_vector_metadata()))
+                .collect();
+            self.vector_store.upsert(collection_name, vectors).await?;
+        }
+
+        self.ensemble_synced
+            .write()
+            .await
+            .insert(collection_name.to_string(), digest.to_string());
+        Ok(())
+    }
+
+    /// Names of the ensemble collections derived from a user collection
+    async fn ensemble_collections(&self, base: &str) -> Vec<String> {
+        let is_derived = |name: &str| {
+            name.strip_prefix(ENSEMBLE_PREFIX)
+                .and_then(|rest| rest.split_once('_'))
+                .is_some_and(|(_, rest)| rest == base)
+        };
+        // Stores without collection listing still know the synced ones
+        let mut names: Vec<String> = self
+            .vector_store
+            .list_collections()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| is_derived(name))
+            .collect();
+        for name in self.ensemble_synced.read().await.keys() {
+            if is_derived(name) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// Remove deleted memories from the ensemble collections of `base`
+    async fn forget_in_ensembles(&self, base: &str, ids: &[String]) -> Result<()> {
+        for name in self.ensemble_collections(base).await {
+            self.vector_store.delete(&name, ids.to_vec()).await?;
+            self.ensemble_synced.write().await.remove(&name);
+        }
+        Ok(())
+    }
+
+    /// Delete the ensemble collections derived from `base`
+    async fn drop_ensembles(&self, base: &str) -> Result<()> {
+        for name in self.ensemble_collections(base).await {
+            self.vector_store.delete_collection(&name).await?;
+            self.ensemble_synced.write().await.remove(&name);
+        }
+        Ok(())
+    }
+
+    /// Explain the ranking produced by `search_weighted`
+    ///
+    /// Returns each ranked candidate with its raw score, normalization, the
+    /// score floor and the importance and feedback adjustments, ordered by
+    /// final score.
+    pub async fn explain_ranking(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<RankExplanation>> {
+        if limit == 0 {
+            return Err(Error::invalid_arg("limit must be greater than 0"));
+        }
+
+        let collection_name = self.get_collection_name(user_id);
+        self.ensure_collection(&collection_name).await?;
+        let candidates = limit.saturating_mul(CANDIDATE_OVERFETCH);
+
+        let query_embedding = self.embedder.embed_query(query).await?;
+        self.check_dimension(&query_embedding, "query")?;
+
+        let score_floor = self.config.get_min_score();
+        let results = self
+            .vector_store
+            .search(&collection_name, query_embedding, candidates, score_floor)
+            .await?;
+
+        let normalizer = self.score_normalizer(&collection_name).await;
+        let mut explanations: Vec<RankExplanation> = results
+            .into_iter()
+            .map(|result| {
+                let memory = MemoryItem::from(result.metadata);
+                let normalized_score = normalizer.map(|n| n.normalize(result.score));
+                let (importance_multiplier, feedback_bonus) = weight_components(&memory.metadata);
+                let final_score = normalized_score.unwrap_or(result.score) * importance_multiplier
+                    + feedback_bonus;
+
+                RankExplanation {
+                    memory,
+                    vector_score: result.score,
+                    normalized_score,
+                    score_floor,
+                    importance_multiplier,
+                    feedback_bonus,
+                    final_score,
+                }
+            })
+            .collect();
+
+        explanations.sort_by(|a, b| {
+            b.final_score
+                .partial_cmp(&a.final_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        explanations.truncate(limit);
+
+        Ok(explanations)
+    }
+
+    /// Search memories, decaying scores exponentially with memory age
+    ///
+    /// Each score is multiplied by `0.5^(age_days / half_life_days)`. Memories
+    /// with unparseable `created_at` timestamps are not decayed.
+    pub async fn search_recency(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        half_life_days: f64,
+    ) -> Result<Vec<SearchResultItem>> {
+        let scoring = ScoringConfig::new().with_recency_half_life(half_life_days);
+        self.search_scored(user_id, query, limit, scoring).await
+    }
+
+    /// Update a memory only if it has not changed since `expected_updated_at`
+    ///
+    /// Provides optimistic concurrency: returns `Error::InvalidArgument("conflict")`
+    /// when another writer updated the memory first.
+    pub async fn update_cas(
+        &self,
+        memory_id: &str,
+        expected_updated_at: &str,
+        new_content: &str,
+    ) -> Result<MemoryItem> {
+        let collection_name = self.locate(memory_id).await?;
+        let mut metadata = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+
+        if metadata.updated_at != expected_updated_at {
+            return Err(Error::invalid_arg("conflict"));
+        }
+
+        let redacted = self.redact(new_content);
+        let new_content = redacted.as_ref();
+        let embedding = self.embedder.embed_document(new_content).await?;
+        self.check_dimension(&embedding, "vector")?;
+        metadata.text = new_content.to_string();
+        metadata.updated_at = Utc::now().to_rfc3339();
+
+        self.vector_store
+            .compare_and_swap(
+                &collection_name,
+                memory_id,
+                expected_updated_at,
+                embedding,
+                metadata.clone(),
+            )
+            .await?;
+
+        self.audit(AuditOperation::Update, Some(memory_id), Some(&metadata.user_id));
+        Ok(metadata.into())
+    }
+
+    /// Suggest a search score threshold from sample queries
+    ///
+    /// For each query, finds the largest gap between consecutive result
+    /// scores and takes its midpoint; the suggestion is the mean of those
+    /// midpoints. Falls back to the configured minimum score, or 0.0, when
+    /// no query returns at least two results. Read-only.
+    pub async fn suggest_threshold(&self, user_id: &str, sample_queries: &[&str]) -> Result<f32> {
+        if sample_queries.is_empty() {
+            return Err(Error::invalid_arg("at least one sample query is required"));
+        }
+
+        let mut knees = Vec::new();
+        for query in sample_queries {
+            let results = self
+                .search_unprocessed(user_id, query, THRESHOLD_SAMPLE_SIZE)
+                .await?;
+            let knee = results
+                .windows(2)
+                .map(|pair| (pair[0].score - pair[1].score, (pair[0].score + pair[1].score) / 2.0))
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            if let Some((_, midpoint)) = knee {
+                knees.push(midpoint);
+            }
+        }
+
+        if knees.is_empty() {
+            return Ok(self.config.get_min_score().unwrap_or(0.0));
+        }
+        Ok(knees.iter().sum::<f32>() / knees.len() as f32)
+    }
+
+    /// Delete many memories, issuing one store delete per collection
+    ///
+    /// Unknown ids and ids in a collection whose delete fails are counted as
+    /// failures.
+    pub async fn delete_many(&self, ids: &[String]) -> Result<BatchResult> {
+        let mut result = BatchResult::new(ids.len());
+
+        // Group ids by their collection
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        {
+            let index = self.id_index.read().await;
+            for id in ids {
+                match index.get(id) {
+                    Some(collection_name) => groups
+                        .entry(collection_name.clone())
+                        .or_default()
+                        .push(id.clone()),
+                    None => result.add_error(format!("Memory not found: {}", id)),
+                }
+            }
+        }
+
+        for (collection_name, group) in groups {
+            let deleted = match self.vector_store.delete(&collection_name, group.clone()).await {
+                Ok(()) => self.forget_in_ensembles(&collection_name, &group).await,
+                Err(e) => Err(e),
+            };
+            match deleted {
+                Ok(()) => {
+                    let mut index = self.id_index.write().await;
+                    for id in &group {
+                        index.remove(id);
+                        self.audit(AuditOperation::Delete, Some(id), None);
+                        result.add_success();
+                    }
+                }
+                Err(e) => {
+                    for id in &group {
+                        result.add_error(format!("Failed to delete {}: {}", id, e));
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Remove a user's memories whose content exactly duplicates an older one
+    ///
+    /// Memories are grouped by content hash; the earliest created memory of
+    /// each group keeps its ID and the rest are deleted. Returns the number
+    /// of memories removed.
+    pub async fn compact_duplicates(&self, user_id: &str) -> Result<usize> {
+        let mut groups: HashMap<String, Vec<MemoryItem>> = HashMap::new();
+        for memory in self.get_all(user_id).await? {
+            groups.entry(memory.hash.clone()).or_default().push(memory);
+        }
+
+        let mut duplicates = Vec::new();
+        for mut group in groups.into_values() {
+            group.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+            duplicates.extend(group.into_iter().skip(1).map(|m| m.id));
+        }
+        if duplicates.is_empty() {
+            return Ok(0);
+        }
+
+        let collection_name = self.get_collection_name(user_id);
+        self.vector_store
+            .delete(&collection_name, duplicates.clone())
+            .await?;
+        self.forget_in_ensembles(&collection_name, &duplicates)
+            .await?;
+
+        let mut index = self.id_index.write().await;
+        for id in &duplicates {
+            index.remove(id);
+            self.audit(AuditOperation::Delete, Some(id), Some(user_id));
+        }
+
+        Ok(duplicates.len())
+    }
+
+    /// Stable SHA-256 digest of a user's memories
+    ///
+    /// Hashes the ID, content, type and creation time of each memory in ID
+    /// order, so two stores holding the same memories produce the same
+    /// digest regardless of insertion order.
+    pub async fn content_digest(&self, user_id: &str) -> Result<String> {
+        use sha2::{Digest, Sha256};
+
+        let mut memories = self.get_all(user_id).await?;
+        memories.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut hasher = Sha256::new();
+        for memory in &memories {
+            for field in [&memory.id, &memory.content, &memory.memory_type, &memory.created_at] {
+                // Length prefix keeps field boundaries unambiguous
+                hasher.update((field.len() as u64).to_be_bytes());
+                hasher.update(field.as_bytes());
+            }
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Compare two users' memories by content hash
+    pub async fn diff(&self, user_a: &str, user_b: &str) -> Result<MemoryDiff> {
+        let memories_a = self.get_all(user_a).await?;
+        let memories_b = self.get_all(user_b).await?;
+
+        let hashes_a: HashSet<String> = memories_a.iter().map(|m| m.hash.clone()).collect();
+        let hashes_b: HashSet<String> = memories_b.iter().map(|m| m.hash.clone()).collect();
+
+        let (common, only_a) = memories_a
+            .into_iter()
+            .partition(|m| hashes_b.contains(&m.hash));
+        let only_b = memories_b
+            .into_iter()
+            .filter(|m| !hashes_a.contains(&m.hash))
+            .collect();
+
+        Ok(MemoryDiff {
+            only_a,
+            only_b,
+            common,
+        })
+    }
+
+    /// Classify content into a memory type using an LLM
+    ///
+    /// Requests JSON output and falls back to reading the type from free
+    /// text when the model ignores the format.
+    pub async fn classify(&self, content: &str, llm: &dyn LlmBase) -> Result<Classification> {
+        let mut vars = HashMap::new();
+        vars.insert("memory".to_string(), content.to_string());
+        let prompt = PromptManager::new().render("classify_memory", &vars)?;
+
+        let params = GenerationParams {
+            response_format: Some(ResponseFormat::JsonObject),
+            ..Default::default()
+        };
+        let response = self.generate(llm, &prompt, Some(params)).await?;
+        Classification::parse(&response)
+    }
+
+    /// Classify content with an LLM and store it under the chosen type
+    ///
+    /// Returns `None` when the classification confidence is below
+    /// `min_llm_confidence` and downgrading is disabled.
+    pub async fn add_classified(
+        &self,
+        user_id: &str,
+        content: &str,
+        llm: &dyn LlmBase,
+    ) -> Result<Option<MemoryItem>> {
+        let classification = self.classify(content, llm).await?;
+        self.add_derived(
+            user_id,
+            content,
+            &classification.memory_type,
+            classification.confidence,
+        )
+        .await
+    }
+
+    /// Extract facts from a conversation with an LLM and store them
+    ///
+    /// Facts below `min_llm_confidence` are skipped, or stored as
+    /// `unverified` when downgrading is enabled. When the model ignores the
+    /// JSON format, each line is taken as a `fact` with
+    /// `TEXT_FALLBACK_CONFIDENCE`.
+    pub async fn add_from_conversation(
+        &self,
+        user_id: &str,
+        conversation: &str,
+        llm: &dyn LlmBase,
+    ) -> Result<ExtractionResult> {
+        let mut vars = HashMap::new();
+        vars.insert("conversation".to_string(), conversation.to_string());
+        let prompt = PromptManager::new().render("extract_facts", &vars)?;
+
+        let params = GenerationParams {
+            response_format: Some(ResponseFormat::JsonObject),
+            ..Default::default()
+        };
+        let response = self.generate(llm, &prompt, Some(params)).await?;
+        let facts = ExtractedFact::parse_all(&response).unwrap_or_else(|| {
+            response
+                .lines()
+                .map(strip_list_marker)
+                .filter(|line| !line.is_empty())
+                .map(|line| ExtractedFact {
+                    content: line.to_string(),
+                    memory_type: "fact".to_string(),
+                    confidence: TEXT_FALLBACK_CONFIDENCE,
+                })
+                .collect()
+        });
+
+        let mut result = ExtractionResult::default();
+        for fact in facts {
+            let memory_type = if fact.memory_type.is_empty() {
+                "fact"
+            } else {
+                fact.memory_type.as_str()
+            };
+            let stored = self
+                .add_derived(user_id, &fact.content, memory_type, fact.confidence)
+                .await?;
+            match stored {
+                Some(item) => result.added.push(item),
+                None => result.skipped.push(fact),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Store an LLM-derived memory, applying the confidence gate
+    async fn add_derived(
+        &self,
+        user_id: &str,
+        content: &str,
+        memory_type: &str,
+        confidence: f32,
+    ) -> Result<Option<MemoryItem>> {
+        let memory_type = if confidence >= self.config.get_min_llm_confidence() {
+            memory_type
+        } else if self.config.is_unverified_downgrade_enabled() {
+            UNVERIFIED_MEMORY_TYPE
+        } else {
+            tracing::debug!("Skipping LLM-derived memory with confidence {}", confidence);
+            return Ok(None);
+        };
+
+        let options = AddOptions::new()
+            .with_memory_type(memory_type.to_string())
+            .with_metadata(LLM_CONFIDENCE_KEY.to_string(), confidence.to_string());
+        self.add_with_options(user_id, content, options).await.map(Some)
+    }
+
+    /// Answer a question from a user's memories with an LLM
+    pub async fn answer(
+        &self,
+        user_id: &str,
+        question: &str,
+        limit: usize,
+        llm: &dyn LlmBase,
+    ) -> Result<String> {
+        let (answer, _) = self.answer_with_sources(user_id, question, limit, llm).await?;
+        Ok(answer)
+    }
+
+    /// Answer a question from a user's memories, returning the memory IDs used
+    ///
+    /// The `limit` most relevant memories are listed, best first, as the
+    /// context of the `answer_with_context` prompt.
+    pub async fn answer_with_sources(
+        &self,
+        user_id: &str,
+        question: &str,
+        limit: usize,
+        llm: &dyn LlmBase,
+    ) -> Result<(String, Vec<String>)> {
+        let results = self.search_unprocessed(user_id, question, limit).await?;
+
+        let context = results
+            .iter()
+            .map(|r| format!("- {}", r.memory.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut vars = HashMap::new();
+        vars.insert("context".to_string(), context);
+        vars.insert("question".to_string(), question.to_string());
+        let prompt = PromptManager::new().render("answer_with_context", &vars)?;
+
+        let answer = self.generate(llm, &prompt, None).await?;
+        let sources = results.into_iter().map(|r| r.memory.id).collect();
+        Ok((answer.trim().to_string(), sources))
+    }
+
+    /// Consolidate several memories of a user into one using an LLM
+    ///
+    /// The merged statement is stored as a new memory with the type of the
+    /// first original, and the originals are deleted.
+    pub async fn merge(
+        &self,
+        user_id: &str,
+        memory_ids: Vec<String>,
+        llm: &dyn LlmBase,
+    ) -> Result<MemoryItem> {
+        if memory_ids.len() < 2 {
+            return Err(Error::invalid_arg("merge requires at least two memories"));
+        }
+
+        let mut originals = Vec::with_capacity(memory_ids.len());
+        for memory_id in &memory_ids {
+            let collection_name = self.locate(memory_id).await?;
+            let metadata = self
+                .vector_store
+                .get_by_id(&collection_name, memory_id)
+                .await?
+                .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+
+            if metadata.user_id != user_id {
+                return Err(Error::invalid_arg(format!(
+                    "Memory {} does not belong to user {}",
+                    memory_id, user_id
+                )));
+            }
+            originals.push(MemoryItem::from(metadata));
+        }
+
+        let listing = originals
+            .iter()
+            .map(|m| format!("- {}", m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut vars = HashMap::new();
+        vars.insert("memories".to_string(), listing);
+        let prompt = PromptManager::new().render("merge_memories", &vars)?;
+
+        let merged = self.generate(llm, &prompt, None).await?;
+        let merged = merged.trim();
+        if merged.is_empty() {
+            return Err(Error::llm("LLM returned an empty merge"));
+        }
+
+        let item = self
+            .add(user_id, merged, Some(&originals[0].memory_type))
+            .await?;
+
+        for original in &originals {
+            self.delete(&original.id).await?;
+        }
+
+        Ok(item)
+    }
+
+    /// Distill a user's `fact` memories into `insight` memories with an LLM
+    ///
+    /// The LLM is asked for one insight per line; insights whose content
+    /// matches an existing insight are skipped. Returns the newly stored
+    /// insights.
+    pub async fn generate_insights(
+        &self,
+        user_id: &str,
+        llm: &dyn LlmBase,
+    ) -> Result<Vec<MemoryItem>> {
+        let facts = self.search_by_type(user_id, "fact", true).await?;
+        if facts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let listing = facts
+            .iter()
+            .map(|m| format!("- {}", m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut vars = HashMap::new();
+        vars.insert("facts".to_string(), listing);
+        let prompt = PromptManager::new().render("generate_insights", &vars)?;
+
+        let response = self.generate(llm, &prompt, None).await?;
+
+        let mut seen: HashSet<String> = self
+            .search_by_type(user_id, "insight", false)
+            .await?
+            .into_iter()
+            .map(|m| m.hash)
+            .collect();
+
+        let mut created = Vec::new();
+        for line in response.lines() {
+            let insight = strip_list_marker(line);
+            if insight.is_empty() || !seen.insert(crate::utils::compute_hash(insight)) {
+                continue;
+            }
+            created.push(self.add(user_id, insight, Some("insight")).await?);
+        }
+
+        Ok(created)
+    }
+
+    /// Search memories, dropping results too similar to a higher-scored one
+    ///
+    /// Results whose content embedding has a cosine similarity above
+    /// `threshold` with an already kept result are discarded.
+    pub async fn search_deduped(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SearchResultItem>> {
+        let results = self
+            .search_unprocessed(user_id, query, limit.saturating_mul(CANDIDATE_OVERFETCH))
+            .await?;
+        if results.is_empty() {
+            return Ok(results);
+        }
+
+        let embeddings = self
+            .embedder
+            .embed_documents(results.iter().map(|r| r.memory.content.as_str()).collect())
+            .await?;
+
+        let mut kept: Vec<usize> = Vec::new();
+        for (i, embedding) in embeddings.iter().enumerate() {
+            if kept.len() >= limit {
+                break;
+            }
+            let duplicate = kept.iter().any(|&k| {
+                Deduplicator::compute_similarity(embedding, &embeddings[k]) > threshold
+            });
+            if !duplicate {
+                kept.push(i);
+            }
+        }
+
+        let deduped = results
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| kept.contains(i))
+            .map(|(_, r)| r)
+            .collect();
+        Ok(self.post_processor.process(deduped))
+    }
+
+    /// Pairwise cosine similarity of a user's stored memory vectors
+    ///
+    /// Returns the memory IDs, sorted, and an NxN symmetric matrix whose
+    /// entry `[i][j]` is the similarity of memories `i` and `j`. Reads every
+    /// vector and computes O(N²) similarities, so it is meant for offline
+    /// analysis of modest collections.
+    pub async fn similarity_matrix(&self, user_id: &str) -> Result<(Vec<String>, Vec<Vec<f32>>)> {
+        let collection_name = self.get_collection_name(user_id);
+        self.ensure_readable_collection(&collection_name).await?;
+
+        let mut ids: Vec<String> = self
+            .vector_store
+            .get_all(&collection_name)
+            .await?
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        ids.sort();
+
+        let mut vectors = Vec::with_capacity(ids.len());
+        let mut present = Vec::with_capacity(ids.len());
+        for id in ids {
+            // Skip memories deleted since the listing
+            if let Some(vector) = self.vector_store.get_vector(&collection_name, &id).await? {
+                vectors.push(vector);
+                present.push(id);
+            }
+        }
+
+        let n = vectors.len();
+        let mut matrix = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in i..n {
+                let similarity = crate::utils::cosine_similarity(&vectors[i], &vectors[j]);
+                matrix[i][j] = similarity;
+                matrix[j][i] = similarity;
+            }
+        }
+        Ok((present, matrix))
+    }
+
+    /// Search memories using Maximal Marginal Relevance
+    ///
+    /// Candidates are picked iteratively by maximizing
+    /// `lambda * relevance - (1 - lambda) * max_similarity_to_selected`.
+    /// `lambda = 1.0` reduces to plain relevance ranking.
+    pub async fn search_mmr(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        lambda: f32,
+    ) -> Result<Vec<SearchResultItem>> {
+        let candidates = self
+            .search_unprocessed(user_id, query, limit.saturating_mul(CANDIDATE_OVERFETCH))
+            .await?;
+        if candidates.is_empty() {
+            return Ok(candidates);
+        }
+
+        let embeddings = self
+            .embedder
+            .embed_documents(candidates.iter().map(|r| r.memory.content.as_str()).collect())
+            .await?;
+
+        let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+        let mut selected: Vec<usize> = Vec::new();
+        while selected.len() < limit && !remaining.is_empty() {
+            let mmr = |i: usize| {
+                let redundancy = selected
+                    .iter()
+                    .map(|&j| Deduplicator::compute_similarity(&embeddings[i], &embeddings[j]))
+                    .fold(f32::MIN, f32::max);
+                let redundancy = if selected.is_empty() { 0.0 } else { redundancy };
+                lambda * candidates[i].score - (1.0 - lambda) * redundancy
+            };
+
+            let (position, _) = remaining
+                .iter()
+                .enumerate()
+                .map(|(position, &i)| (position, mmr(i)))
+                .fold((0, f32::MIN), |best, current| if current.1 > best.1 { current } else { best });
+            selected.push(remaining.remove(position));
+        }
+
+        let mut candidates: Vec<Option<SearchResultItem>> = candidates.into_iter().map(Some).collect();
+        let selected = selected
+            .into_iter()
+            .filter_map(|i| candidates[i].take())
+            .collect();
+        Ok(self.post_processor.process(selected))
+    }
+
+    /// Search memories and expand the hits through graph relationships
+    ///
+    /// Graph node IDs must match memory IDs. Related memories up to `depth`
+    /// hops away are appended with a decayed score and flagged with
+    /// `graph_derived` in their metadata. Memories `user_id` cannot read
+    /// are neither returned nor expanded through.
+    pub async fn search_graph_expanded(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        graph: &dyn GraphStoreBase,
+        depth: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        let mut results = self.search_unprocessed(user_id, query, limit).await?;
+
+        let mut seen: HashSet<String> = results.iter().map(|r| r.memory.id.clone()).collect();
+        let mut frontier: Vec<(String, f32)> = results
+            .iter()
+            .map(|r| (r.memory.id.clone(), r.score))
+            .collect();
+
+        for _ in 0..depth {
+            let mut next = Vec::new();
+
+            for (node_id, score) in frontier {
+                for rel in graph.get_relationships(&node_id).await? {
+                    let neighbor = if rel.source_id == node_id {
+                        rel.target_id
+                    } else {
+                        rel.source_id
+                    };
+
+                    if !seen.insert(neighbor.clone()) {
+                        continue;
+                    }
+
+                    // Nodes without a backing memory are skipped
+                    let Ok(collection_name) = self.locate(&neighbor).await else {
+                        continue;
+                    };
+                    let Some(metadata) = self
+                        .vector_store
+                        .get_by_id(&collection_name, &neighbor)
+                        .await?
+                    else {
+                        continue;
+                    };
+
+                    let mut memory: MemoryItem = metadata.into();
+                    if !memory.is_readable_by(user_id) {
+                        continue;
+                    }
+                    memory
+                        .metadata
+                        .insert(GRAPH_DERIVED_KEY.to_string(), "true".to_string());
+
+                    let derived_score = score * GRAPH_SCORE_DECAY;
+                    results.push(SearchResultItem {
+                        memory,
+                        score: derived_score,
+                        distance: None,
+                    });
+                    next.push((neighbor, derived_score));
+                }
+            }
+
+            frontier = next;
+        }
+
+        Ok(self.post_processor.process(results))
+    }
+
+    /// Link two existing memories in the graph
+    ///
+    /// Both memories must exist. Missing graph nodes are created from the
+    /// stored memories, with the memory ID as node ID. Relating an already
+    /// related pair with the same type is a no-op.
+    pub async fn relate(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        rel_type: RelationType,
+        graph: &dyn GraphStoreBase,
+    ) -> Result<()> {
+        // Resolve both memories before touching the graph
+        let mut memories = Vec::with_capacity(2);
+        for id in [source_id, target_id] {
+            let collection_name = self.locate(id).await?;
+            let metadata = self
+                .vector_store
+                .get_by_id(&collection_name, id)
+                .await?
+                .ok_or_else(|| Error::not_found(format!("Memory not found: {}", id)))?;
+            memories.push(metadata);
+        }
+
+        for metadata in memories {
+            if graph.get_node(&metadata.id).await?.is_some() {
+                continue;
+            }
+            graph
+                .create_node(GraphNode {
+                    id: metadata.id,
+                    content: metadata.text,
+                    labels: vec!["Memory".to_string()],
+                    properties: HashMap::from([("user_id".to_string(), metadata.user_id)]),
+                })
+                .await?;
+        }
+
+        let exists = graph.get_relationships(source_id).await?.iter().any(|rel| {
+            rel.source_id == source_id && rel.target_id == target_id && rel.rel_type == rel_type
+        });
+        if exists {
+            return Ok(());
+        }
+
+        graph
+            .create_relationship(GraphRelationship {
+                source_id: source_id.to_string(),
+                target_id: target_id.to_string(),
+                rel_type,
+                properties: HashMap::new(),
+                weight: None,
+            })
+            .await
+    }
+
+    /// Remove a relationship created with `relate`
+    ///
+    /// Graph nodes are kept. Removing a missing relationship is a no-op.
+    pub async fn unrelate(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        rel_type: RelationType,
+        graph: &dyn GraphStoreBase,
+    ) -> Result<()> {
+        graph.delete_relationship(source_id, target_id, rel_type).await
+    }
+
+    /// Summarize each community of a user's memory graph with an LLM
+    ///
+    /// Graph node IDs must match memory IDs. Nodes without a backing memory
+    /// of this user are ignored, and communities left empty are skipped.
+    pub async fn summarize_clusters(
+        &self,
+        user_id: &str,
+        graph: &dyn GraphStoreBase,
+        llm: &dyn LlmBase,
+    ) -> Result<Vec<MemoryCluster>> {
+        let mut clusters = Vec::new();
+
+        for community in graph.detect_communities().await? {
+            let mut memories = Vec::new();
+            for node_id in &community {
+                let Ok(collection_name) = self.locate(node_id).await else {
+                    continue;
+                };
+                if let Some(metadata) = self.vector_store.get_by_id(&collection_name, node_id).await?
+                    && metadata.user_id == user_id
+                {
+                    memories.push(MemoryItem::from(metadata));
+                }
+            }
+            if memories.is_empty() {
+                continue;
+            }
+
+            let listing = memories
+                .iter()
+                .map(|m| format!("- {}", m.content))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let mut vars = HashMap::new();
+            vars.insert("memories".to_string(), listing);
+            let prompt = PromptManager::new().render("summarize_memories", &vars)?;
+
+            let summary = self.generate(llm, &prompt, None).await?;
+            clusters.push(MemoryCluster {
+                memories,
+                summary: summary.trim().to_string(),
+            });
+        }
+
+        Ok(clusters)
+    }
+}
+
+/// Strip a leading `-`, `*` or `N.` list marker from an LLM output line
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix('-').or_else(|| line.strip_prefix('*')) {
+        return rest.trim();
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[digits..].strip_prefix('.') {
+        Some(rest) if digits > 0 => rest.trim(),
+        _ => line,
+    }
+}
+
+/// Short identity of an embedder: its dimension and a hash of its probe embedding
+async fn embedder_fingerprint(embedder: &dyn EmbedderBase) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let probe = embedder.embed_document(ENSEMBLE_PROBE).await?;
+    let mut hasher = Sha256::new();
+    hasher.update((embedder.dimension() as u64).to_le_bytes());
+    for value in &probe {
+        hasher.update(value.to_le_bytes());
+    }
+    Ok(format!("{:x}", hasher.finalize())[..16].to_string())
+}
+
+/// Digest of the IDs and contents of a set of memories
+fn ensemble_digest(memories: &HashMap<String, MemoryItem>) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut ids: Vec<&String> = memories.keys().collect();
+    ids.sort();
+    let mut hasher = Sha256::new();
+    for id in ids {
+        hasher.update(id.as_bytes());
+        hasher.update([0]);
+        hasher.update(memories[id].content.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Fuse ranked ID lists with weighted reciprocal rank fusion
+///
+/// Each list contributes `weight / (RRF_K + rank)` for every ID it ranks,
+/// with ranks starting at 1. Returns IDs by descending fused score, ties
+/// broken by ID.
+fn reciprocal_rank_fusion(rankings: &[(Vec<String>, f32)]) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    for (ids, weight) in rankings {
+        for (rank, id) in ids.iter().enumerate() {
+            *scores.entry(id).or_default() += weight / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(id, score)| (id.to_string(), score))
+        .collect();
+    fused.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    fused
+}
+
+/// Sort search results by descending score
+fn sort_by_score(results: &mut [SearchResultItem]) {
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Sort search results by descending score, then ascending ID
+fn sort_by_score_then_id(results: &mut [SearchResultItem]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.memory.id.cmp(&b.memory.id))
+    });
+}
+
+/// Exponential decay factor for a memory created at `created_at`
+fn recency_factor(created_at: &str, now: DateTime<Utc>, half_life_days: f64) -> f64 {
+    let Ok(created) = DateTime::parse_from_rfc3339(created_at) else {
+        return 1.0;
+    };
+    if half_life_days <= 0.0 {
+        return 1.0;
+    }
+
+    let age_days = (now - created.with_timezone(&Utc)).num_seconds().max(0) as f64 / 86_400.0;
+    0.5_f64.powf(age_days / half_life_days)
+}
+
+/// Importance stored in metadata, clamped to 0-1
+fn importance(metadata: &HashMap<String, String>) -> f32 {
+    metadata
+        .get(IMPORTANCE_KEY)
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(DEFAULT_IMPORTANCE)
+        .clamp(0.0, 1.0)
+}
+
+/// Importance multiplier and feedback bonus stored in metadata
+fn weight_components(metadata: &HashMap<String, String>) -> (f32, f32) {
+    let importance = importance(metadata);
+    let feedback = metadata
+        .get(FEEDBACK_SCORE_KEY)
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(0.0);
+
+    (0.5 + importance, feedback * DEFAULT_FEEDBACK_WEIGHT)
+}
+
+#[async_trait]
+impl MemoryBase for Memory {
+    async fn add(
+        &self,
+        user_id: &str,
+        content: &str,
+        memory_type: Option<&str>,
+    ) -> Result<MemoryItem> {
+        self.add_scoped(user_id, None, None, content, memory_type).await
+    }
+
+    async fn search(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        self.search_scoped(user_id, None, None, query, limit).await
+    }
+
+    async fn update(
+        &self,
+        memory_id: &str,
+        content: &str,
+    ) -> Result<MemoryItem> {
+        let collection_name = self.locate(memory_id).await?;
+        let mut stored = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Memory not found: {}", memory_id)))?;
+        let redacted = self.redact(content);
+        let content = redacted.as_ref();
+
+        // Same content keeps the stored vector; fall back to re-embedding
+        // when the store cannot update metadata alone
+        if crate::utils::compute_hash(&stored.text) == crate::utils::compute_hash(content) {
+            stored.updated_at = Utc::now().to_rfc3339();
+            match self
+                .vector_store
+                .update_metadata(&collection_name, memory_id, stored.clone())
+                .await
+            {
+                Ok(()) => {
+                    self.audit(AuditOperation::Update, Some(memory_id), Some(&stored.user_id));
+                    return Ok(stored.into());
+                }
+                Err(Error::InvalidArgument(e)) => {
+                    tracing::debug!("Re-embedding unchanged memory {}: {}", memory_id, e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Generate new embedding for updated content
+        let embedding = self.embedder.embed_document(content).await?;
+        self.check_dimension(&embedding, "vector")?;
+
+        stored.text = content.to_string();
+        stored.updated_at = Utc::now().to_rfc3339();
+
+        self.vector_store
+            .upsert(
+                &collection_name,
+                vec![(memory_id.to_string(), embedding, stored.clone())],
+            )
+            .await?;
+
+        self.audit(AuditOperation::Update, Some(memory_id), Some(&stored.user_id));
+        Ok(stored.into())
+    }
+
+    async fn delete(&self, memory_id: &str) -> Result<()> {
+        let collection_name = self.locate(memory_id).await?;
+        let user_id = self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .map(|m| m.user_id);
+
+        self.vector_store
+            .delete(&collection_name, vec![memory_id.to_string()])
+            .await?;
+        self.forget_in_ensembles(&collection_name, &[memory_id.to_string()])
+            .await?;
+
+        self.id_index.write().await.remove(memory_id);
+        self.audit(AuditOperation::Delete, Some(memory_id), user_id.as_deref());
+        Ok(())
+    }
+
+    async fn get(&self, memory_id: &str) -> Result<Option<MemoryItem>> {
+        let Some(collection_name) = self.id_index.read().await.get(memory_id).cloned() else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .vector_store
+            .get_by_id(&collection_name, memory_id)
+            .await?
+            .map(MemoryItem::from))
+    }
+
+    async fn get_all(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<MemoryItem>> {
+        self.get_all_scoped(user_id, None, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::{EmbedderBase, LocalEmbedder};
+    use crate::memory::{Attachment, ATTACHMENTS_KEY};
+    use crate::vector_store::{InMemoryStore, RecordingStore, StoreCall};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn local_memory() -> Memory {
+        Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        )
+    }
+
+    #[test]
+    fn test_collection_name() {
+        let config = MemoryConfig::new("memory.db".to_string());
+
+        let memory = Memory::new(
+            config,
+            Arc::new(MockVectorStore),
+            Arc::new(MockEmbedder),
+        );
+
+        assert_eq!(
+            memory.get_collection_name("user_123"),
+            "memory_user_123"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_feedback_promotes_memory() {
+        let memory = local_memory();
+        let first = memory.add("user_1", "I like tea", None).await.unwrap();
+        let second = memory.add("user_1", "I like tea", None).await.unwrap();
+
+        memory.record_feedback(&second.id, true).await.unwrap();
+        let results = memory.search_weighted("user_1", "I like tea", 2).await.unwrap();
+        assert_eq!(results[0].memory.id, second.id);
+
+        memory.record_feedback(&second.id, false).await.unwrap();
+        memory.record_feedback(&second.id, false).await.unwrap();
+        let results = memory.search_weighted("user_1", "I like tea", 2).await.unwrap();
+        assert_eq!(results[0].memory.id, first.id);
+        assert_eq!(
+            results[1].memory.metadata.get(FEEDBACK_SCORE_KEY),
+            Some(&"-1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_min_content_chars() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_min_content_chars(3),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        assert!(matches!(
+            memory.add("user_1", "  ", None).await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            memory.add("user_1", "ok", None).await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(memory.add("user_1", "I like coffee", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_search_deduped() {
+        let memory = local_memory();
+        memory.add("user_1", "I drink coffee every morning", None).await.unwrap();
+        memory.add("user_1", "I drink coffee every morning", None).await.unwrap();
+        memory.add("user_1", "I have a cat", None).await.unwrap();
+
+        let plain = memory
+            .search("user_1", "I drink coffee every morning", 10)
+            .await
+            .unwrap();
+        let deduped = memory
+            .search_deduped("user_1", "I drink coffee every morning", 10, 0.95)
+            .await
+            .unwrap();
+
+        let coffee = |results: &[SearchResultItem]| {
+            results
+                .iter()
+                .filter(|r| r.memory.content == "I drink coffee every morning")
+                .count()
+        };
+        assert_eq!(coffee(&plain), 2);
+        assert_eq!(coffee(&deduped), 1);
+        assert_eq!(deduped.len(), plain.len() - 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_cas() {
+        let memory = local_memory();
+        let item = memory.add("user_1", "I like tea", None).await.unwrap();
+
+        let updated = memory
+            .update_cas(&item.id, &item.updated_at, "I like green tea")
+            .await
+            .unwrap();
+        assert_eq!(updated.content, "I like green tea");
+
+        // The original timestamp is now stale
+        let conflict = memory
+            .update_cas(&item.id, &item.updated_at, "I like black tea")
+            .await;
+        assert!(matches!(conflict, Err(Error::InvalidArgument(_))));
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert_eq!(all[0].content, "I like green tea");
+    }
+
+    #[tokio::test]
+    async fn test_per_user_agent_collections() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string())
+                .with_collection_strategy(CollectionStrategy::PerUserAgent),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        assert_eq!(
+            memory.get_scoped_collection_name("user_1", Some("planner"), None),
+            "memory_user_1_planner"
+        );
+
+        memory
+            .add_scoped("user_1", Some("planner"), None, "Plan the trip", None)
+            .await
+            .unwrap();
+        memory
+            .add_scoped("user_1", Some("coder"), None, "Write the parser", None)
+            .await
+            .unwrap();
+
+        let planner = memory.get_all_scoped("user_1", Some("planner"), None).await.unwrap();
+        assert_eq!(planner.len(), 1);
+        assert_eq!(planner[0].content, "Plan the trip");
+        assert_eq!(planner[0].agent_id, Some("planner".to_string()));
+    }
+
+    fn stored_metadata(id: &str, text: &str, created_at: &str) -> crate::vector_store::VectorMetadata {
+        crate::vector_store::VectorMetadata {
+            id: id.to_string(),
+            user_id: "user_1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: text.to_string(),
+            memory_type: "general".to_string(),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            custom_metadata: HashMap::new(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_similarity_matrix() {
+        let memory = local_memory();
+        let collection = memory.get_collection_name("user_1");
+        memory.vector_store.create_collection(&collection, 3).await.unwrap();
+        let vectors = vec![
+            ("a".to_string(), vec![1.0, 0.0, 0.0], stored_metadata("a", "x", "")),
+            ("b".to_string(), vec![0.0, 1.0, 0.0], stored_metadata("b", "y", "")),
+            ("c".to_string(), vec![1.0, 1.0, 0.0], stored_metadata("c", "z", "")),
+        ];
+        memory.vector_store.upsert(&collection, vectors).await.unwrap();
+
+        let (ids, matrix) = memory.similarity_matrix("user_1").await.unwrap();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(matrix.len(), 3);
+        for (i, row) in matrix.iter().enumerate() {
+            assert!((row[i] - 1.0).abs() < 1e-6);
+            for (j, score) in row.iter().enumerate() {
+                assert_eq!(*score, matrix[j][i]);
+            }
+        }
+        assert!(matrix[0][1].abs() < 1e-6);
+        assert!((matrix[0][2] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_forgetting_and_reinforcement() {
+        let memory = local_memory();
+        let idle = memory.add("user_1", "I like tea", None).await.unwrap();
+        let used = memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        // Simulate both memories last being accessed ten days ago
+        let ten_days_ago = (Utc::now() - chrono::Duration::days(10)).to_rfc3339();
+        for id in [&idle.id, &used.id] {
+            memory
+                .update_metadata(id, HashMap::from([(LAST_ACCESSED_KEY.to_string(), ten_days_ago.clone())]))
+                .await
+                .unwrap();
+        }
+
+        let before = memory.get(&idle.id).await.unwrap().unwrap().updated_at;
+        assert_eq!(memory.apply_forgetting("user_1", 0.1).await.unwrap(), 2);
+        let importance_of = |item: &MemoryItem| importance(&item.metadata);
+        let idle_after = memory.get(&idle.id).await.unwrap().unwrap();
+        assert_eq!(idle_after.updated_at, before);
+        let idle_decayed = importance_of(&idle_after);
+        let expected = DEFAULT_IMPORTANCE * (-1.0_f32).exp();
+        assert!((idle_decayed - expected).abs() < 1e-3);
+
+        let used_recovered = importance_of(&memory.record_access(&used.id).await.unwrap());
+        assert!((used_recovered - (idle_decayed + ACCESS_REINFORCEMENT)).abs() < 1e-3);
+
+        // A second pass right away does not decay again
+        memory.apply_forgetting("user_1", 0.1).await.unwrap();
+        let idle_again = importance_of(&memory.get(&idle.id).await.unwrap().unwrap());
+        assert!((idle_again - idle_decayed).abs() < 1e-3);
+
+        let evicted = memory.evict_lru("user_1", 0.25, 10).await.unwrap();
+        assert_eq!(evicted, vec![idle.id.clone()]);
+        assert!(memory.get(&used.id).await.unwrap().is_some());
+
+        assert!(matches!(
+            memory.apply_forgetting("user_1", -1.0).await,
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prune_inactive_collections() {
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        memory.add("active", "I like coffee", None).await.unwrap();
+
+        let old = (Utc::now() - chrono::Duration::days(90)).to_rfc3339();
+        store.create_collection("memory_idle", 384).await.unwrap();
+        let mut stale = stored_metadata("stale", "I like tea", &old);
+        stale.user_id = "idle".to_string();
+        store
+            .upsert("memory_idle", vec![("stale".to_string(), vec![0.1; 384], stale)])
+            .await
+            .unwrap();
+        store.create_collection("other_app", 384).await.unwrap();
+
+        let pruned = memory
+            .prune_inactive_collections(std::time::Duration::from_secs(30 * 86_400))
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 1);
+        let collections = store.list_collections().await.unwrap();
+        assert!(!collections.contains(&"memory_idle".to_string()));
+        assert!(collections.contains(&"memory_active".to_string()));
+        assert!(collections.contains(&"other_app".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_fallback_tops_up() {
+        let memory = local_memory();
+        for content in ["I like coffee", "I walk the dog", "I play chess", "I read books"] {
+            memory.add("user_1", content, None).await.unwrap();
+        }
+
+        let strict = memory
+            .search_with_min_score("user_1", "I like coffee", 3, 0.8)
+            .await
+            .unwrap();
+        assert_eq!(strict.len(), 1);
+
+        let topped_up = memory
+            .search_with_fallback("user_1", "I like coffee", 3, 0.8, 0.1)
+            .await
+            .unwrap();
+        let contents: Vec<&str> = topped_up.iter().map(|r| r.memory.content.as_str()).collect();
+        assert_eq!(contents, vec!["I like coffee", "I walk the dog", "I play chess"]);
+
+        assert!(matches!(
+            memory.search_with_fallback("user_1", "I like coffee", 3, 0.1, 0.8).await,
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_scored() {
+        let memory = local_memory();
+        let mut ids = HashMap::new();
+        for content in ["I like coffee", "I walk the dog", "I play chess"] {
+            ids.insert(content, memory.add("user_1", content, None).await.unwrap().id);
+        }
+
+        let plain = memory.search("user_1", "I like coffee", 3).await.unwrap();
+        let scored = memory
+            .search_scored("user_1", "I like coffee", 3, ScoringConfig::new())
+            .await
+            .unwrap();
+        let ranking = |results: &[SearchResultItem]| {
+            results.iter().map(|r| (r.memory.id.clone(), r.score)).collect::<Vec<_>>()
+        };
+        assert_eq!(ranking(&scored), ranking(&plain));
+
+        // Importance lifts the less similar memory above the exact match
+        for (content, importance) in [("I like coffee", "0.0"), ("I walk the dog", "1.0")] {
+            memory
+                .update_metadata(&ids[content], HashMap::from([(IMPORTANCE_KEY.to_string(), importance.to_string())]))
+                .await
+                .unwrap();
+        }
+        let weighted = memory
+            .search_scored("user_1", "I like coffee", 3, ScoringConfig::new().with_importance_weight(1.0))
+            .await
+            .unwrap();
+        assert_eq!(weighted[0].memory.content, "I walk the dog");
+
+        // Pure keyword relevance is the share of query tokens present
+        let keyword = memory
+            .search_scored("user_1", "I like coffee", 3, ScoringConfig::new().with_keyword_alpha(1.0))
+            .await
+            .unwrap();
+        assert_eq!(keyword[0].memory.content, "I like coffee");
+        assert!((keyword[0].score - 1.0).abs() < 1e-6);
+        assert!((keyword[1].score - 1.0 / 3.0).abs() < 1e-6);
+
+        assert!(matches!(
+            memory
+                .search_scored("user_1", "coffee", 3, ScoringConfig::new().with_keyword_alpha(1.5))
+                .await,
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_recency() {
+        let store = Arc::new(InMemoryStore::new());
+        let embedder = LocalEmbedder::with_defaults();
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let query = embedder.embed("favourite drink").await.unwrap();
+        let mut nearby = query.clone();
+        for value in nearby.iter_mut().take(40) {
+            *value = -*value;
+        }
+
+        let old = (Utc::now() - chrono::Duration::days(60)).to_rfc3339();
+        let recent = Utc::now().to_rfc3339();
+        store
+            .upsert(
+                "memory_user_1",
+                vec![
+                    ("old".to_string(), query.clone(), stored_metadata("old", "Likes tea", &old)),
+                    ("recent".to_string(), nearby, stored_metadata("recent", "Likes coffee", &recent)),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let plain = memory.search("user_1", "favourite drink", 2).await.unwrap();
+        assert_eq!(plain[0].memory.id, "old");
+
+        let decayed = memory
+            .search_recency("user_1", "favourite drink", 2, 7.0)
+            .await
+            .unwrap();
+        assert_eq!(decayed[0].memory.id, "recent");
+
+        let undecayed = memory
+            .search_recency("user_1", "favourite drink", 2, 1.0e9)
+            .await
+            .unwrap();
+        assert_eq!(undecayed[0].memory.id, "old");
+    }
+
+    #[tokio::test]
+    async fn test_suggest_threshold() {
+        let store = Arc::new(InMemoryStore::new());
+        let embedder = LocalEmbedder::with_defaults();
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        // Vectors with more negated components are less similar to the query
+        let query = embedder.embed("weekend plans").await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        let entries = [0, 4, 8, 150, 160, 170]
+            .into_iter()
+            .map(|negated| {
+                let mut vector = query.clone();
+                for value in vector.iter_mut().take(negated) {
+                    *value = -*value;
+                }
+                let id = format!("n{}", negated);
+                let metadata = stored_metadata(&id, "memory", &now);
+                (id, vector, metadata)
+            })
+            .collect();
+        store.upsert("memory_user_1", entries).await.unwrap();
+
+        let results = memory.search("user_1", "weekend plans", 10).await.unwrap();
+        let score = |id: &str| results.iter().find(|r| r.memory.id == id).unwrap().score;
+        let lowest_relevant = score("n8");
+        let highest_tail = score("n150");
+
+        let threshold = memory
+            .suggest_threshold("user_1", &["weekend plans"])
+            .await
+            .unwrap();
+        assert!(threshold < lowest_relevant, "{} >= {}", threshold, lowest_relevant);
+        assert!(threshold > highest_tail, "{} <= {}", threshold, highest_tail);
+    }
+
+    #[tokio::test]
+    async fn test_search_mmr_diversity() {
+        let store = Arc::new(InMemoryStore::new());
+        let embedder = LocalEmbedder::with_defaults();
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let query = embedder.embed("morning routine").await.unwrap();
+        let mut nearby = query.clone();
+        for value in nearby.iter_mut().take(40) {
+            *value = -*value;
+        }
+
+        let now = Utc::now().to_rfc3339();
+        store
+            .upsert(
+                "memory_user_1",
+                vec![
+                    ("dup_1".to_string(), query.clone(), stored_metadata("dup_1", "Drinks coffee at 7am", &now)),
+                    ("dup_2".to_string(), query.clone(), stored_metadata("dup_2", "Drinks coffee at 7am", &now)),
+                    ("other".to_string(), nearby, stored_metadata("other", "Goes for a run", &now)),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let relevance = memory.search_mmr("user_1", "morning routine", 2, 1.0).await.unwrap();
+        let ids: HashSet<String> = relevance.iter().map(|r| r.memory.id.clone()).collect();
+        assert_eq!(ids, HashSet::from(["dup_1".to_string(), "dup_2".to_string()]));
+
+        let diverse = memory.search_mmr("user_1", "morning routine", 2, 0.3).await.unwrap();
+        assert_eq!(diverse.len(), 2);
+        assert_eq!(diverse[1].memory.id, "other");
+    }
+
+    #[test]
+    fn test_recency_factor_unparseable() {
+        assert_eq!(recency_factor("not a date", Utc::now(), 7.0), 1.0);
+    }
+
+    struct MockLlm {
+        response: String,
+    }
+
+    #[async_trait]
+    impl LlmBase for MockLlm {
+        async fn generate(
+            &self,
+            _prompt: &str,
+            _params: Option<crate::llm::GenerationParams>,
+        ) -> crate::Result<String> {
+            Ok(self.response.clone())
+        }
+    }
+
+    /// LLM that records its prompts and answers with a fixed response
+    struct PromptRecordingLlm {
+        response: String,
+        prompts: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LlmBase for PromptRecordingLlm {
+        async fn generate(
+            &self,
+            prompt: &str,
+            _params: Option<crate::llm::GenerationParams>,
+        ) -> crate::Result<String> {
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            Ok(self.response.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_answer_uses_top_memory_as_context() {
+        let memory = local_memory();
+        let paris = memory.add("user_1", "I live in Paris", None).await.unwrap();
+        memory.add("user_1", "My dog is called Rex", None).await.unwrap();
+        memory.add("user_2", "I live in Berlin", None).await.unwrap();
+
+        // LocalEmbedder only ranks exact text first, so ask with the stored wording
+        let llm = PromptRecordingLlm {
+            response: " You live in Paris. \n".to_string(),
+            prompts: std::sync::Mutex::new(Vec::new()),
+        };
+        let (answer, sources) = memory
+            .answer_with_sources("user_1", "I live in Paris", 1, &llm)
+            .await
+            .unwrap();
+
+        assert_eq!(answer, "You live in Paris.");
+        assert_eq!(sources, vec![paris.id]);
+        let prompt = llm.prompts.lock().unwrap()[0].clone();
+        assert!(prompt.contains("- I live in Paris"));
+        assert!(prompt.contains("Question: I live in Paris"));
+        assert!(!prompt.contains("Berlin"));
+        assert!(!prompt.contains("Rex"));
+
+        assert_eq!(
+            memory.answer("user_1", "I live in Paris", 1, &llm).await.unwrap(),
+            "You live in Paris."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_min_llm_confidence_gate() {
+        let llm = MockLlm {
+            response: r#"{"facts": [
+                {"content": "I live in Paris", "memory_type": "fact", "confidence": 0.9},
+                {"content": "I might own a boat", "memory_type": "fact", "confidence": 0.3}
+            ]}"#
+            .to_string(),
+        };
+
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_min_llm_confidence(0.5),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let result = memory
+            .add_from_conversation("user_1", "user: I live in Paris. Maybe a boat?", &llm)
+            .await
+            .unwrap();
+        assert_eq!(result.added.len(), 1);
+        assert_eq!(result.added[0].content, "I live in Paris");
+        assert_eq!(result.added[0].metadata.get(LLM_CONFIDENCE_KEY).map(String::as_str), Some("0.9"));
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].content, "I might own a boat");
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 1);
+
+        let downgrading = Memory::new(
+            MemoryConfig::new("memory.db".to_string())
+                .with_min_llm_confidence(0.5)
+                .with_unverified_downgrade(true),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let result = downgrading
+            .add_from_conversation("user_1", "user: I live in Paris. Maybe a boat?", &llm)
+            .await
+            .unwrap();
+        assert!(result.skipped.is_empty());
+        assert_eq!(result.added[1].memory_type, UNVERIFIED_MEMORY_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_add_classified_below_gate() {
+        let llm = MockLlm {
+            response: r#"{"memory_type": "preference", "confidence": 0.4}"#.to_string(),
+        };
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_min_llm_confidence(0.5),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        assert!(memory.add_classified("user_1", "I like tea", &llm).await.unwrap().is_none());
+
+        let item = local_memory()
+            .add_classified("user_1", "I like tea", &llm)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.memory_type, "preference");
+    }
+
+    #[tokio::test]
+    async fn test_collection_schema_enforced_on_add() {
+        use crate::vector_store::CollectionSchema;
+
+        let memory = local_memory();
+        let schema = CollectionSchema::new().with_required_key("source");
+        memory
+            .ensure_collection_with("user_1", CollectionConfig::new(384).with_schema(schema))
+            .await
+            .unwrap();
+
+        let err = memory
+            .add_with_options("user_1", "I like coffee", AddOptions::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)));
+
+        let options = AddOptions::new().with_metadata("source".to_string(), "chat".to_string());
+        let item = memory
+            .add_with_options("user_1", "I like coffee", options)
+            .await
+            .unwrap();
+        assert_eq!(item.metadata.get("source").map(String::as_str), Some("chat"));
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_classify() {
+        let memory = local_memory();
+        let llm = MockLlm {
+            response: r#"{"memory_type": "event", "confidence": 0.8}"#.to_string(),
+        };
+        let classification = memory.classify("Went to Paris last week", &llm).await.unwrap();
+        assert_eq!(classification.memory_type, "event");
+        assert_eq!(classification.confidence, 0.8);
+
+        let llm = MockLlm {
+            response: "Category: preference.".to_string(),
+        };
+        let classification = memory.classify("I like coffee", &llm).await.unwrap();
+        assert_eq!(classification.memory_type, "preference");
+    }
+
+    #[tokio::test]
+    async fn test_merge() {
+        let memory = local_memory();
+        let first = memory.add("user_1", "Likes coffee", Some("preference")).await.unwrap();
+        let second = memory
+            .add("user_1", "Drinks coffee every morning", Some("preference"))
+            .await
+            .unwrap();
+        let llm = MockLlm {
+            response: "Likes coffee and drinks it every morning".to_string(),
+        };
+
+        let merged = memory
+            .merge("user_1", vec![first.id.clone(), second.id.clone()], &llm)
+            .await
+            .unwrap();
+        assert_eq!(merged.content, "Likes coffee and drinks it every morning");
+        assert_eq!(merged.memory_type, "preference");
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, merged.id);
+        assert!(matches!(memory.delete(&first.id).await, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_search_by_type_with_subtypes() {
+        let memory = local_memory()
+            .with_taxonomy(TypeTaxonomy::new().with_subtype("preference", "dietary_preference"));
+        memory.add("user_1", "Prefers window seats", Some("preference")).await.unwrap();
+        memory
+            .add("user_1", "Is vegetarian", Some("dietary_preference"))
+            .await
+            .unwrap();
+        memory.add("user_1", "Lives in Paris", Some("fact")).await.unwrap();
+
+        let with_subtypes = memory.search_by_type("user_1", "preference", true).await.unwrap();
+        assert_eq!(with_subtypes.len(), 2);
+        assert!(with_subtypes.iter().any(|m| m.memory_type == "dietary_preference"));
+
+        let exact = memory.search_by_type("user_1", "preference", false).await.unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].memory_type, "preference");
+    }
+
+    #[tokio::test]
+    async fn test_min_score_floor() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_min_score(0.6),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.add("user_1", "The train leaves at noon", None).await.unwrap();
+        memory.add("user_1", "My sister lives in Oslo", None).await.unwrap();
+
+        let results = memory.search("user_1", "I like coffee", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.content, "I like coffee");
+
+        // Without a floor, unrelated memories are kept even at negative scores
+        let unfloored = local_memory();
+        for content in ["I like coffee", "The train leaves at noon", "My sister lives in Oslo"] {
+            unfloored.add("user_1", content, None).await.unwrap();
+        }
+        let results = unfloored.search("user_1", "I like coffee", 10).await.unwrap();
+        assert_eq!(results.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_normalized_scores() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_normalized_scores(true),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.add("user_1", "The train leaves at noon", None).await.unwrap();
+
+        let results = memory.search("user_1", "I like coffee", 10).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!((results[0].score - 1.0).abs() < 0.001);
+        assert!(results.iter().all(|r| (0.0..=1.0).contains(&r.score)));
+    }
+
+    #[tokio::test]
+    async fn test_configured_id_strategy() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_id_strategy(crate::config::IdStrategy::Uuidv7),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let item = memory.add("user_1", "I like tea", None).await.unwrap();
+        let id = uuid::Uuid::parse_str(&item.id).unwrap();
+        assert_eq!(id.get_version_num(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_search_zero_limit() {
+        let memory = local_memory();
+        let result = memory.search("user_1", "tea", 0).await;
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn test_diff() {
+        let memory = local_memory();
+        memory.add("user_a", "I like tea", None).await.unwrap();
+        memory.add("user_a", "I live in Paris", None).await.unwrap();
+        memory.add("user_b", "I like tea", None).await.unwrap();
+        memory.add("user_b", "I live in Rome", None).await.unwrap();
+
+        let diff = memory.diff("user_a", "user_b").await.unwrap();
+
+        assert_eq!(diff.common.len(), 1);
+        assert_eq!(diff.common[0].content, "I like tea");
+        assert_eq!(diff.only_a.len(), 1);
+        assert_eq!(diff.only_a[0].content, "I live in Paris");
+        assert_eq!(diff.only_b.len(), 1);
+        assert_eq!(diff.only_b[0].content, "I live in Rome");
+    }
+
+    #[tokio::test]
+    async fn test_generate_insights() {
+        let memory = local_memory();
+        memory.add("user_1", "I drink tea every morning", Some("fact")).await.unwrap();
+        memory.add("user_1", "I run five kilometers daily", Some("fact")).await.unwrap();
+        let llm = MockLlm {
+            response: "- Values morning routines\n- Prioritizes fitness\n".to_string(),
+        };
+
+        let insights = memory.generate_insights("user_1", &llm).await.unwrap();
+        assert_eq!(insights.len(), 2);
+        assert!(insights.iter().all(|m| m.memory_type == "insight"));
+        assert_eq!(insights[0].content, "Values morning routines");
+
+        let repeated = memory.generate_insights("user_1", &llm).await.unwrap();
+        assert!(repeated.is_empty());
+        assert_eq!(memory.search_by_type("user_1", "insight", false).await.unwrap().len(), 2);
+    }
+
+    /// Embedder whose vectors do not match its reported dimension
+    struct MismatchedEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for MismatchedEmbedder {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+            Ok(vec![0.5; 3])
+        }
+
+        fn dimension(&self) -> usize {
+            384
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dimension_mismatch() {
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(MismatchedEmbedder),
+        );
+
+        let result = memory.search("user_1", "tea", 5).await;
+        match result {
+            Err(Error::InvalidArgument(msg)) => assert_eq!(msg, "query dim 3 != expected 384"),
+            other => panic!("expected dimension error, got {:?}", other.map(|r| r.len())),
+        }
+
+        let result = memory.add("user_1", "I like tea", None).await;
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+        assert_eq!(store.count("memory_user_1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_round_trip() {
+        let memory = local_memory();
+        let attachment = Attachment::new(
+            "s3://docs/contract.pdf".to_string(),
+            "application/pdf".to_string(),
+        )
+        .with_sha256("abc123".to_string());
+
+        memory
+            .add_with_options(
+                "user_1",
+                "The contract PDF we discussed",
+                AddOptions::new().with_attachment(attachment.clone()),
+            )
+            .await
+            .unwrap();
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert_eq!(all[0].attachments, vec![attachment.clone()]);
+        assert!(!all[0].metadata.contains_key(ATTACHMENTS_KEY));
+
+        let results = memory.search("user_1", "contract", 1).await.unwrap();
+        assert_eq!(results[0].memory.attachments, vec![attachment]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_attachments() {
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let mut metadata = stored_metadata("broken", "Has bad attachments", &Utc::now().to_rfc3339());
+        metadata
+            .custom_metadata
+            .insert(ATTACHMENTS_KEY.to_string(), "{not json".to_string());
+        store
+            .upsert("memory_user_1", vec![("broken".to_string(), vec![0.1; 384], metadata)])
+            .await
+            .unwrap();
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].attachments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_explain_ranking() {
+        let memory = local_memory();
+        memory.add("user_1", "I like green tea", None).await.unwrap();
+        let important = memory.add("user_1", "I like black tea", None).await.unwrap();
+        memory
+            .update_metadata(&important.id, HashMap::from([(IMPORTANCE_KEY.to_string(), "1.0".to_string())]))
+            .await
+            .unwrap();
+
+        let explanations = memory.explain_ranking("user_1", "tea", 5).await.unwrap();
+        let weighted = memory.search_weighted("user_1", "tea", 5).await.unwrap();
+
+        assert_eq!(explanations.len(), weighted.len());
+        for (explanation, result) in explanations.iter().zip(&weighted) {
+            assert_eq!(explanation.memory.id, result.memory.id);
+            assert!(explanation.normalized_score.is_none());
+            assert_eq!(explanation.feedback_bonus, 0.0);
+            let product = explanation.vector_score * explanation.importance_multiplier;
+            assert!((explanation.final_score - product).abs() < 1e-6);
+            assert!((explanation.final_score - result.score).abs() < 1e-6);
+        }
+
+        let boosted = explanations.iter().find(|e| e.memory.id == important.id).unwrap();
+        assert_eq!(boosted.importance_multiplier, 1.5);
+    }
+
+    #[tokio::test]
+    async fn test_search_cross_encoder_reorders() {
+        let store = Arc::new(InMemoryStore::new());
+        let embedder = LocalEmbedder::with_defaults();
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        // Vector order follows the number of negated components, the reverse
+        // of the token overlap with the query
+        let query = "morning coffee routine";
+        let query_embedding = embedder.embed(query).await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        let entries = [(0, "I walk the dog"), (4, "I drink coffee"), (8, "My morning coffee routine")]
+            .into_iter()
+            .map(|(negated, text)| {
+                let mut vector = query_embedding.clone();
+                for value in vector.iter_mut().take(negated) {
+                    *value = -*value;
+                }
+                let id = format!("n{}", negated);
+                let metadata = stored_metadata(&id, text, &now);
+                (id, vector, metadata)
+            })
+            .collect();
+        store.upsert("memory_user_1", entries).await.unwrap();
+
+        let plain = memory.search("user_1", query, 3).await.unwrap();
+        assert_eq!(plain[0].memory.id, "n0");
+
+        let reranked = memory
+            .search_cross_encoder("user_1", query, 2, &crate::rerank::TokenOverlapReranker::new())
+            .await
+            .unwrap();
+        let ids: Vec<&str> = reranked.iter().map(|r| r.memory.id.as_str()).collect();
+        assert_eq!(ids, vec!["n8", "n4"]);
+    }
+
+    #[tokio::test]
+    async fn test_export_to_writer_streams_pages() {
+        let store = Arc::new(RecordingStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let now = Utc::now().to_rfc3339();
+        store.create_collection("memory_user_1", 384).await.unwrap();
+        let vectors = (0..1000)
+            .map(|i| {
+                let id = format!("mem_{:04}", i);
+                let metadata = stored_metadata(&id, &format!("memory {}", i), &now);
+                (id, vec![0.0; 384], metadata)
+            })
+            .collect();
+        store.upsert("memory_user_1", vectors).await.unwrap();
+
+        let mut output = Vec::new();
+        let exported = memory.export_to_writer("user_1", &mut output).await.unwrap();
+        assert_eq!(exported, 1000);
+
+        let lines: Vec<MemoryItem> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1000);
+        assert_eq!(lines[0].id, "mem_0000");
+        assert_eq!(lines[999].id, "mem_0999");
+
+        // Ten full pages, then an empty one ends the scan
+        let scans: Vec<usize> = store
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                StoreCall::Scan { limit, .. } => Some(limit),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(scans, vec![EXPORT_PAGE_SIZE; 11]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_many_groups_by_collection() {
+        let store = Arc::new(RecordingStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let mut ids = Vec::new();
+        for (user, content) in [("user_1", "I like tea"), ("user_2", "I like coffee"), ("user_1", "I live in Paris")] {
+            ids.push(memory.add(user, content, None).await.unwrap().id);
+        }
+        ids.push("unknown".to_string());
+
+        store.clear();
+        let result = memory.delete_many(&ids).await.unwrap();
+
+        assert_eq!(result.successful, 3);
+        assert_eq!(result.failed, 1);
+        let mut deletes: Vec<(String, Vec<String>)> = store
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                StoreCall::Delete { collection, ids } => Some((collection, ids)),
+                _ => None,
+            })
+            .collect();
+        deletes.sort();
+        assert_eq!(deletes.len(), 2);
+        assert_eq!(deletes[0].0, "memory_user_1");
+        assert_eq!(deletes[0].1.len(), 2);
+        assert_eq!(deletes[1].0, "memory_user_2");
+        assert!(memory.get_all("user_1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_changes() {
+        let memory = local_memory();
+        let mut events = memory.subscribe();
+
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.search("user_1", "coffee", 1).await.unwrap();
+        memory.delete(&item.id).await.unwrap();
+
+        assert_eq!(
+            events.recv().await.unwrap(),
+            MemoryEvent::Added {
+                memory_id: item.id.clone(),
+                user_id: Some("user_1".to_string()),
+            }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            MemoryEvent::Deleted {
+                memory_id: item.id.clone(),
+                user_id: Some("user_1".to_string()),
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_writes_emit_events() {
+        let memory = local_memory();
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+        let snapshot = memory.export_snapshot().await.unwrap();
+        let mut events = memory.subscribe();
+
+        memory.record_feedback(&item.id, true).await.unwrap();
+        assert_eq!(
+            events.recv().await.unwrap(),
+            MemoryEvent::Updated {
+                memory_id: item.id.clone(),
+                user_id: Some("user_1".to_string()),
+            }
+        );
+
+        let restored = local_memory();
+        let mut restored_events = restored.subscribe();
+        restored.import_snapshot(snapshot).await.unwrap();
+        assert_eq!(
+            restored_events.recv().await.unwrap(),
+            MemoryEvent::Added {
+                memory_id: item.id.clone(),
+                user_id: Some("user_1".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_user() {
+        let memory = local_memory();
+        let paris = memory.add("old_user", "I live in Paris", Some("fact")).await.unwrap();
+        let tea = memory.add("old_user", "I like tea", Some("preference")).await.unwrap();
+        let existing = memory.add("new_user", "I have a cat", None).await.unwrap();
+
+        assert_eq!(memory.rename_user("old_user", "new_user").await.unwrap(), 2);
+
+        let mut moved = memory.get_all("new_user").await.unwrap();
+        moved.sort_by(|a, b| a.content.cmp(&b.content));
+        let contents: Vec<&str> = moved.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["I have a cat", "I like tea", "I live in Paris"]);
+        assert!(moved.iter().all(|m| m.user_id == "new_user"));
+
+        let fetched = memory.get(&paris.id).await.unwrap().unwrap();
+        assert_eq!(fetched.user_id, "new_user");
+        assert_eq!(fetched.memory_type, "fact");
+        assert!(memory.get(&tea.id).await.unwrap().is_some());
+        assert!(memory.get(&existing.id).await.unwrap().is_some());
+        assert_eq!(memory.search("new_user", "I live in Paris", 1).await.unwrap()[0].memory.id, paris.id);
+        assert!(memory.get_all("old_user").await.unwrap().is_empty());
+
+        assert_eq!(memory.rename_user("missing", "other").await.unwrap(), 0);
+        assert!(memory.rename_user("new_user", "new_user").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_configured_distance_metric() {
+        use crate::vector_store::DistanceMetric;
+
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string())
+                .with_distance_metric(DistanceMetric::DotProduct),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        // Cosine would score a scaled copy of the stored vector as 1.0
+        let query = LocalEmbedder::with_defaults().embed("I like coffee").await.unwrap();
+        let scaled: Vec<f32> = query.iter().map(|v| v * 3.0).collect();
+        let expected = DistanceMetric::DotProduct.score(&query, &scaled);
+        let results = store.search("memory_user_1", scaled, 1, None).await.unwrap();
+        assert!((results[0].score - expected).abs() < 1e-3);
+        assert!((results[0].score - 1.0).abs() > 1e-2);
+    }
+
+    #[tokio::test]
+    async fn test_count_by_type() {
+        let memory = local_memory();
+        memory.add("user_1", "I live in Paris", Some("fact")).await.unwrap();
+        memory.add("user_1", "I work remotely", Some("fact")).await.unwrap();
+        memory.add("user_1", "I like tea", Some("preference")).await.unwrap();
+        memory.add("user_2", "I have a cat", Some("fact")).await.unwrap();
+
+        let counts = memory.count_by_type("user_1").await.unwrap();
+        assert_eq!(
+            counts,
+            HashMap::from([("fact".to_string(), 2), ("preference".to_string(), 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_projected() {
+        let store = Arc::new(RecordingStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+        store.clear();
+        let payloads = || -> Vec<Vec<String>> {
+            store
+                .calls()
+                .into_iter()
+                .filter_map(|call| match call {
+                    StoreCall::SearchWithPayload { payload_fields, .. } => {
+                        Some(payload_fields)
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let results = memory
+            .search_projected("user_1", "coffee", 5, &[MemoryField::Id, MemoryField::Score])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, item.id);
+        assert!(results[0].score > 0.0);
+        assert!(results[0].content.is_empty());
+        assert!(results[0].memory_type.is_empty());
+        assert_eq!(payloads(), vec![Vec::<String>::new()]);
+
+        let results = memory
+            .search_projected("user_1", "coffee", 5, &[MemoryField::Content])
+            .await
+            .unwrap();
+        assert!(results[0].id.is_empty());
+        assert_eq!(results[0].content, "I like coffee");
+        assert_eq!(payloads()[1], vec!["text".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_run_query() {
+        use crate::filtering::{
+            FilterCondition, FilterQuery, FilterValue, LogicalOperator, QueryBuilder, TimeFilter,
+        };
+
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        for content in ["I live in Paris", "I work remotely", "I have a cat"] {
+            memory.add("user_1", content, Some("fact")).await.unwrap();
+        }
+        memory.add("user_1", "I like tea", Some("preference")).await.unwrap();
+
+        let old = (Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+        let mut stale = stored_metadata("stale", "I lived in Rome", &old);
+        stale.memory_type = "fact".to_string();
+        store
+            .upsert("memory_user_1", vec![("stale".to_string(), vec![0.1; 384], stale)])
+            .await
+            .unwrap();
+
+        let query = QueryBuilder::new()
+            .time_filter(TimeFilter::last_n_days("created_at".to_string(), 7))
+            .filter(FilterQuery::new(LogicalOperator::And).add_condition(FilterCondition::eq(
+                "memory_type".to_string(),
+                FilterValue::String("fact".to_string()),
+            )))
+            .limit(2)
+            .build();
+
+        let QueryResult::Rows(rows) = memory.run_query("user_1", query).await.unwrap() else {
+            panic!("expected rows");
+        };
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|m| m.memory_type == "fact" && m.id != "stale"));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_consolidates_oldest_memories() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_max_memories_per_user(3),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        )
+        .with_llm(Arc::new(MockLlm {
+            response: "The user lives in Paris and works remotely".to_string(),
+        }));
+
+        let first = memory.add("user_1", "I live in Paris", Some("fact")).await.unwrap();
+        memory.add("user_1", "I work remotely", Some("fact")).await.unwrap();
+        memory.add("user_1", "I have a cat", Some("fact")).await.unwrap();
+        let latest = memory.add("user_1", "I like tea", Some("preference")).await.unwrap();
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert!(all.len() <= 3);
+        assert!(all.iter().any(|m| m.id == latest.id));
+        assert!(all.iter().all(|m| m.id != first.id));
+        assert!(all
+            .iter()
+            .any(|m| m.memory_type == "insight" && m.content.contains("Paris")));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_counts_all_user_scopes() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string())
+                .with_max_memories_per_user(3)
+                .with_collection_strategy(CollectionStrategy::PerUserAgent),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        )
+        .with_llm(Arc::new(MockLlm {
+            response: "The user lives in Paris and works remotely".to_string(),
+        }));
+
+        for (agent, content) in [("a", "I live in Paris"), ("b", "I work remotely"), ("c", "I have a cat")] {
+            memory
+                .add_with_options("user_1", content, AddOptions::new().with_agent_id(agent.to_string()))
+                .await
+                .unwrap();
+        }
+        memory
+            .add_with_options("user_1", "I like tea", AddOptions::new().with_agent_id("d".to_string()))
+            .await
+            .unwrap();
+
+        let all = memory.get_all_user_scopes("user_1").await.unwrap();
+        assert!(all.len() <= 3);
+        assert!(all.iter().any(|m| m.memory_type == "insight"));
+    }
+
+    /// Store that cannot list its collections
+    #[derive(Default)]
+    struct UnlistedStore {
+        inner: InMemoryStore,
+    }
+
+    #[async_trait]
+    impl VectorStoreBase for UnlistedStore {
+        async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+            self.inner.create_collection(collection_name, vector_size).await
+        }
+
+        async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+            self.inner.collection_exists(collection_name).await
+        }
+
+        async fn upsert(
+            &self,
+            collection_name: &str,
+            vectors: Vec<(String, Vec<f32>, crate::vector_store::VectorMetadata)>,
+        ) -> Result<()> {
+            self.inner.upsert(collection_name, vectors).await
+        }
+
+        async fn search(
+            &self,
+            collection_name: &str,
+            query_vector: Vec<f32>,
+            limit: usize,
+            score_threshold: Option<f32>,
+        ) -> Result<Vec<crate::vector_store::SearchResult>> {
+            self.inner
+                .search(collection_name, query_vector, limit, score_threshold)
+                .await
+        }
+
+        async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+            self.inner.delete(collection_name, ids).await
+        }
+
+        async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+            self.inner.delete_collection(collection_name).await
+        }
+
+        async fn count(&self, collection_name: &str) -> Result<usize> {
+            self.inner.count(collection_name).await
+        }
+
+        async fn get_by_id(
+            &self,
+            collection_name: &str,
+            id: &str,
+        ) -> Result<Option<crate::vector_store::VectorMetadata>> {
+            self.inner.get_by_id(collection_name, id).await
+        }
+
+        async fn get_all(&self, collection_name: &str) -> Result<Vec<crate::vector_store::VectorMetadata>> {
+            self.inner.get_all(collection_name).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capacity_without_collection_listing() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_max_memories_per_user(2),
+            Arc::new(UnlistedStore::default()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        )
+        .with_llm(Arc::new(MockLlm {
+            response: "The user lives in Paris and works remotely".to_string(),
+        }));
+
+        for content in ["I live in Paris", "I work remotely", "I have a cat"] {
+            memory.add("user_1", content, None).await.unwrap();
+        }
+
+        let all = memory.get_all("user_1").await.unwrap();
+        assert!(all.len() <= 2);
+        assert!(all.iter().any(|m| m.memory_type == "insight"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_consolidation_keeps_add() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_max_memories_per_user(2),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        )
+        .with_llm(Arc::new(MockLlm {
+            response: "  ".to_string(),
+        }));
+
+        memory.add("user_1", "I live in Paris", None).await.unwrap();
+        memory.add("user_1", "I work remotely", None).await.unwrap();
+        let latest = memory.add("user_1", "I like tea", None).await.unwrap();
+
+        // The summary failed, so nothing was evicted
+        let all = memory.get_all("user_1").await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().any(|m| m.id == latest.id));
+    }
+
+    #[tokio::test]
+    async fn test_language_detection_tags_memories() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_language_detection(true),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let french = memory
+            .add("user_1", "Je préfère boire du café le matin avant de partir au travail", None)
+            .await
+            .unwrap();
+        let english = memory
+            .add("user_1", "I usually drink a cup of coffee every morning before I go to the office", None)
+            .await
+            .unwrap();
+        assert_eq!(french.metadata.get(LANG_KEY).map(String::as_str), Some("fra"));
+
+        let stored = memory.find_by_metadata("user_1", LANG_KEY, "eng").await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, english.id);
+        assert_eq!(stored[0].metadata.get(LANG_KEY).map(String::as_str), Some("eng"));
+    }
+
+    #[tokio::test]
+    async fn test_get_by_id() {
+        let memory = local_memory();
+        let added = memory
+            .add_with_options(
+                "user_1",
+                "I like coffee",
+                AddOptions::new().with_memory_type("preference".to_string()),
+            )
+            .await
+            .unwrap();
+        memory
+            .update_metadata(&added.id, HashMap::from([("source".to_string(), "chat".to_string())]))
+            .await
+            .unwrap();
+
+        let fetched = memory.get(&added.id).await.unwrap().unwrap();
+        assert_eq!(fetched.content, "I like coffee");
+        assert_eq!(fetched.memory_type, "preference");
+        assert_eq!(fetched.metadata.get("source").map(String::as_str), Some("chat"));
+
+        assert!(memory.get("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_by_tags() {
+        let memory = local_memory();
+        let add = |content: &'static str, tags: &[&str]| {
+            let options = tags
+                .iter()
+                .fold(AddOptions::new(), |o, t| o.with_tag(t.to_string()));
+            memory.add_with_options("user_1", content, options)
+        };
+        let both = add("Coffee before my morning run", &["coffee", "morning-routine"]).await.unwrap();
+        let coffee = add("I like espresso", &["coffee"]).await.unwrap();
+        add("I read before bed", &["evening"]).await.unwrap();
+
+        let all = memory
+            .search_by_tags("user_1", &["coffee", "morning-routine"], true)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, both.id);
+        assert_eq!(all[0].tags, vec!["coffee", "morning-routine"]);
+
+        let mut any: Vec<String> = memory
+            .search_by_tags("user_1", &["coffee", "morning-routine"], false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        any.sort();
+        let mut expected = vec![both.id, coffee.id];
+        expected.sort();
+        assert_eq!(any, expected);
+    }
+
+    #[tokio::test]
+    async fn test_compact_duplicates_keeps_oldest() {
+        let store = Arc::new(InMemoryStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let distinct = memory.add("user_1", "I live in Paris", None).await.unwrap();
+
+        // Duplicates stored directly, as before dedup-on-add existed
+        let entries = [
+            ("dup_b", "2024-01-02T00:00:00Z"),
+            ("dup_a", "2024-01-01T00:00:00Z"),
+            ("dup_c", "2024-01-03T00:00:00Z"),
+        ]
+        .into_iter()
+        .map(|(id, created_at)| {
+            (id.to_string(), vec![0.1; 384], stored_metadata(id, "I like coffee", created_at))
+        })
+        .collect();
+        store.upsert("memory_user_1", entries).await.unwrap();
+
+        assert_eq!(memory.compact_duplicates("user_1").await.unwrap(), 2);
+
+        let mut ids: Vec<String> = memory
+            .get_all("user_1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        ids.sort();
+        let mut expected = vec!["dup_a".to_string(), distinct.id];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        assert_eq!(memory.compact_duplicates("user_1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_strict_collections_suggest_near_miss() {
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_strict_collections(true),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        memory.add("user-123", "I like coffee", None).await.unwrap();
+        memory.add("alice", "I live in Paris", None).await.unwrap();
+
+        let message = match memory.search("user_123", "coffee", 5).await.unwrap_err() {
+            Error::NotFound(message) => message,
+            other => panic!("expected NotFound, got {:?}", other),
+        };
+        assert!(message.contains("did you mean: memory_user-123?"), "{}", message);
+
+        assert!(memory.get_all("bob").await.is_err());
+        assert_eq!(memory.get_all("user-123").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_shared_respects_acl() {
+        let memory = local_memory();
+        let shared = AddOptions::new().with_reader("bob".to_string());
+        memory
+            .add_with_options("alice", "Team standup is at 9am", shared)
+            .await
+            .unwrap();
+        memory
+            .add_with_options("alice", "Private standup notes", AddOptions::new())
+            .await
+            .unwrap();
+
+        let bob = memory.search_shared("bob", "alice", "standup", 10).await.unwrap();
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].memory.content, "Team standup is at 9am");
+
+        let carol = memory.search_shared("carol", "alice", "standup", 10).await.unwrap();
+        assert!(carol.is_empty());
+
+        let alice = memory.search_shared("alice", "alice", "standup", 10).await.unwrap();
+        assert_eq!(alice.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_shared_past_unshared_candidates() {
+        let memory = local_memory();
+        for i in 0..8 {
+            memory
+                .add("alice", &format!("Private standup notes {}", i), None)
+                .await
+                .unwrap();
+        }
+        let shared = AddOptions::new().with_reader("bob".to_string());
+        memory
+            .add_with_options("alice", "Team meeting is at 9am", shared)
+            .await
+            .unwrap();
+
+        let bob = memory.search_shared("bob", "alice", "standup notes", 1).await.unwrap();
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].memory.content, "Team meeting is at 9am");
+    }
+
+    #[tokio::test]
+    async fn test_ranking_fetches_bounded_candidates() {
+        let store = Arc::new(RecordingStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        for i in 0..20 {
+            memory.add("user_1", &format!("I like coffee {}", i), None).await.unwrap();
+        }
+
+        store.clear();
+        memory.search_weighted("user_1", "coffee", 2).await.unwrap();
+        memory.explain_ranking("user_1", "coffee", 2).await.unwrap();
+        let limits: Vec<usize> = store
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                StoreCall::Search { limit, .. } => Some(limit),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(limits, vec![2 * CANDIDATE_OVERFETCH; 2]);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let ids = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let fused = reciprocal_rank_fusion(&[
+            (ids(&["a", "b", "c"]), 1.0),
+            (ids(&["c", "b", "d"]), 1.0),
+        ]);
+        let order: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+        // b and c appear in both lists; c's first place beats b's two seconds
+        assert_eq!(order, vec!["c", "b", "a", "d"]);
+
+        let weighted = reciprocal_rank_fusion(&[
+            (ids(&["a", "b"]), 1.0),
+            (ids(&["b", "a"]), 3.0),
+        ]);
+        assert_eq!(weighted[0].0, "b");
+    }
+
+    #[tokio::test]
+    async fn test_search_ensemble() {
+        let memory = local_memory();
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.add("user_1", "I live in Paris", None).await.unwrap();
+
+        let embedders: Vec<(Arc<dyn EmbedderBase>, f32)> = vec![
+            (Arc::new(LocalEmbedder::with_defaults()), 1.0),
+            (Arc::new(LocalEmbedder::new(64)), 0.5),
+        ];
+        let results = memory
+            .search_ensemble("user_1", "I like coffee", 1, embedders.clone())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.content, "I like coffee");
+
+        // Ensemble collections are kept out of the user's memories
+        assert_eq!(memory.get_all("user_1").await.unwrap().len(), 2);
+        let results = memory
+            .search_ensemble("user_1", "Paris", 5, embedders)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_collections_follow_embedders() {
+        let memory = local_memory();
+        let coffee = memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.add("user_1", "I live in Paris", None).await.unwrap();
+        let base = memory.get_collection_name("user_1");
+
+        let wide: Vec<(Arc<dyn EmbedderBase>, f32)> =
+            vec![(Arc::new(LocalEmbedder::with_defaults()), 1.0)];
+        let narrow: Vec<(Arc<dyn EmbedderBase>, f32)> = vec![(Arc::new(LocalEmbedder::new(64)), 1.0)];
+        memory.search_ensemble("user_1", "coffee", 2, wide).await.unwrap();
+        // A different embedder in the same position gets its own collection
+        let results = memory.search_ensemble("user_1", "coffee", 2, narrow).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let ensembles = memory.ensemble_collections(&base).await;
+        assert_eq!(ensembles.len(), 2);
+
+        memory.delete(&coffee.id).await.unwrap();
+        for name in &ensembles {
+            assert!(memory.vector_store.get_by_id(name, &coffee.id).await.unwrap().is_none());
+        }
+
+        memory.rename_user("user_1", "user_2").await.unwrap();
+        assert!(memory.ensemble_collections(&base).await.is_empty());
+        for name in &ensembles {
+            assert!(!memory.vector_store.collection_exists(name).await.unwrap());
+        }
+    }
+
+    /// Embedder that takes a long time to respond
+    struct SlowEmbedder;
+
+    #[async_trait]
+    impl EmbedderBase for SlowEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            LocalEmbedder::with_defaults().embed(text).await
+        }
+
+        fn dimension(&self) -> usize {
+            384
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cancels_slow_add() {
+        use crate::context::OperationContext;
+
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(SlowEmbedder),
+        );
+
+        let result = OperationContext::new()
+            .with_timeout(std::time::Duration::from_millis(20))
+            .run(memory.add("user_1", "I like coffee", None))
+            .await;
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        assert!(memory.get_all("user_1").await.unwrap().is_empty());
+    }
+
+    /// LLM that answers with a fixed response after a delay
+    struct SlowLlm {
+        response: String,
+    }
+
+    #[async_trait]
+    impl LlmBase for SlowLlm {
+        async fn generate(
+            &self,
+            _prompt: &str,
+            _params: Option<crate::llm::GenerationParams>,
+        ) -> crate::Result<String> {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            Ok(self.response.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_client_timeouts() {
+        use std::time::Duration;
+
+        let llm = SlowLlm {
+            response: r#"{"facts": [{"content": "I live in Paris"}]}"#.to_string(),
+        };
+        let config = MemoryConfig::new("memory.db".to_string())
+            .with_llm_timeout(Duration::from_secs(5))
+            .with_embedding_timeout(Duration::from_millis(50));
+
+        // The slow LLM call fits its timeout; the slow embedding does not
+        let slow_embedding = Memory::new(
+            config.clone(),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(SlowEmbedder),
+        );
+        let result = slow_embedding
+            .add_from_conversation("user_1", "user: I live in Paris", &llm)
+            .await;
+        assert!(matches!(result, Err(Error::Timeout(msg)) if msg.contains("embedding")));
+
+        let memory = Memory::new(
+            config.clone(),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let result = memory
+            .add_from_conversation("user_1", "user: I live in Paris", &llm)
+            .await
+            .unwrap();
+        assert_eq!(result.added.len(), 1);
+
+        let short_llm = Memory::new(
+            config.with_llm_timeout(Duration::from_millis(10)),
+            Arc::new(InMemoryStore::new()),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+        let result = short_llm
+            .add_from_conversation("user_1", "user: I live in Paris", &llm)
+            .await;
+        assert!(matches!(result, Err(Error::Timeout(msg)) if msg.contains("LLM")));
+    }
+
+    #[tokio::test]
+    async fn test_search_paginated() {
+        let memory = local_memory();
+        for i in 0..7 {
+            memory
+                .add("user_1", &format!("I like coffee number {}", i), None)
+                .await
+                .unwrap();
+        }
+
+        let (first, cursor) = memory
+            .search_paginated("user_1", "coffee", 3, None)
+            .await
+            .unwrap();
+        let (second, _) = memory
+            .search_paginated("user_1", "coffee", 3, cursor)
+            .await
+            .unwrap();
+
+        let mut expected = memory.search("user_1", "coffee", 6).await.unwrap();
+        sort_by_score_then_id(&mut expected);
+        let ids = |results: &[SearchResultItem]| {
+            results.iter().map(|r| r.memory.id.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!([ids(&first), ids(&second)].concat(), ids(&expected));
+
+        // The last page has no next cursor
+        let (rest, cursor) = memory
+            .search_paginated("user_1", "coffee", 10, None)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 7);
+        assert!(cursor.is_none());
+
+        // Pages deeper than the first fetch are still complete
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next) = memory
+                .search_paginated("user_1", "coffee", 1, cursor)
+                .await
+                .unwrap();
+            paged.extend(page);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        let mut all = memory.search("user_1", "coffee", 7).await.unwrap();
+        sort_by_score_then_id(&mut all);
+        let paged: Vec<&str> = paged.iter().map(|r| r.memory.id.as_str()).collect();
+        let all: Vec<&str> = all.iter().map(|r| r.memory.id.as_str()).collect();
+        assert_eq!(paged, all);
+    }
+
+    #[tokio::test]
+    async fn test_content_digest() {
+        let entries = |order: &[&str], coffee: &str| {
+            order
+                .iter()
+                .map(|&id| {
+                    let text = if id == "a" { coffee } else { "I live in Paris" };
+                    (id.to_string(), vec![0.1; 384], stored_metadata(id, text, "2024-01-01T00:00:00Z"))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut digests = Vec::new();
+        for (order, coffee) in [
+            (["a", "b"], "I like coffee"),
+            (["b", "a"], "I like coffee"),
+            (["a", "b"], "I like tea"),
+        ] {
+            let store = Arc::new(InMemoryStore::new());
+            let memory = Memory::new(
+                MemoryConfig::new("memory.db".to_string()),
+                store.clone(),
+                Arc::new(LocalEmbedder::with_defaults()),
+            );
+            for entry in entries(&order, coffee) {
+                store.upsert("memory_user_1", vec![entry]).await.unwrap();
+            }
+            digests.push(memory.content_digest("user_1").await.unwrap());
+        }
+
+        assert_eq!(digests[0], digests[1]);
+        assert_ne!(digests[0], digests[2]);
+    }
+
+    /// Embedder that counts `embed` calls
+    struct CountingEmbedder {
+        inner: LocalEmbedder,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for CountingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.embed(text).await
+        }
+
+        fn dimension(&self) -> usize {
+            self.inner.dimension()
+        }
+    }
+
+    /// Embedder that records the texts it embeds
+    struct RecordingEmbedder {
+        inner: LocalEmbedder,
+        texts: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EmbedderBase for RecordingEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.texts.lock().unwrap().push(text.to_string());
+            self.inner.embed(text).await
+        }
+
+        fn dimension(&self) -> usize {
+            self.inner.dimension()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pii_redacted_before_embedding() {
+        let embedder = Arc::new(RecordingEmbedder {
+            inner: LocalEmbedder::with_defaults(),
+            texts: std::sync::Mutex::new(Vec::new()),
+        });
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()).with_pii_redaction(true),
+            Arc::new(InMemoryStore::new()),
+            embedder.clone(),
+        );
+
+        let item = memory.add("user_1", "call me at 555-123-4567", None).await.unwrap();
+        assert_eq!(item.content, "call me at [PHONE]");
+        assert_eq!(memory.get(&item.id).await.unwrap().unwrap().content, "call me at [PHONE]");
+        assert_eq!(embedder.texts.lock().unwrap().as_slice(), &["call me at [PHONE]".to_string()]);
+
+        let updated = memory.update(&item.id, "write to jane@example.com").await.unwrap();
+        assert_eq!(updated.content, "write to [EMAIL]");
+
+        let swapped = memory
+            .update_cas(&item.id, &updated.updated_at, "call 555-987-6543")
+            .await
+            .unwrap();
+        assert_eq!(swapped.content, "call [PHONE]");
+        assert_eq!(memory.get(&item.id).await.unwrap().unwrap().content, "call [PHONE]");
+        assert_eq!(embedder.texts.lock().unwrap().last().unwrap(), "call [PHONE]");
+
+        let plain = local_memory();
+        let item = plain.add("user_1", "call me at 555-123-4567", None).await.unwrap();
+        assert_eq!(item.content, "call me at 555-123-4567");
+    }
+
+    struct UppercasePostProcessor;
+
+    impl ResultPostProcessor for UppercasePostProcessor {
+        fn process(&self, results: Vec<SearchResultItem>) -> Vec<SearchResultItem> {
+            results
+                .into_iter()
+                .map(|mut result| {
+                    result.memory.content = result.memory.content.to_uppercase();
+                    result
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_processor_applied_to_search() {
+        let memory = local_memory().with_post_processor(Arc::new(UppercasePostProcessor));
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        let results = memory.search("user_1", "I like coffee", 1).await.unwrap();
+        assert_eq!(results[0].memory.id, item.id);
+        assert_eq!(results[0].memory.content, "I LIKE COFFEE");
+        assert_eq!(memory.get(&item.id).await.unwrap().unwrap().content, "I like coffee");
+    }
+
+    #[tokio::test]
+    async fn test_post_processor_applied_at_every_entry_point() {
+        let memory = local_memory().with_post_processor(Arc::new(UppercasePostProcessor));
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        let projected = memory
+            .search_projected("user_1", "coffee", 1, &[MemoryField::Content])
+            .await
+            .unwrap();
+        assert_eq!(projected[0].content, "I LIKE COFFEE");
+
+        let (page, _) = memory.search_paginated("user_1", "coffee", 1, None).await.unwrap();
+        assert_eq!(page[0].memory.content, "I LIKE COFFEE");
+
+        let shared = memory.search_shared("user_2", "user_1", "coffee", 1).await.unwrap();
+        assert!(shared.is_empty());
+        let own = memory.search_shared("user_1", "user_1", "coffee", 1).await.unwrap();
+        assert_eq!(own[0].memory.content, "I LIKE COFFEE");
+
+        let deduped = memory.search_deduped("user_1", "coffee", 1, 0.99).await.unwrap();
+        assert_eq!(deduped[0].memory.content, "I LIKE COFFEE");
+
+        // Derived searches work on stored content, not processed results
+        let llm = PromptRecordingLlm {
+            response: "Coffee".to_string(),
+            prompts: std::sync::Mutex::new(Vec::new()),
+        };
+        memory.answer("user_1", "What do I like?", 1, &llm).await.unwrap();
+        assert!(llm.prompts.lock().unwrap()[0].contains("- I like coffee"));
+    }
+
+    #[tokio::test]
+    async fn test_embedding_prefixes_by_mode() {
+        let embedder = Arc::new(RecordingEmbedder {
+            inner: LocalEmbedder::with_defaults(),
+            texts: std::sync::Mutex::new(Vec::new()),
+        });
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string())
+                .with_embedding_query_prefix("search_query: ".to_string())
+                .with_embedding_document_prefix("search_document: ".to_string()),
+            Arc::new(InMemoryStore::new()),
+            embedder.clone(),
+        );
+
+        memory.add("user_1", "I like coffee", None).await.unwrap();
+        memory.search("user_1", "coffee", 1).await.unwrap();
+
+        assert_eq!(
+            embedder.texts.lock().unwrap().as_slice(),
+            &["search_document: I like coffee".to_string(), "search_query: coffee".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_skips_embedding_for_unchanged_content() {
+        let embedder = Arc::new(CountingEmbedder {
+            inner: LocalEmbedder::with_defaults(),
+            calls: AtomicUsize::new(0),
+        });
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            Arc::new(InMemoryStore::new()),
+            embedder.clone(),
+        );
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+        let calls = embedder.calls.load(Ordering::SeqCst);
+
+        let updated = memory.update(&item.id, "I like coffee").await.unwrap();
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), calls);
+        assert_eq!(updated.content, "I like coffee");
+        assert_eq!(memory.search("user_1", "coffee", 1).await.unwrap()[0].memory.id, item.id);
+
+        let calls = embedder.calls.load(Ordering::SeqCst);
+        memory.update(&item.id, "I like tea").await.unwrap();
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), calls + 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_metadata() {
+        let embedder = Arc::new(CountingEmbedder {
+            inner: LocalEmbedder::with_defaults(),
+            calls: AtomicUsize::new(0),
+        });
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            Arc::new(InMemoryStore::new()),
+            embedder.clone(),
+        );
+
+        let alpha = memory.add("user_1", "Kickoff on Monday", None).await.unwrap();
+        let beta = memory.add("user_1", "Deadline on Friday", None).await.unwrap();
+        memory
+            .update_metadata(&alpha.id, HashMap::from([("project".to_string(), "alpha".to_string())]))
+            .await
+            .unwrap();
+        memory
+            .update_metadata(&beta.id, HashMap::from([("project".to_string(), "alpha-2".to_string())]))
+            .await
+            .unwrap();
+
+        let calls = embedder.calls.load(Ordering::SeqCst);
+        let found = memory.find_by_metadata("user_1", "project", "alpha").await.unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, alpha.id);
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), calls);
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: std::sync::Mutex<Vec<AuditEvent>>,
+    }
+
+    impl AuditSink for RecordingSink {
+        fn record(&self, event: AuditEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_trail() {
+        let sink = Arc::new(RecordingSink::default());
+        let memory = local_memory().with_audit_sink(sink.clone());
+
+        let item = memory.add("user_1", "I like tea", None).await.unwrap();
+        memory.search("user_1", "tea", 5).await.unwrap();
+        memory.update(&item.id, "I like green tea").await.unwrap();
+        memory.delete(&item.id).await.unwrap();
+
+        let events = sink.events.lock().unwrap();
+        let operations: Vec<AuditOperation> = events.iter().map(|e| e.operation).collect();
+        assert_eq!(
+            operations,
+            vec![
+                AuditOperation::Add,
+                AuditOperation::Search,
+                AuditOperation::Update,
+                AuditOperation::Delete,
+            ]
+        );
+        assert_eq!(events[0].memory_id, Some(item.id.clone()));
+        assert_eq!(events[1].memory_id, None);
+        assert!(events.iter().all(|e| e.user_id.as_deref() == Some("user_1")));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_unknown_memory() {
+        let memory = local_memory();
+        let result = memory.record_feedback("missing", true).await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_search_graph_expanded() {
+        use crate::graph::InMemoryGraphStore;
+
+        let memory = local_memory();
+        let claim = memory.add("user_1", "Rust is memory safe", None).await.unwrap();
+        let evidence = memory
+            .add("user_1", "The borrow checker rejects dangling references", None)
+            .await
+            .unwrap();
+
+        let graph = InMemoryGraphStore::new();
+        for item in [&claim, &evidence] {
+            graph
+                .create_node(GraphNode {
+                    id: item.id.clone(),
+                    content: item.content.clone(),
+                    labels: vec!["Memory".to_string()],
+                    properties: HashMap::new(),
+                })
+                .await
+                .unwrap();
+        }
+        graph
+            .create_relationship(GraphRelationship {
+                source_id: evidence.id.clone(),
+                target_id: claim.id.clone(),
+                rel_type: RelationType::Supports,
+                properties: HashMap::new(),
+                weight: None,
+            })
+            .await
+            .unwrap();
+
+        let plain = memory.search("user_1", "Rust is memory safe", 1).await.unwrap();
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].memory.id, claim.id);
+
+        let expanded = memory
+            .search_graph_expanded("user_1", "Rust is memory safe", 1, &graph, 1)
+            .await
+            .unwrap();
+        assert_eq!(expanded.len(), 2);
+        assert_eq!(expanded[1].memory.id, evidence.id);
+        assert_eq!(
+            expanded[1].memory.metadata.get(GRAPH_DERIVED_KEY),
+            Some(&"true".to_string())
+        );
+        assert!(expanded[1].score < expanded[0].score);
+    }
+
+    #[tokio::test]
+    async fn test_search_graph_expanded_skips_other_users() {
+        use crate::graph::InMemoryGraphStore;
+
+        let memory = local_memory();
+        let mine = memory.add("user_1", "Rust is memory safe", None).await.unwrap();
+        let private = memory.add("user_2", "My bank PIN is 1234", None).await.unwrap();
+        let shared = AddOptions::new().with_reader("user_1".to_string());
+        let readable = memory
+            .add_with_options("user_2", "Our team uses Rust", shared)
+            .await
+            .unwrap();
+
+        let graph = InMemoryGraphStore::new();
+        for other in [&private, &readable] {
+            graph
+                .create_relationship(GraphRelationship {
+                    source_id: mine.id.clone(),
+                    target_id: other.id.clone(),
+                    rel_type: RelationType::RelatedTo,
+                    properties: HashMap::new(),
+                    weight: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let expanded = memory
+            .search_graph_expanded("user_1", "Rust is memory safe", 1, &graph, 2)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = expanded.iter().map(|r| r.memory.id.as_str()).collect();
+        assert_eq!(ids, vec![mine.id.as_str(), readable.id.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_clusters() {
+        use crate::graph::InMemoryGraphStore;
+
+        let memory = local_memory();
+        let work = memory.add("user_1", "Works on a Rust compiler", None).await.unwrap();
+        let team = memory.add("user_1", "Leads a team of four", None).await.unwrap();
+        let hobby = memory.add("user_1", "Plays the cello", None).await.unwrap();
+        let other = memory.add("user_2", "Plays the violin", None).await.unwrap();
+
+        let graph = InMemoryGraphStore::new();
+        for (source, target) in [(&work, &team), (&hobby, &other)] {
+            graph
+                .create_relationship(GraphRelationship {
+                    source_id: source.id.clone(),
+                    target_id: target.id.clone(),
+                    rel_type: RelationType::RelatedTo,
+                    properties: HashMap::new(),
+                    weight: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let llm = MockLlm {
+            response: " A summary \n".to_string(),
+        };
+        let clusters = memory.summarize_clusters("user_1", &graph, &llm).await.unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert!(clusters.iter().all(|c| c.summary == "A summary"));
+
+        let mut sizes: Vec<usize> = clusters.iter().map(|c| c.memories.len()).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 2]);
+        assert!(clusters
+            .iter()
+            .flat_map(|c| &c.memories)
+            .all(|m| m.user_id == "user_1"));
+    }
+
+    #[tokio::test]
+    async fn test_relate_and_unrelate() {
+        use crate::graph::InMemoryGraphStore;
+
+        let memory = local_memory();
+        let tea = memory.add("user_1", "I like tea", None).await.unwrap();
+        let kettle = memory.add("user_1", "I bought a kettle", None).await.unwrap();
+        let graph = InMemoryGraphStore::new();
+
+        for _ in 0..2 {
+            memory
+                .relate(&kettle.id, &tea.id, RelationType::Supports, &graph)
+                .await
+                .unwrap();
+        }
+        assert_eq!(graph.node_count().await.unwrap(), 2);
+        assert_eq!(graph.get_node(&tea.id).await.unwrap().unwrap().content, "I like tea");
+        let relationships = graph.get_relationships(&tea.id).await.unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert_eq!(relationships[0].source_id, kettle.id);
+
+        assert!(matches!(
+            memory.relate(&tea.id, "missing", RelationType::RelatedTo, &graph).await,
+            Err(Error::NotFound(_))
+        ));
+        assert_eq!(graph.relationship_count().await.unwrap(), 1);
+
+        memory
+            .unrelate(&kettle.id, &tea.id, RelationType::Supports, &graph)
+            .await
+            .unwrap();
+        assert_eq!(graph.relationship_count().await.unwrap(), 0);
+        assert_eq!(graph.node_count().await.unwrap(), 2);
     }
 
     // Mock implementations for testing
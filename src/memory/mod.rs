@@ -4,16 +4,138 @@ use async_trait::async_trait;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use uuid::Uuid;
 
-use crate::Result;
+use crate::{Error, Result};
+use crate::config::IdStrategy;
+use crate::llm::ExtractedFact;
 use crate::vector_store::VectorMetadata;
 
 pub mod main;
+pub mod audit;
 pub mod dedup;
+pub mod events;
 pub mod batch;
+pub mod postprocess;
+pub mod redact;
+pub mod scoring;
+pub mod snapshot;
+pub mod taxonomy;
 
+pub use audit::{AuditEvent, AuditOperation, AuditSink, JsonlFileAuditSink, NoopAuditSink};
+pub use events::MemoryEvent;
 pub use main::Memory;
+pub use postprocess::{NoopPostProcessor, ResultPostProcessor};
+pub use redact::Redactor;
+pub use scoring::ScoringConfig;
+pub use snapshot::MemorySnapshot;
+pub use taxonomy::TypeTaxonomy;
+
+/// Custom metadata key holding serialized attachments
+pub const ATTACHMENTS_KEY: &str = "attachments";
+
+/// Reference to an external file or binary associated with a memory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Location of the attachment
+    pub uri: String,
+
+    /// MIME type (e.g. "application/pdf")
+    pub mime_type: String,
+
+    /// SHA256 of the attachment content (optional)
+    pub sha256: Option<String>,
+}
+
+impl Attachment {
+    /// Create a new attachment reference
+    pub fn new(uri: String, mime_type: String) -> Self {
+        Self {
+            uri,
+            mime_type,
+            sha256: None,
+        }
+    }
+
+    /// Set content hash
+    pub fn with_sha256(mut self, sha256: String) -> Self {
+        self.sha256 = Some(sha256);
+        self
+    }
+}
+
+/// Options for adding a memory
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    /// Memory type (default: "general")
+    pub memory_type: Option<String>,
+
+    /// Agent ID (optional)
+    pub agent_id: Option<String>,
+
+    /// Run ID (optional)
+    pub run_id: Option<String>,
+
+    /// Attachment references
+    pub attachments: Vec<Attachment>,
+
+    /// Tags (labels)
+    pub tags: Vec<String>,
+
+    /// Users other than the owner allowed to read the memory
+    pub acl: Vec<String>,
+
+    /// Custom metadata
+    pub metadata: HashMap<String, String>,
+}
+
+impl AddOptions {
+    /// Create default add options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set memory type
+    pub fn with_memory_type(mut self, memory_type: String) -> Self {
+        self.memory_type = Some(memory_type);
+        self
+    }
+
+    /// Set agent ID
+    pub fn with_agent_id(mut self, agent_id: String) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    /// Set run ID
+    pub fn with_run_id(mut self, run_id: String) -> Self {
+        self.run_id = Some(run_id);
+        self
+    }
+
+    /// Add an attachment reference
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Add a tag
+    pub fn with_tag(mut self, tag: String) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Add a custom metadata entry
+    pub fn with_metadata(mut self, key: String, value: String) -> Self {
+        self.metadata.insert(key, value);
+        self
+    }
+
+    /// Share the memory read-only with another user
+    pub fn with_reader(mut self, user_id: String) -> Self {
+        self.acl.push(user_id);
+        self
+    }
+}
 
 /// Memory item
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,16 +169,38 @@ pub struct MemoryItem {
 
     /// Custom metadata
     pub metadata: HashMap<String, String>,
+
+    /// Attachment references
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+
+    /// Tags (labels)
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Users other than the owner allowed to read the memory (private when empty)
+    #[serde(default)]
+    pub acl: Vec<String>,
 }
 
 impl MemoryItem {
-    /// Create a new memory item
+    /// Create a new memory item with a random UUIDv4 ID
     pub fn new(
         user_id: String,
         content: String,
         memory_type: String,
     ) -> Self {
-        let id = Uuid::new_v4().to_string();
+        Self::new_with_strategy(user_id, content, memory_type, IdStrategy::Uuidv4)
+    }
+
+    /// Create a new memory item with an ID from the given strategy
+    pub fn new_with_strategy(
+        user_id: String,
+        content: String,
+        memory_type: String,
+        id_strategy: IdStrategy,
+    ) -> Self {
+        let id = id_strategy.generate();
         let hash = Self::compute_hash(&content);
         let now = Utc::now().to_rfc3339();
 
@@ -71,6 +215,9 @@ impl MemoryItem {
             created_at: now.clone(),
             updated_at: now,
             metadata: HashMap::new(),
+            attachments: Vec::new(),
+            tags: Vec::new(),
+            acl: Vec::new(),
         }
     }
 
@@ -97,8 +244,38 @@ impl MemoryItem {
         self
     }
 
+    /// Add an attachment reference
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Add a tag
+    pub fn with_tag(mut self, tag: String) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Check whether the memory has a tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Check whether a user may read the memory
+    pub fn is_readable_by(&self, user_id: &str) -> bool {
+        self.user_id == user_id || self.acl.iter().any(|u| u == user_id)
+    }
+
     /// Convert to vector metadata
+    ///
+    /// Attachments are stored as JSON under `ATTACHMENTS_KEY`.
     pub fn to_vector_metadata(&self) -> VectorMetadata {
+        let mut custom_metadata = self.metadata.clone();
+        if !self.attachments.is_empty() {
+            let json = serde_json::to_string(&self.attachments).unwrap_or_default();
+            custom_metadata.insert(ATTACHMENTS_KEY.to_string(), json);
+        }
+
         VectorMetadata {
             id: self.id.clone(),
             user_id: self.user_id.clone(),
@@ -108,7 +285,40 @@ impl MemoryItem {
             memory_type: self.memory_type.clone(),
             created_at: self.created_at.clone(),
             updated_at: self.updated_at.clone(),
-            custom_metadata: self.metadata.clone(),
+            custom_metadata,
+            tags: self.tags.clone(),
+            acl: self.acl.clone(),
+        }
+    }
+}
+
+impl From<VectorMetadata> for MemoryItem {
+    fn from(mut metadata: VectorMetadata) -> Self {
+        let hash = Self::compute_hash(&metadata.text);
+
+        // Malformed attachment JSON is dropped rather than failing the read
+        let attachments = match metadata.custom_metadata.remove(ATTACHMENTS_KEY) {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring malformed attachments on memory {}: {}", metadata.id, e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        Self {
+            id: metadata.id,
+            user_id: metadata.user_id,
+            agent_id: metadata.agent_id,
+            run_id: metadata.run_id,
+            content: metadata.text,
+            memory_type: metadata.memory_type,
+            hash,
+            created_at: metadata.created_at,
+            updated_at: metadata.updated_at,
+            metadata: metadata.custom_metadata,
+            attachments,
+            tags: metadata.tags,
+            acl: metadata.acl,
         }
     }
 }
@@ -121,6 +331,181 @@ pub struct SearchResultItem {
 
     /// Relevance score (0-1)
     pub score: f32,
+
+    /// Raw distance, when the vector store measures distance natively
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f32>,
+}
+
+/// Field of a memory that a projected search can return
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryField {
+    /// Memory ID
+    Id,
+    /// Relevance score
+    Score,
+    /// Owning user
+    UserId,
+    /// Memory content
+    Content,
+    /// Memory type
+    MemoryType,
+    /// Creation timestamp
+    CreatedAt,
+    /// Last update timestamp
+    UpdatedAt,
+    /// Custom metadata
+    Metadata,
+    /// Tags
+    Tags,
+}
+
+impl MemoryField {
+    /// Name of the `VectorMetadata` payload field backing this field
+    ///
+    /// `None` for fields that are not stored in the payload.
+    pub fn payload_field(&self) -> Option<&'static str> {
+        match self {
+            Self::Id | Self::Score => None,
+            Self::UserId => Some("user_id"),
+            Self::Content => Some("text"),
+            Self::MemoryType => Some("memory_type"),
+            Self::CreatedAt => Some("created_at"),
+            Self::UpdatedAt => Some("updated_at"),
+            Self::Metadata => Some("custom_metadata"),
+            Self::Tags => Some("tags"),
+        }
+    }
+}
+
+/// Search result holding only the projected fields
+///
+/// Fields that were not requested are left empty.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectedResult {
+    /// Memory ID
+    pub id: String,
+    /// Relevance score
+    pub score: f32,
+    /// Owning user
+    pub user_id: String,
+    /// Memory content
+    pub content: String,
+    /// Memory type
+    pub memory_type: String,
+    /// Creation timestamp
+    pub created_at: String,
+    /// Last update timestamp
+    pub updated_at: String,
+    /// Custom metadata
+    pub metadata: HashMap<String, String>,
+    /// Tags
+    pub tags: Vec<String>,
+}
+
+impl ProjectedResult {
+    /// Keep the requested fields of a search hit
+    pub fn project(
+        id: String,
+        score: f32,
+        metadata: VectorMetadata,
+        projection: &[MemoryField],
+    ) -> Self {
+        let mut result = Self::default();
+        for field in projection {
+            match field {
+                MemoryField::Id => result.id = id.clone(),
+                MemoryField::Score => result.score = score,
+                MemoryField::UserId => result.user_id = metadata.user_id.clone(),
+                MemoryField::Content => result.content = metadata.text.clone(),
+                MemoryField::MemoryType => result.memory_type = metadata.memory_type.clone(),
+                MemoryField::CreatedAt => result.created_at = metadata.created_at.clone(),
+                MemoryField::UpdatedAt => result.updated_at = metadata.updated_at.clone(),
+                MemoryField::Metadata => result.metadata = metadata.custom_metadata.clone(),
+                MemoryField::Tags => result.tags = metadata.tags.clone(),
+            }
+        }
+        result
+    }
+}
+
+/// Position after the last result of a search page
+///
+/// Results are ordered by descending score, then ascending ID; the next
+/// page starts strictly after this `(score, id)` watermark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    /// Score of the last returned result
+    pub score: f32,
+
+    /// ID of the last returned result
+    pub id: String,
+}
+
+impl SearchCursor {
+    /// Whether a result comes after this cursor in page order
+    pub fn precedes(&self, result: &SearchResultItem) -> bool {
+        result.score < self.score || (result.score == self.score && result.memory.id > self.id)
+    }
+}
+
+/// Scoring breakdown of a ranked memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankExplanation {
+    /// Memory item
+    pub memory: MemoryItem,
+
+    /// Raw similarity score from the vector store
+    pub vector_score: f32,
+
+    /// Score after normalization, when enabled
+    pub normalized_score: Option<f32>,
+
+    /// Minimum raw score a candidate needed to be considered, if any
+    pub score_floor: Option<f32>,
+
+    /// Multiplier from the memory's importance (`0.5 + importance`)
+    pub importance_multiplier: f32,
+
+    /// Additive bonus from user feedback
+    pub feedback_bonus: f32,
+
+    /// Final score: `(normalized or vector score) * importance_multiplier + feedback_bonus`
+    pub final_score: f32,
+}
+
+/// Difference between two memory sets, matched by content hash
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryDiff {
+    /// Memories only in the first set
+    pub only_a: Vec<MemoryItem>,
+
+    /// Memories only in the second set
+    pub only_b: Vec<MemoryItem>,
+
+    /// Memories in both sets (taken from the first set)
+    pub common: Vec<MemoryItem>,
+}
+
+/// Outcome of storing LLM-extracted facts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtractionResult {
+    /// Memories stored, including facts downgraded to `unverified`
+    pub added: Vec<MemoryItem>,
+
+    /// Facts skipped for falling below `min_llm_confidence`
+    pub skipped: Vec<ExtractedFact>,
+}
+
+/// A community of related memories with an LLM-written summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryCluster {
+    /// Memories in the community
+    pub memories: Vec<MemoryItem>,
+
+    /// Summary of the community
+    pub summary: String,
 }
 
 /// Base trait for memory implementations
@@ -152,6 +537,13 @@ pub trait MemoryBase: Send + Sync {
     /// Delete a memory
     async fn delete(&self, memory_id: &str) -> Result<()>;
 
+    /// Get a single memory by ID, or `None` if it does not exist
+    ///
+    /// The default implementation reports that lookup by ID is unsupported.
+    async fn get(&self, _memory_id: &str) -> Result<Option<MemoryItem>> {
+        Err(Error::invalid_arg("Getting memories by ID is not supported by this implementation"))
+    }
+
     /// Get all memories for a user
     async fn get_all(
         &self,
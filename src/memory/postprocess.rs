@@ -0,0 +1,22 @@
+//! Search result post-processing
+
+use super::SearchResultItem;
+
+/// Transformation applied to search results before they are returned
+///
+/// Use it to redact, translate or reformat results in one place instead of
+/// at every call site. Only the returned results change; stored memories
+/// are untouched.
+pub trait ResultPostProcessor: Send + Sync {
+    /// Transform the results of a search
+    fn process(&self, results: Vec<SearchResultItem>) -> Vec<SearchResultItem>;
+}
+
+/// Post-processor that returns results unchanged
+pub struct NoopPostProcessor;
+
+impl ResultPostProcessor for NoopPostProcessor {
+    fn process(&self, results: Vec<SearchResultItem>) -> Vec<SearchResultItem> {
+        results
+    }
+}
@@ -0,0 +1,100 @@
+//! Regex-based redaction of personal data before storage
+
+use regex::Regex;
+
+use crate::{Error, Result};
+
+/// Token replacing email addresses
+pub const EMAIL_TOKEN: &str = "[EMAIL]";
+
+/// Token replacing phone numbers
+pub const PHONE_TOKEN: &str = "[PHONE]";
+
+/// Token replacing credit-card-like numbers
+pub const CARD_TOKEN: &str = "[CARD]";
+
+/// Default (pattern, token) rules, applied in order
+///
+/// Card numbers go before phone numbers so long digit runs are not
+/// partially taken for a phone number.
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    (r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b", EMAIL_TOKEN),
+    (r"\b\d(?:[ -]?\d){12,18}\b", CARD_TOKEN),
+    (r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b", PHONE_TOKEN),
+];
+
+/// Replaces personal data in text with placeholder tokens
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// Create a redactor for emails, phone numbers and card numbers
+    pub fn new() -> Self {
+        Self {
+            rules: DEFAULT_RULES
+                .iter()
+                .map(|(pattern, token)| {
+                    (Regex::new(pattern).expect("valid default pattern"), token.to_string())
+                })
+                .collect(),
+        }
+    }
+
+    /// Create a redactor without any rules
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Add a rule replacing matches of `pattern` with `token`
+    pub fn with_pattern(mut self, pattern: &str, token: &str) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| Error::invalid_arg(format!("Invalid redaction pattern: {}", e)))?;
+        self.rules.push((regex, token.to_string()));
+        Ok(self)
+    }
+
+    /// Replace every match of every rule
+    pub fn redact(&self, text: &str) -> String {
+        self.rules
+            .iter()
+            .fold(text.to_string(), |redacted, (regex, token)| {
+                regex.replace_all(&redacted, token.as_str()).into_owned()
+            })
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules() {
+        let redactor = Redactor::new();
+        assert_eq!(redactor.redact("call me at 555-123-4567"), "call me at [PHONE]");
+        assert_eq!(redactor.redact("or (555) 123-4567"), "or [PHONE]");
+        assert_eq!(redactor.redact("mail jane.doe@example.com"), "mail [EMAIL]");
+        assert_eq!(
+            redactor.redact("card 4111 1111 1111 1111 expires soon"),
+            "card [CARD] expires soon"
+        );
+        assert_eq!(redactor.redact("I have 2 cats"), "I have 2 cats");
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let redactor = Redactor::empty()
+            .with_pattern(r"\bAB\d{6}\b", "[PASSPORT]")
+            .unwrap();
+        assert_eq!(redactor.redact("passport AB123456"), "passport [PASSPORT]");
+        assert_eq!(redactor.redact("call 555-123-4567"), "call 555-123-4567");
+        assert!(Redactor::empty().with_pattern("(", "[X]").is_err());
+    }
+}
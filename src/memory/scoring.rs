@@ -0,0 +1,97 @@
+//! Combined ranking of search results from several scoring components
+
+use crate::{Error, Result};
+
+/// Feedback bonus per unit of feedback score used by `ScoringConfig::weighted`
+pub const DEFAULT_FEEDBACK_WEIGHT: f32 = 0.05;
+
+/// Weighted combination of ranking components used by `Memory::search_scored`
+///
+/// The final score of a candidate is
+/// `relevance * importance_factor * recency_factor + feedback_weight * feedback_score`,
+/// where `relevance = (1 - keyword_alpha) * similarity + keyword_alpha * keyword_overlap`
+/// and `importance_factor = 1 + importance_weight * (importance - 0.5)`.
+/// The default disables every component except similarity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoringConfig {
+    /// Share of relevance taken from query keyword overlap, in `[0, 1]` (default: 0)
+    pub keyword_alpha: f32,
+    /// Strength of the importance multiplier (default: 0)
+    ///
+    /// At 1 a memory's score is multiplied by `0.5 + importance`.
+    pub importance_weight: f32,
+    /// Half-life in days of the age decay (default: none)
+    ///
+    /// Non-positive values disable the decay.
+    pub recency_half_life_days: Option<f64>,
+    /// Bonus per unit of accumulated feedback (default: 0)
+    pub feedback_weight: f32,
+}
+
+impl ScoringConfig {
+    /// Rank by similarity only
+    pub fn new() -> Self {
+        Self {
+            keyword_alpha: 0.0,
+            importance_weight: 0.0,
+            recency_half_life_days: None,
+            feedback_weight: 0.0,
+        }
+    }
+
+    /// Rank like `Memory::search_weighted`: importance and feedback
+    pub fn weighted() -> Self {
+        Self::new()
+            .with_importance_weight(1.0)
+            .with_feedback_weight(DEFAULT_FEEDBACK_WEIGHT)
+    }
+
+    /// Set the share of relevance taken from keyword overlap
+    pub fn with_keyword_alpha(mut self, alpha: f32) -> Self {
+        self.keyword_alpha = alpha;
+        self
+    }
+
+    /// Set the strength of the importance multiplier
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.importance_weight = weight;
+        self
+    }
+
+    /// Set the half-life of the age decay
+    pub fn with_recency_half_life(mut self, half_life_days: f64) -> Self {
+        self.recency_half_life_days = Some(half_life_days);
+        self
+    }
+
+    /// Set the bonus per unit of feedback
+    pub fn with_feedback_weight(mut self, weight: f32) -> Self {
+        self.feedback_weight = weight;
+        self
+    }
+
+    /// Whether keyword overlap contributes to the score
+    pub fn uses_keywords(&self) -> bool {
+        self.keyword_alpha > 0.0
+    }
+
+    /// Check that the weights are usable
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.keyword_alpha) {
+            return Err(Error::invalid_arg("keyword_alpha must be between 0 and 1"));
+        }
+        if !self.importance_weight.is_finite() || !self.feedback_weight.is_finite() {
+            return Err(Error::invalid_arg("scoring weights must be finite"));
+        }
+        if self.recency_half_life_days.is_some_and(|h| h.is_nan()) {
+            return Err(Error::invalid_arg("recency half-life must be a number"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,69 @@
+//! Portable snapshots of all memories in a store
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{Error, Result};
+use super::MemoryItem;
+
+/// Current snapshot format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Memories of every collection, without vectors
+///
+/// Vectors are recomputed on import, so a snapshot can be restored into a
+/// different store or with a different embedder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    /// Snapshot format version
+    pub version: u32,
+    /// Creation timestamp
+    pub created_at: String,
+    /// Collection name -> memories
+    pub collections: BTreeMap<String, Vec<MemoryItem>>,
+}
+
+impl MemorySnapshot {
+    /// Create an empty snapshot
+    pub fn new() -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            created_at: Utc::now().to_rfc3339(),
+            collections: BTreeMap::new(),
+        }
+    }
+
+    /// Total number of memories
+    pub fn memory_count(&self) -> usize {
+        self.collections.values().map(Vec::len).sum()
+    }
+
+    /// Read a snapshot file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::internal(format!("Failed to read snapshot: {}", e)))?;
+        let snapshot: Self = serde_json::from_str(&json)?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(Error::invalid_arg(format!(
+                "Unsupported snapshot version {}",
+                snapshot.version
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    /// Write a snapshot file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::internal(format!("Failed to write snapshot: {}", e)))
+    }
+}
+
+impl Default for MemorySnapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,79 @@
+//! Memory type taxonomy
+
+use std::collections::{HashMap, HashSet};
+
+/// Hierarchy of memory types (e.g. `dietary_preference` is a `preference`)
+#[derive(Debug, Clone, Default)]
+pub struct TypeTaxonomy {
+    /// Parent type -> direct child types
+    children: HashMap<String, Vec<String>>,
+}
+
+impl TypeTaxonomy {
+    /// Create an empty taxonomy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `child` as a subtype of `parent`
+    pub fn add_subtype(&mut self, parent: &str, child: &str) {
+        let children = self.children.entry(parent.to_string()).or_default();
+        if !children.iter().any(|c| c == child) {
+            children.push(child.to_string());
+        }
+    }
+
+    /// Register a subtype, builder style
+    pub fn with_subtype(mut self, parent: &str, child: &str) -> Self {
+        self.add_subtype(parent, child);
+        self
+    }
+
+    /// Get a type and all of its descendants
+    pub fn descendants(&self, memory_type: &str) -> HashSet<String> {
+        let mut found = HashSet::from([memory_type.to_string()]);
+        let mut stack = vec![memory_type.to_string()];
+
+        while let Some(current) = stack.pop() {
+            for child in self.children.get(&current).into_iter().flatten() {
+                if found.insert(child.clone()) {
+                    stack.push(child.clone());
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Check whether `memory_type` is `ancestor` or one of its descendants
+    pub fn is_a(&self, memory_type: &str, ancestor: &str) -> bool {
+        self.descendants(ancestor).contains(memory_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendants() {
+        let taxonomy = TypeTaxonomy::new()
+            .with_subtype("preference", "dietary_preference")
+            .with_subtype("dietary_preference", "allergy");
+
+        let descendants = taxonomy.descendants("preference");
+        assert_eq!(descendants.len(), 3);
+        assert!(taxonomy.is_a("allergy", "preference"));
+        assert!(taxonomy.is_a("preference", "preference"));
+        assert!(!taxonomy.is_a("preference", "dietary_preference"));
+    }
+
+    #[test]
+    fn test_cycle_terminates() {
+        let taxonomy = TypeTaxonomy::new()
+            .with_subtype("a", "b")
+            .with_subtype("b", "a");
+
+        assert_eq!(taxonomy.descendants("a").len(), 2);
+    }
+}
@@ -0,0 +1,103 @@
+//! Reranking of search candidates by query-document relevance
+
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::Result;
+use crate::utils::{DefaultTokenizer, Tokenizer};
+
+/// Base trait for rerankers such as cross-encoders
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Score each document against the query, returning one score per document in input order
+    async fn rerank(&self, query: &str, docs: Vec<String>) -> Result<Vec<f32>>;
+}
+
+/// Reranker scoring documents by the fraction of query tokens they contain
+///
+/// Cheap and dependency-free; useful for tests and as a lexical baseline.
+#[derive(Clone)]
+pub struct TokenOverlapReranker {
+    tokenizer: Arc<dyn Tokenizer>,
+}
+
+impl TokenOverlapReranker {
+    /// Create a new token overlap reranker using the default tokenizer
+    pub fn new() -> Self {
+        Self {
+            tokenizer: Arc::new(DefaultTokenizer::new()),
+        }
+    }
+
+    /// Set the tokenizer
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Distinct tokens of a text
+    fn tokens(&self, text: &str) -> HashSet<String> {
+        self.tokenizer.tokenize(text).into_iter().collect()
+    }
+}
+
+impl Default for TokenOverlapReranker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Reranker for TokenOverlapReranker {
+    async fn rerank(&self, query: &str, docs: Vec<String>) -> Result<Vec<f32>> {
+        let query_tokens = self.tokens(query);
+        if query_tokens.is_empty() {
+            return Ok(vec![0.0; docs.len()]);
+        }
+
+        Ok(docs
+            .iter()
+            .map(|doc| {
+                let doc_tokens = self.tokens(doc);
+                let overlap = query_tokens.intersection(&doc_tokens).count();
+                overlap as f32 / query_tokens.len() as f32
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_overlap_scores() {
+        let reranker = TokenOverlapReranker::new();
+        let scores = reranker
+            .rerank(
+                "Morning coffee",
+                vec![
+                    "I drink coffee every morning".to_string(),
+                    "I like coffee".to_string(),
+                    "I walk the dog".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(scores, vec![1.0, 0.5, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_stopwords_ignored_with_tokenizer() {
+        let reranker = TokenOverlapReranker::new()
+            .with_tokenizer(Arc::new(DefaultTokenizer::new().with_stopwords_removed(true)));
+        let scores = reranker
+            .rerank("I love coffee", vec!["Coffee, always!".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(scores, vec![0.5]);
+    }
+}
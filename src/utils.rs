@@ -9,6 +9,107 @@ pub fn compute_hash(content: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// KSUID epoch (2014-05-13T16:53:20Z)
+const KSUID_EPOCH: i64 = 1_400_000_000;
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Generate a KSUID: a 4-byte timestamp and 16 random bytes, base62-encoded
+pub fn generate_ksuid() -> String {
+    let timestamp = (chrono::Utc::now().timestamp() - KSUID_EPOCH) as u32;
+
+    let mut bytes: Vec<u8> = timestamp.to_be_bytes().to_vec();
+    bytes.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+
+    // Repeated division of the 160-bit big-endian number by 62
+    let mut digits = Vec::with_capacity(27);
+    while bytes.iter().any(|&b| b != 0) {
+        let mut remainder = 0u32;
+        for byte in bytes.iter_mut() {
+            let value = (remainder << 8) | *byte as u32;
+            *byte = (value / 62) as u8;
+            remainder = value % 62;
+        }
+        digits.push(BASE62[remainder as usize]);
+    }
+    digits.resize(27, b'0');
+    digits.reverse();
+
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Detect the language of a text as an ISO 639-3 code (e.g. `eng`, `fra`)
+///
+/// Returns `None` when detection is not reliable, which is common for
+/// short texts.
+pub fn detect_language(text: &str) -> Option<String> {
+    whatlang::detect(text)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+}
+
+/// Common English stopwords removed by `DefaultTokenizer` when enabled
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he",
+    "her", "his", "i", "in", "is", "it", "its", "me", "my", "of", "on", "or", "our", "she", "so",
+    "that", "the", "their", "them", "they", "this", "to", "was", "we", "were", "what", "when",
+    "which", "who", "will", "with", "you", "your",
+];
+
+/// Splits text into normalized tokens for keyword matching
+pub trait Tokenizer: Send + Sync {
+    /// Tokenize a text, in order of appearance
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+/// Tokenizer that lowercases, splits on non-alphanumeric characters and
+/// optionally drops stopwords
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultTokenizer {
+    remove_stopwords: bool,
+}
+
+impl DefaultTokenizer {
+    /// Create a tokenizer that keeps stopwords
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable/disable stopword removal
+    pub fn with_stopwords_removed(mut self, enabled: bool) -> Self {
+        self.remove_stopwords = enabled;
+        self
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(str::to_lowercase)
+            .filter(|t| !self.remove_stopwords || !STOPWORDS.contains(&t.as_str()))
+            .collect()
+    }
+}
+
+/// Levenshtein edit distance between two strings, in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
 /// Compute cosine similarity between two vectors
 pub fn cosine_similarity(vec1: &[f32], vec2: &[f32]) -> f32 {
     if vec1.len() != vec2.len() || vec1.is_empty() {
@@ -30,6 +131,14 @@ pub fn cosine_similarity(vec1: &[f32], vec2: &[f32]) -> f32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("user_123", "user-123"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
     #[test]
     fn test_compute_hash() {
         let hash1 = compute_hash("hello");
@@ -41,6 +150,28 @@ mod tests {
         assert_eq!(hash1.len(), 64); // SHA256 hex is 64 chars
     }
 
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("Je préfère boire du café le matin avant de partir au travail").as_deref(),
+            Some("fra")
+        );
+        assert_eq!(
+            detect_language("I usually drink a cup of coffee every morning before I go to the office").as_deref(),
+            Some("eng")
+        );
+    }
+
+    #[test]
+    fn test_default_tokenizer() {
+        let tokenizer = DefaultTokenizer::new();
+        assert_eq!(tokenizer.tokenize("I love Coffee!"), vec!["i", "love", "coffee"]);
+
+        let tokenizer = tokenizer.with_stopwords_removed(true);
+        assert_eq!(tokenizer.tokenize("I love Coffee!"), vec!["love", "coffee"]);
+        assert_eq!(tokenizer.tokenize("coffee, tea"), vec!["coffee", "tea"]);
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let vec1 = vec![1.0, 0.0, 0.0];
@@ -1,5 +1,7 @@
 //! Vector store backend implementations
 
+use super::DistanceMetric;
+
 /// Backend type enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BackendType {
@@ -29,6 +31,21 @@ impl BackendType {
             Self::PostgreSQL => "PostgreSQL with pgvector extension",
         }
     }
+
+    /// Name of a distance metric in the backend's collection/index settings
+    pub fn distance_name(&self, metric: DistanceMetric) -> &'static str {
+        match (self, metric) {
+            (Self::Qdrant, DistanceMetric::Cosine) => "Cosine",
+            (Self::Qdrant, DistanceMetric::Euclidean) => "Euclid",
+            (Self::Qdrant, DistanceMetric::DotProduct) => "Dot",
+            (Self::Milvus, DistanceMetric::Cosine) => "COSINE",
+            (Self::Milvus, DistanceMetric::Euclidean) => "L2",
+            (Self::Milvus, DistanceMetric::DotProduct) => "IP",
+            (Self::PostgreSQL, DistanceMetric::Cosine) => "vector_cosine_ops",
+            (Self::PostgreSQL, DistanceMetric::Euclidean) => "vector_l2_ops",
+            (Self::PostgreSQL, DistanceMetric::DotProduct) => "vector_ip_ops",
+        }
+    }
 }
 
 /// Backend configuration
@@ -101,4 +118,14 @@ mod tests {
         assert!(!BackendType::Milvus.description().is_empty());
         assert!(!BackendType::PostgreSQL.description().is_empty());
     }
+
+    #[test]
+    fn test_distance_names() {
+        assert_eq!(BackendType::Qdrant.distance_name(DistanceMetric::DotProduct), "Dot");
+        assert_eq!(BackendType::Milvus.distance_name(DistanceMetric::Euclidean), "L2");
+        assert_eq!(
+            BackendType::PostgreSQL.distance_name(DistanceMetric::Cosine),
+            "vector_cosine_ops"
+        );
+    }
 }
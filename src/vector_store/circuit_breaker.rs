@@ -0,0 +1,538 @@
+//! Circuit breaker around a vector store
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+use super::{
+    CollectionConfig, ScoreNormalizer, SearchResult, SparseVector, UpsertMode, VectorMetadata,
+    VectorStoreBase,
+};
+
+/// Default number of consecutive failures that opens the circuit
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time the circuit stays open before a trial call
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Breaker state
+#[derive(Debug, Clone, Copy)]
+enum BreakerState {
+    /// Calls pass through; counts consecutive failures
+    Closed { failures: u32 },
+    /// Calls are rejected until the deadline
+    Open { until: Instant },
+    /// A single trial call is in flight
+    HalfOpen,
+}
+
+/// Vector store wrapper that stops calling a failing store for a while
+///
+/// After `failure_threshold` consecutive store failures the circuit opens
+/// and calls fail immediately with `Error::VectorStoreError("circuit open")`.
+/// Once the cooldown has elapsed one trial call is let through: success
+/// closes the circuit, failure opens it again, and so does a trial that is
+/// dropped before finishing. Caller errors such as `InvalidArgument` and
+/// `NotFound` do not count as failures, and outcomes of calls admitted
+/// before the last state change are ignored.
+pub struct CircuitBreaker {
+    inner: Arc<dyn VectorStoreBase>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<Circuit>,
+}
+
+/// Breaker state and a counter bumped on every state change
+#[derive(Debug)]
+struct Circuit {
+    state: BreakerState,
+    generation: u64,
+}
+
+/// An admitted call, tied to the breaker generation it started in
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    generation: u64,
+    trial: bool,
+    finished: bool,
+}
+
+impl Permit<'_> {
+    /// Record the outcome of the call
+    fn finish(mut self, failed: bool) {
+        self.finished = true;
+        self.breaker.record(self.generation, failed);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // A trial cancelled mid-call (e.g. by a timeout) must not leave the
+        // circuit half-open forever
+        if !self.finished && self.trial {
+            self.breaker.record(self.generation, true);
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Wrap a store with the default threshold and cooldown
+    pub fn new(inner: Arc<dyn VectorStoreBase>) -> Self {
+        Self {
+            inner,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+            state: Mutex::new(Circuit {
+                state: BreakerState::Closed { failures: 0 },
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Set the number of consecutive failures that opens the circuit
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Set how long the circuit stays open before a trial call
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Whether calls are currently being rejected
+    pub fn is_open(&self) -> bool {
+        match self.state.lock().unwrap().state {
+            BreakerState::Closed { .. } => false,
+            BreakerState::Open { until } => Instant::now() < until,
+            BreakerState::HalfOpen => true,
+        }
+    }
+
+    /// Admit a call or reject it while the circuit is open
+    fn acquire(&self) -> Result<Permit<'_>> {
+        let mut circuit = self.state.lock().unwrap();
+        let trial = match circuit.state {
+            BreakerState::Closed { .. } => false,
+            BreakerState::Open { until } if Instant::now() >= until => {
+                circuit.state = BreakerState::HalfOpen;
+                circuit.generation += 1;
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen => {
+                return Err(Error::vector_store("circuit open"));
+            }
+        };
+        Ok(Permit {
+            breaker: self,
+            generation: circuit.generation,
+            trial,
+            finished: false,
+        })
+    }
+
+    /// Update the state with the outcome of a call admitted in `generation`
+    fn record(&self, generation: u64, failed: bool) {
+        let mut circuit = self.state.lock().unwrap();
+        if circuit.generation != generation {
+            return;
+        }
+        let next = match (circuit.state, failed) {
+            (BreakerState::Open { .. }, _) => return,
+            (_, false) => BreakerState::Closed { failures: 0 },
+            (BreakerState::Closed { failures }, true) if failures + 1 < self.failure_threshold => {
+                BreakerState::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, true) => {
+                tracing::warn!("Vector store circuit opened for {:?}", self.cooldown);
+                BreakerState::Open {
+                    until: Instant::now() + self.cooldown,
+                }
+            }
+        };
+        if std::mem::discriminant(&next) != std::mem::discriminant(&circuit.state) {
+            circuit.generation += 1;
+        }
+        circuit.state = next;
+    }
+
+    /// Run a call on the inner store through the breaker
+    async fn guarded<T: Send>(&self, call: impl Future<Output = Result<T>> + Send) -> Result<T> {
+        let permit = self.acquire()?;
+        let result = call.await;
+        permit.finish(matches!(&result, Err(e) if is_store_failure(e)));
+        result
+    }
+}
+
+/// Whether an error indicates the store itself is failing
+fn is_store_failure(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::VectorStoreError(_)
+            | Error::QdrantError(_)
+            | Error::RequestError(_)
+            | Error::Timeout(_)
+            | Error::InternalError(_)
+    )
+}
+
+#[async_trait]
+impl VectorStoreBase for CircuitBreaker {
+    async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+        self.guarded(self.inner.create_collection(collection_name, vector_size))
+            .await
+    }
+
+    async fn create_collection_with(
+        &self,
+        collection_name: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        self.guarded(self.inner.create_collection_with(collection_name, config))
+            .await
+    }
+
+    async fn score_normalizer(&self, collection_name: &str) -> ScoreNormalizer {
+        self.inner.score_normalizer(collection_name).await
+    }
+
+    async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+        self.guarded(self.inner.collection_exists(collection_name))
+            .await
+    }
+
+    async fn upsert(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+    ) -> Result<()> {
+        self.guarded(self.inner.upsert(collection_name, vectors))
+            .await
+    }
+
+    async fn upsert_with_mode(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        mode: UpsertMode,
+    ) -> Result<()> {
+        self.guarded(self.inner.upsert_with_mode(collection_name, vectors, mode))
+            .await
+    }
+
+    async fn search(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+    ) -> Result<Vec<SearchResult>> {
+        self.guarded(
+            self.inner
+                .search(collection_name, query_vector, limit, score_threshold),
+        )
+        .await
+    }
+
+    async fn search_with_payload(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+        payload_fields: &[&str],
+    ) -> Result<Vec<SearchResult>> {
+        self.guarded(self.inner.search_with_payload(
+            collection_name,
+            query_vector,
+            limit,
+            score_threshold,
+            payload_fields,
+        ))
+        .await
+    }
+
+    async fn upsert_sparse(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, SparseVector)>,
+    ) -> Result<()> {
+        self.guarded(self.inner.upsert_sparse(collection_name, vectors))
+            .await
+    }
+
+    async fn search_sparse(
+        &self,
+        collection_name: &str,
+        query: SparseVector,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        self.guarded(self.inner.search_sparse(collection_name, query, limit))
+            .await
+    }
+
+    async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+        self.guarded(self.inner.delete(collection_name, ids)).await
+    }
+
+    async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+        self.guarded(self.inner.delete_collection(collection_name))
+            .await
+    }
+
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        self.guarded(self.inner.list_collections()).await
+    }
+
+    async fn count(&self, collection_name: &str) -> Result<usize> {
+        self.guarded(self.inner.count(collection_name)).await
+    }
+
+    async fn get_by_id(&self, collection_name: &str, id: &str) -> Result<Option<VectorMetadata>> {
+        self.guarded(self.inner.get_by_id(collection_name, id))
+            .await
+    }
+
+    async fn get_vector(&self, collection_name: &str, id: &str) -> Result<Option<Vec<f32>>> {
+        self.guarded(self.inner.get_vector(collection_name, id))
+            .await
+    }
+
+    async fn get_all(&self, collection_name: &str) -> Result<Vec<VectorMetadata>> {
+        self.guarded(self.inner.get_all(collection_name)).await
+    }
+
+    async fn scan(
+        &self,
+        collection_name: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.guarded(self.inner.scan(collection_name, after, limit))
+            .await
+    }
+
+    async fn find_by_metadata(
+        &self,
+        collection_name: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.guarded(self.inner.find_by_metadata(collection_name, key, value))
+            .await
+    }
+
+    async fn update_metadata(
+        &self,
+        collection_name: &str,
+        id: &str,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.guarded(self.inner.update_metadata(collection_name, id, metadata))
+            .await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        collection_name: &str,
+        id: &str,
+        expected_updated_at: &str,
+        vector: Vec<f32>,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.guarded(self.inner.compare_and_swap(
+            collection_name,
+            id,
+            expected_updated_at,
+            vector,
+            metadata,
+        ))
+        .await
+    }
+
+    async fn flush(&self, collection_name: &str) -> Result<()> {
+        self.guarded(self.inner.flush(collection_name)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::InMemoryStore;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Store whose `count` fails on demand and records how often it is called
+    #[derive(Default)]
+    struct FlakyStore {
+        inner: InMemoryStore,
+        failing: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl VectorStoreBase for FlakyStore {
+        async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+            self.inner.create_collection(collection_name, vector_size).await
+        }
+
+        async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+            self.inner.collection_exists(collection_name).await
+        }
+
+        async fn upsert(
+            &self,
+            collection_name: &str,
+            vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        ) -> Result<()> {
+            self.inner.upsert(collection_name, vectors).await
+        }
+
+        async fn search(
+            &self,
+            collection_name: &str,
+            query_vector: Vec<f32>,
+            limit: usize,
+            score_threshold: Option<f32>,
+        ) -> Result<Vec<SearchResult>> {
+            self.inner
+                .search(collection_name, query_vector, limit, score_threshold)
+                .await
+        }
+
+        async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+            self.inner.delete(collection_name, ids).await
+        }
+
+        async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+            self.inner.delete_collection(collection_name).await
+        }
+
+        async fn count(&self, collection_name: &str) -> Result<usize> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if collection_name == "slow" {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                return Ok(0);
+            }
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(Error::vector_store("connection refused"));
+            }
+            self.inner.count(collection_name).await
+        }
+
+        async fn get_by_id(&self, collection_name: &str, id: &str) -> Result<Option<VectorMetadata>> {
+            self.inner.get_by_id(collection_name, id).await
+        }
+
+        async fn get_all(&self, collection_name: &str) -> Result<Vec<VectorMetadata>> {
+            self.inner.get_all(collection_name).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_failures_and_recovers() {
+        let flaky = Arc::new(FlakyStore::default());
+        flaky.failing.store(true, Ordering::SeqCst);
+        let breaker = CircuitBreaker::new(flaky.clone())
+            .with_failure_threshold(3)
+            .with_cooldown(Duration::from_millis(50));
+
+        for _ in 0..3 {
+            assert!(breaker.count("test").await.is_err());
+        }
+        assert!(breaker.is_open());
+
+        // Rejected without reaching the inner store
+        let err = breaker.count("test").await.unwrap_err();
+        assert!(err.to_string().contains("circuit open"));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        flaky.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert_eq!(breaker.count("test").await.unwrap(), 0);
+        assert!(!breaker.is_open());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_trial_reopens() {
+        let flaky = Arc::new(FlakyStore::default());
+        flaky.failing.store(true, Ordering::SeqCst);
+        let breaker = CircuitBreaker::new(flaky.clone())
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::from_millis(10));
+        assert!(breaker.count("test").await.is_err());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // The trial call is abandoned by its caller
+        let trial = tokio::time::timeout(Duration::from_millis(10), breaker.count("slow")).await;
+        assert!(trial.is_err());
+        assert!(breaker.is_open());
+
+        flaky.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(breaker.count("test").await.unwrap(), 0);
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_stale_success_does_not_close() {
+        let flaky = Arc::new(FlakyStore::default());
+        flaky.failing.store(true, Ordering::SeqCst);
+        let breaker = CircuitBreaker::new(flaky.clone())
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::from_secs(60));
+
+        // A slow call admitted while closed finishes after the circuit opened
+        let (slow, failed) = tokio::join!(breaker.count("slow"), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            breaker.count("test").await
+        });
+        assert!(slow.is_ok());
+        assert!(failed.is_err());
+        assert!(breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_caller_errors_do_not_trip() {
+        let breaker = CircuitBreaker::new(Arc::new(InMemoryStore::new())).with_failure_threshold(1);
+
+        let metadata = VectorMetadata {
+            id: "1".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: "text".to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+        assert!(breaker.update_metadata("test", "1", metadata).await.is_err());
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_operations_do_not_trip() {
+        let breaker = CircuitBreaker::new(Arc::new(FlakyStore::default())).with_failure_threshold(1);
+
+        let sparse = SparseVector::from([(1, 1.0)]);
+        assert!(matches!(
+            breaker.search_sparse("test", sparse, 1).await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            breaker.get_vector("test", "1").await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(!breaker.is_open());
+    }
+}
@@ -2,13 +2,25 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use crate::Result;
+use crate::{Error, Result};
 
 pub mod qdrant;
 pub mod backends;
+pub mod circuit_breaker;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod recording;
+pub mod schema;
+pub mod timeout;
+pub mod wal;
 
 pub use qdrant::InMemoryStore;
 pub use backends::{BackendType, BackendConfig};
+pub use circuit_breaker::CircuitBreaker;
+#[cfg(any(test, feature = "test-utils"))]
+pub use recording::{RecordingStore, StoreCall};
+pub use schema::{CollectionSchema, MetadataType};
+pub use timeout::TimeoutStore;
+pub use wal::{WalOp, WriteAheadLog};
 
 /// Metadata associated with a vector
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +51,215 @@ pub struct VectorMetadata {
 
     /// Additional custom metadata
     pub custom_metadata: std::collections::HashMap<String, String>,
+
+    /// Tags (labels) attached to the memory
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Users other than the owner allowed to read the memory
+    #[serde(default)]
+    pub acl: Vec<String>,
+}
+
+/// Sparse vector as term ID -> weight (e.g. BM25 term weights)
+pub type SparseVector = std::collections::HashMap<u32, f32>;
+
+/// Dot product of two sparse vectors
+pub fn sparse_dot(a: &SparseVector, b: &SparseVector) -> f32 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(term, weight)| large.get(term).map(|w| weight * w))
+        .sum()
+}
+
+/// Distance metric used to score vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    /// Cosine similarity
+    #[default]
+    Cosine,
+    /// Euclidean distance, scored as `1 / (1 + distance)`
+    Euclidean,
+    /// Raw dot product
+    DotProduct,
+}
+
+impl DistanceMetric {
+    /// Get metric name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Euclidean => "euclidean",
+            Self::DotProduct => "dot",
+        }
+    }
+
+    /// Score two vectors, higher meaning more similar
+    pub fn score(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Self::Cosine => crate::utils::cosine_similarity(a, b),
+            Self::Euclidean => {
+                if a.len() != b.len() {
+                    return 0.0;
+                }
+                let distance: f32 = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f32>()
+                    .sqrt();
+                1.0 / (1.0 + distance)
+            }
+            Self::DotProduct => {
+                if a.len() != b.len() {
+                    return 0.0;
+                }
+                a.iter().zip(b).map(|(x, y)| x * y).sum()
+            }
+        }
+    }
+}
+
+impl DistanceMetric {
+    /// Normalizer mapping this metric's scores into `[0, 1]`
+    ///
+    /// Euclidean scores are already `1 / (1 + distance)`.
+    pub fn score_normalizer(&self) -> ScoreNormalizer {
+        match self {
+            Self::Cosine => ScoreNormalizer::Cosine,
+            Self::Euclidean => ScoreNormalizer::Certainty,
+            Self::DotProduct => ScoreNormalizer::Sigmoid,
+        }
+    }
+
+    /// Recover the raw distance behind a score of a distance-based metric
+    ///
+    /// `None` for similarity metrics (cosine, dot product).
+    pub fn distance_from_score(&self, score: f32) -> Option<f32> {
+        match self {
+            Self::Euclidean if score > 0.0 => Some(1.0 / score - 1.0),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for DistanceMetric {
+    type Err = Error;
+
+    /// Parse a metric name such as `cosine`, `euclidean` or `dot`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace(['_', '-'], "").as_str() {
+            "cosine" => Ok(Self::Cosine),
+            "euclidean" | "euclid" | "l2" => Ok(Self::Euclidean),
+            "dot" | "dotproduct" => Ok(Self::DotProduct),
+            _ => Err(Error::invalid_arg(format!("Unknown distance metric: {}", s))),
+        }
+    }
+}
+
+/// Maps a backend's raw scores into `[0, 1]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoreNormalizer {
+    /// Leave scores untouched
+    Raw,
+    /// Cosine similarity in `[-1, 1]`, mapped with `(s + 1) / 2`
+    Cosine,
+    /// Scores already in `[0, 1]` (e.g. certainty), clamped
+    Certainty,
+    /// Distances in `[0, inf)`, mapped with `1 / (1 + d)`
+    Distance,
+    /// Unbounded similarities (e.g. dot product), mapped with the logistic function
+    Sigmoid,
+}
+
+impl ScoreNormalizer {
+    /// Normalize a raw score
+    pub fn normalize(&self, score: f32) -> f32 {
+        match self {
+            Self::Raw => score,
+            Self::Cosine => ((score + 1.0) / 2.0).clamp(0.0, 1.0),
+            Self::Certainty => score.clamp(0.0, 1.0),
+            Self::Distance => 1.0 / (1.0 + score.max(0.0)),
+            Self::Sigmoid => 1.0 / (1.0 + (-score).exp()),
+        }
+    }
+}
+
+/// HNSW index parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexParams {
+    /// Number of edges per node (HNSW `m`)
+    pub m: Option<usize>,
+    /// Candidate list size during index construction (HNSW `ef_construct`)
+    pub ef_construct: Option<usize>,
+}
+
+/// Collection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionConfig {
+    /// Vector dimension
+    pub vector_size: usize,
+    /// Distance metric
+    pub distance: DistanceMetric,
+    /// Index parameters
+    pub index: IndexParams,
+    /// Replication factor
+    pub replication_factor: usize,
+    /// Custom metadata schema enforced on writes (none when absent)
+    #[serde(default)]
+    pub schema: Option<CollectionSchema>,
+}
+
+impl CollectionConfig {
+    /// Create a collection configuration with default settings
+    pub fn new(vector_size: usize) -> Self {
+        Self {
+            vector_size,
+            distance: DistanceMetric::Cosine,
+            index: IndexParams::default(),
+            replication_factor: 1,
+            schema: None,
+        }
+    }
+
+    /// Set distance metric
+    pub fn with_distance(mut self, distance: DistanceMetric) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Set index parameters
+    pub fn with_index(mut self, index: IndexParams) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Set replication factor
+    pub fn with_replication_factor(mut self, factor: usize) -> Self {
+        self.replication_factor = factor;
+        self
+    }
+
+    /// Set the custom metadata schema
+    ///
+    /// Stores that support schemas reject writes that violate it with
+    /// `Error::InvalidArgument`.
+    pub fn with_schema(mut self, schema: CollectionSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+}
+
+/// How `upsert_with_mode` treats IDs that already exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UpsertMode {
+    /// Replace the existing vector
+    #[default]
+    Overwrite,
+    /// Reject the whole batch with `Error::InvalidArgument`
+    FailIfExists,
+    /// Keep the existing vector and insert only new IDs
+    SkipIfExists,
 }
 
 /// Vector search result
@@ -52,6 +273,10 @@ pub struct SearchResult {
 
     /// Associated metadata
     pub metadata: VectorMetadata,
+
+    /// Raw distance, for backends whose native measure is a distance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f32>,
 }
 
 /// Base trait for vector store implementations
@@ -64,6 +289,24 @@ pub trait VectorStoreBase: Send + Sync {
         vector_size: usize,
     ) -> Result<()>;
 
+    /// Create or get a collection with explicit configuration
+    ///
+    /// Stores ignore the settings they do not support. The default
+    /// implementation only honors the vector size.
+    async fn create_collection_with(
+        &self,
+        collection_name: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        self.create_collection(collection_name, config.vector_size)
+            .await
+    }
+
+    /// Normalizer mapping the scores of a collection into `[0, 1]`
+    async fn score_normalizer(&self, _collection_name: &str) -> ScoreNormalizer {
+        ScoreNormalizer::Cosine
+    }
+
     /// Check if collection exists
     async fn collection_exists(&self, collection_name: &str) -> Result<bool>;
 
@@ -74,6 +317,35 @@ pub trait VectorStoreBase: Send + Sync {
         vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
     ) -> Result<()>;
 
+    /// Upsert vectors with explicit handling of existing IDs
+    ///
+    /// The default implementation checks existence with `get_by_id` before
+    /// writing and is not atomic; stores should override it.
+    async fn upsert_with_mode(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        mode: UpsertMode,
+    ) -> Result<()> {
+        if mode == UpsertMode::Overwrite {
+            return self.upsert(collection_name, vectors).await;
+        }
+
+        let mut fresh = Vec::with_capacity(vectors.len());
+        for (id, vector, metadata) in vectors {
+            if self.get_by_id(collection_name, &id).await?.is_none() {
+                fresh.push((id, vector, metadata));
+            } else if mode == UpsertMode::FailIfExists {
+                return Err(Error::invalid_arg(format!("Vector already exists: {}", id)));
+            }
+        }
+
+        if fresh.is_empty() {
+            return Ok(());
+        }
+        self.upsert(collection_name, fresh).await
+    }
+
     /// Search for similar vectors
     async fn search(
         &self,
@@ -83,6 +355,24 @@ pub trait VectorStoreBase: Send + Sync {
         score_threshold: Option<f32>,
     ) -> Result<Vec<SearchResult>>;
 
+    /// Search for similar vectors, requesting only some payload fields
+    ///
+    /// `payload_fields` are `VectorMetadata` field names. Remote stores can
+    /// leave the other fields empty to save bandwidth; the default
+    /// implementation returns the full metadata.
+    async fn search_with_payload(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+        payload_fields: &[&str],
+    ) -> Result<Vec<SearchResult>> {
+        let _ = payload_fields;
+        self.search(collection_name, query_vector, limit, score_threshold)
+            .await
+    }
+
     /// Delete vectors by IDs
     async fn delete(
         &self,
@@ -93,6 +383,36 @@ pub trait VectorStoreBase: Send + Sync {
     /// Delete collection
     async fn delete_collection(&self, collection_name: &str) -> Result<()>;
 
+    /// List the names of all collections
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        Err(Error::invalid_arg("Listing collections is not supported by this store"))
+    }
+
+    /// Attach sparse vectors to stored vectors
+    ///
+    /// Each ID must already hold a dense vector. The default implementation
+    /// reports that sparse vectors are unsupported.
+    async fn upsert_sparse(
+        &self,
+        _collection_name: &str,
+        _vectors: Vec<(String, SparseVector)>,
+    ) -> Result<()> {
+        Err(Error::invalid_arg("Sparse vectors are not supported by this store"))
+    }
+
+    /// Search by sparse dot product, highest first
+    ///
+    /// Vectors without a sparse vector or with no overlapping terms are not
+    /// returned.
+    async fn search_sparse(
+        &self,
+        _collection_name: &str,
+        _query: SparseVector,
+        _limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        Err(Error::invalid_arg("Sparse vectors are not supported by this store"))
+    }
+
     /// Get vector count in collection
     async fn count(&self, collection_name: &str) -> Result<usize>;
 
@@ -103,9 +423,124 @@ pub trait VectorStoreBase: Send + Sync {
         id: &str,
     ) -> Result<Option<VectorMetadata>>;
 
+    /// Get the stored vector for an ID
+    ///
+    /// The default implementation reports that reading vectors back is
+    /// unsupported.
+    async fn get_vector(&self, _collection_name: &str, _id: &str) -> Result<Option<Vec<f32>>> {
+        Err(Error::invalid_arg("Reading vectors is not supported by this store"))
+    }
+
     /// Get all vectors in a collection
     async fn get_all(
         &self,
         collection_name: &str,
     ) -> Result<Vec<VectorMetadata>>;
+
+    /// Get up to `limit` vectors ordered by ID, starting after the ID `after`
+    ///
+    /// Pages through a collection without loading it whole. The default
+    /// implementation sorts `get_all`; stores that can page natively should
+    /// override it.
+    async fn scan(
+        &self,
+        collection_name: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<VectorMetadata>> {
+        let mut all = self.get_all(collection_name).await?;
+        all.retain(|m| after.is_none_or(|after| m.id.as_str() > after));
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        all.truncate(limit);
+        Ok(all)
+    }
+
+    /// Get vectors whose custom metadata `key` equals `value`
+    ///
+    /// The default implementation filters `get_all`; stores with native
+    /// payload filtering should override it.
+    async fn find_by_metadata(
+        &self,
+        collection_name: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<VectorMetadata>> {
+        Ok(self
+            .get_all(collection_name)
+            .await?
+            .into_iter()
+            .filter(|m| m.custom_metadata.get(key).is_some_and(|v| v == value))
+            .collect())
+    }
+
+    /// Replace the metadata of a stored vector, keeping the vector itself
+    ///
+    /// The default implementation reports that metadata updates are
+    /// unsupported.
+    async fn update_metadata(
+        &self,
+        _collection_name: &str,
+        _id: &str,
+        _metadata: VectorMetadata,
+    ) -> Result<()> {
+        Err(Error::invalid_arg("Metadata updates are not supported by this store"))
+    }
+
+    /// Replace a vector only if its stored `updated_at` equals `expected_updated_at`
+    ///
+    /// Returns `Error::InvalidArgument("conflict")` on mismatch. The default
+    /// implementation is not atomic; stores should override it.
+    async fn compare_and_swap(
+        &self,
+        collection_name: &str,
+        id: &str,
+        expected_updated_at: &str,
+        vector: Vec<f32>,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        let current = self
+            .get_by_id(collection_name, id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Vector not found: {}", id)))?;
+
+        if current.updated_at != expected_updated_at {
+            return Err(Error::invalid_arg("conflict"));
+        }
+
+        self.upsert(collection_name, vec![(id.to_string(), vector, metadata)])
+            .await
+    }
+
+    /// Make pending writes to a collection visible to searches
+    ///
+    /// Stores that buffer writes or are eventually consistent should
+    /// override it to wait until earlier writes are searchable. The default
+    /// is a no-op for stores whose writes are visible immediately.
+    async fn flush(&self, collection_name: &str) -> Result<()> {
+        let _ = collection_name;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_normalization() {
+        let normalizer = ScoreNormalizer::Cosine;
+        assert_eq!(normalizer.normalize(-1.0), 0.0);
+        assert_eq!(normalizer.normalize(0.0), 0.5);
+        assert_eq!(normalizer.normalize(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_other_normalizations() {
+        assert_eq!(ScoreNormalizer::Raw.normalize(-0.3), -0.3);
+        assert_eq!(ScoreNormalizer::Certainty.normalize(1.2), 1.0);
+        assert_eq!(ScoreNormalizer::Distance.normalize(0.0), 1.0);
+        assert_eq!(ScoreNormalizer::Distance.normalize(1.0), 0.5);
+        assert_eq!(ScoreNormalizer::Sigmoid.normalize(0.0), 0.5);
+        assert!(ScoreNormalizer::Sigmoid.normalize(3.0) < ScoreNormalizer::Sigmoid.normalize(4.0));
+    }
 }
@@ -1,22 +1,93 @@
 //! In-memory vector store implementation
 
 use async_trait::async_trait;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use crate::{Result, Error};
-use super::{VectorStoreBase, VectorMetadata, SearchResult};
+use super::{CollectionConfig, CollectionSchema, DistanceMetric, ScoreNormalizer, SparseVector, UpsertMode, VectorStoreBase, VectorMetadata, SearchResult};
+use super::wal::{StoreSnapshot, WalOp, WriteAheadLog};
+
+/// Stored vector representation
+#[derive(Clone)]
+enum StoredVector {
+    /// Full-precision vector
+    Full(Vec<f32>),
+    /// Int8 scalar-quantized vector with its per-vector scale
+    Quantized { values: Vec<i8>, scale: f32 },
+    /// Half-precision vector
+    #[cfg(feature = "f16")]
+    Half(Vec<half::f16>),
+}
+
+impl StoredVector {
+    /// Quantize a vector to int8 using its maximum absolute component
+    fn quantize(vector: &[f32]) -> Self {
+        let max_abs = vector.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        let scale = if max_abs > 0.0 { max_abs / 127.0 } else { 1.0 };
+        let values = vector
+            .iter()
+            .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8)
+            .collect();
+        Self::Quantized { values, scale }
+    }
+
+    /// Get the vector in full precision, dequantizing if needed
+    fn to_f32(&self) -> Cow<'_, [f32]> {
+        match self {
+            Self::Full(vector) => Cow::Borrowed(vector),
+            Self::Quantized { values, scale } => {
+                Cow::Owned(values.iter().map(|&v| v as f32 * scale).collect())
+            }
+            #[cfg(feature = "f16")]
+            Self::Half(values) => Cow::Owned(values.iter().map(|v| v.to_f32()).collect()),
+        }
+    }
+
+    /// Approximate heap bytes used by the vector data
+    fn size_bytes(&self) -> usize {
+        match self {
+            Self::Full(vector) => vector.len() * std::mem::size_of::<f32>(),
+            Self::Quantized { values, .. } => values.len() + std::mem::size_of::<f32>(),
+            #[cfg(feature = "f16")]
+            Self::Half(values) => values.len() * std::mem::size_of::<half::f16>(),
+        }
+    }
+}
+
+/// Representation used for stored vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VectorPrecision {
+    /// Full-precision f32
+    Full,
+    /// Int8 scalar quantization
+    Int8,
+    /// Half-precision f16
+    #[cfg(feature = "f16")]
+    F16,
+}
 
 /// Vector entry stored in memory
 #[derive(Clone)]
 struct VectorEntry {
-    vector: Vec<f32>,
+    vector: StoredVector,
     metadata: VectorMetadata,
+    /// Sparse vector, cleared when the dense vector is replaced
+    sparse: Option<SparseVector>,
 }
 
 /// In-memory vector store implementation
 pub struct InMemoryStore {
     collections: Arc<RwLock<HashMap<String, HashMap<String, VectorEntry>>>>,
+    /// Distance metric per collection (cosine when absent)
+    metrics: Arc<RwLock<HashMap<String, DistanceMetric>>>,
+    /// Custom metadata schema per collection (unchecked when absent)
+    schemas: Arc<RwLock<HashMap<String, CollectionSchema>>>,
+    /// Representation of stored vectors
+    precision: VectorPrecision,
+    /// Write-ahead log of mutations (disabled when absent)
+    wal: Option<Arc<WriteAheadLog>>,
 }
 
 impl InMemoryStore {
@@ -24,6 +95,192 @@ impl InMemoryStore {
     pub fn new() -> Self {
         Self {
             collections: Arc::new(RwLock::new(HashMap::new())),
+            metrics: Arc::new(RwLock::new(HashMap::new())),
+            schemas: Arc::new(RwLock::new(HashMap::new())),
+            precision: VectorPrecision::Full,
+            wal: None,
+        }
+    }
+
+    /// Create an in-memory store that keeps int8 scalar-quantized vectors
+    ///
+    /// Uses roughly a quarter of the memory for vectors at a small accuracy
+    /// cost; vectors are dequantized on the fly during search.
+    pub fn with_quantization() -> Self {
+        Self {
+            precision: VectorPrecision::Int8,
+            ..Self::new()
+        }
+    }
+
+    /// Create an in-memory store that keeps half-precision vectors
+    ///
+    /// Halves vector memory with little recall loss for normalized
+    /// embeddings; vectors are widened to f32 during search.
+    #[cfg(feature = "f16")]
+    pub fn with_f16() -> Self {
+        Self {
+            precision: VectorPrecision::F16,
+            ..Self::new()
+        }
+    }
+
+    /// Log every mutation to a write-ahead log before applying it
+    pub fn with_wal(mut self, wal_path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        self.wal = Some(Arc::new(WriteAheadLog::open(wal_path)?));
+        Ok(self)
+    }
+
+    /// Rebuild this store from an optional snapshot and the operations logged since
+    ///
+    /// Operations are replayed into `self`, so a store built with
+    /// `with_quantization` or `with_f16` keeps its precision. Metadata
+    /// updates of vectors deleted later in the log are skipped. The returned
+    /// store keeps appending to `wal_path`.
+    pub async fn recover(
+        mut self,
+        wal_path: impl AsRef<std::path::Path>,
+        snapshot_path: Option<&std::path::Path>,
+    ) -> Result<Self> {
+        // Replayed operations must not be logged again
+        self.wal = None;
+        let store = self;
+
+        if let Some(path) = snapshot_path.filter(|p| p.exists()) {
+            let snapshot = StoreSnapshot::load(path)?;
+            for (collection, distance) in snapshot.metrics {
+                store.metrics.write().await.insert(collection, distance);
+            }
+            *store.schemas.write().await = snapshot.schemas;
+            for (collection, vectors) in snapshot.collections {
+                store.create_collection(&collection, 0).await?;
+                store.upsert(&collection, vectors).await?;
+            }
+        }
+
+        for op in WriteAheadLog::read_ops(wal_path.as_ref())? {
+            match op {
+                WalOp::CreateCollection {
+                    collection,
+                    distance,
+                    schema,
+                } => {
+                    let config = CollectionConfig {
+                        schema,
+                        ..CollectionConfig::new(0).with_distance(distance)
+                    };
+                    store.create_collection_with(&collection, config).await?
+                }
+                WalOp::Upsert { collection, vectors } => store.upsert(&collection, vectors).await?,
+                WalOp::UpdateMetadata {
+                    collection,
+                    id,
+                    metadata,
+                } => match store.update_metadata(&collection, &id, *metadata).await {
+                    Err(Error::NotFound(_)) => {
+                        tracing::debug!("Skipping stale metadata update of vector {}", id)
+                    }
+                    result => result?,
+                },
+                WalOp::Delete { collection, ids } => store.delete(&collection, ids).await?,
+                WalOp::DeleteCollection { collection } => store.delete_collection(&collection).await?,
+            }
+        }
+
+        store.with_wal(wal_path.as_ref())
+    }
+
+    /// Save a full snapshot and truncate the write-ahead log
+    ///
+    /// The snapshot is taken under the store locks but written outside
+    /// them. Operations logged meanwhile stay in the log.
+    pub async fn checkpoint(&self, snapshot_path: impl AsRef<std::path::Path>) -> Result<()> {
+        let (snapshot, position) = self.snapshot().await?;
+        snapshot.save(snapshot_path.as_ref())?;
+
+        if let (Some(wal), Some(position)) = (&self.wal, position) {
+            wal.discard_prefix(position)?;
+        }
+        Ok(())
+    }
+
+    /// Copy the store contents with the log position they correspond to
+    ///
+    /// Mutations log while holding the collections lock, so no operation
+    /// can land between the copy and the position.
+    async fn snapshot(&self) -> Result<(StoreSnapshot, Option<u64>)> {
+        let collections = self.collections.write().await;
+        let metrics = self.metrics.write().await;
+        let schemas = self.schemas.write().await;
+
+        let position = self.wal.as_ref().map(|wal| wal.position()).transpose()?;
+        let snapshot = StoreSnapshot {
+            collections: collections
+                .iter()
+                .map(|(name, entries)| {
+                    let vectors = entries
+                        .iter()
+                        .map(|(id, e)| (id.clone(), e.vector.to_f32().into_owned(), e.metadata.clone()))
+                        .collect();
+                    (name.clone(), vectors)
+                })
+                .collect(),
+            metrics: metrics.clone(),
+            schemas: schemas.clone(),
+        };
+        Ok((snapshot, position))
+    }
+
+    /// Append an operation to the write-ahead log, if enabled
+    fn log(&self, op: impl FnOnce() -> WalOp) -> Result<()> {
+        match &self.wal {
+            Some(wal) => wal.append(&op()),
+            None => Ok(()),
+        }
+    }
+
+    /// Approximate bytes used by stored vector data across all collections
+    pub async fn vector_memory_bytes(&self) -> usize {
+        let collections = self.collections.read().await;
+        collections
+            .values()
+            .flat_map(|c| c.values())
+            .map(|e| e.vector.size_bytes())
+            .sum()
+    }
+
+    /// Check metadata of vectors against the collection's schema, if any
+    async fn validate_schema<'a>(
+        &self,
+        collection_name: &str,
+        metadata: impl IntoIterator<Item = &'a VectorMetadata>,
+    ) -> Result<()> {
+        let schemas = self.schemas.read().await;
+        let Some(schema) = schemas.get(collection_name) else {
+            return Ok(());
+        };
+        for metadata in metadata {
+            schema.validate(&metadata.custom_metadata).map_err(|e| {
+                Error::invalid_arg(format!("Vector {} rejected: {}", metadata.id, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Build an entry in the store's vector representation
+    fn entry(&self, vector: Vec<f32>, metadata: VectorMetadata) -> VectorEntry {
+        let vector = match self.precision {
+            VectorPrecision::Full => StoredVector::Full(vector),
+            VectorPrecision::Int8 => StoredVector::quantize(&vector),
+            #[cfg(feature = "f16")]
+            VectorPrecision::F16 => {
+                StoredVector::Half(vector.iter().map(|&v| half::f16::from_f32(v)).collect())
+            }
+        };
+        VectorEntry {
+            vector,
+            metadata,
+            sparse: None,
         }
     }
 }
@@ -42,11 +299,60 @@ impl VectorStoreBase for InMemoryStore {
         _vector_size: usize,
     ) -> Result<()> {
         let mut collections = self.collections.write().await;
-        collections.entry(collection_name.to_string())
+        if !collections.contains_key(collection_name) {
+            self.log(|| WalOp::CreateCollection {
+                collection: collection_name.to_string(),
+                distance: DistanceMetric::default(),
+                schema: None,
+            })?;
+            collections.insert(collection_name.to_string(), HashMap::new());
+        }
+        Ok(())
+    }
+
+    async fn create_collection_with(
+        &self,
+        collection_name: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        let mut collections = self.collections.write().await;
+        let mut metrics = self.metrics.write().await;
+        let mut schemas = self.schemas.write().await;
+
+        // Only log creations and changed settings
+        let unchanged = collections.contains_key(collection_name)
+            && metrics.get(collection_name).copied().unwrap_or_default() == config.distance
+            && schemas.get(collection_name) == config.schema.as_ref();
+        if unchanged {
+            return Ok(());
+        }
+
+        self.log(|| WalOp::CreateCollection {
+            collection: collection_name.to_string(),
+            distance: config.distance,
+            schema: config.schema.clone(),
+        })?;
+        collections
+            .entry(collection_name.to_string())
             .or_insert_with(HashMap::new);
+        metrics.insert(collection_name.to_string(), config.distance);
+        match config.schema {
+            Some(schema) => schemas.insert(collection_name.to_string(), schema),
+            None => schemas.remove(collection_name),
+        };
         Ok(())
     }
 
+    async fn score_normalizer(&self, collection_name: &str) -> ScoreNormalizer {
+        self.metrics
+            .read()
+            .await
+            .get(collection_name)
+            .copied()
+            .unwrap_or_default()
+            .score_normalizer()
+    }
+
     async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
         let collections = self.collections.read().await;
         Ok(collections.contains_key(collection_name))
@@ -57,13 +363,61 @@ impl VectorStoreBase for InMemoryStore {
         collection_name: &str,
         vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
     ) -> Result<()> {
+        self.validate_schema(collection_name, vectors.iter().map(|(_, _, m)| m))
+            .await?;
         let mut collections = self.collections.write().await;
+        self.log(|| WalOp::Upsert {
+            collection: collection_name.to_string(),
+            vectors: vectors.clone(),
+        })?;
         let collection = collections
             .entry(collection_name.to_string())
             .or_insert_with(HashMap::new);
 
         for (id, vector, metadata) in vectors {
-            collection.insert(id, VectorEntry { vector, metadata });
+            collection.insert(id, self.entry(vector, metadata));
+        }
+        Ok(())
+    }
+
+    async fn upsert_with_mode(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        mode: UpsertMode,
+    ) -> Result<()> {
+        self.validate_schema(collection_name, vectors.iter().map(|(_, _, m)| m))
+            .await?;
+
+        // Check and write under a single write lock
+        let mut collections = self.collections.write().await;
+        let existing = collections.get(collection_name);
+        let exists = |id: &str| existing.is_some_and(|c| c.contains_key(id));
+
+        let vectors: Vec<_> = match mode {
+            UpsertMode::Overwrite => vectors,
+            UpsertMode::FailIfExists => {
+                if let Some((id, _, _)) = vectors.iter().find(|(id, _, _)| exists(id)) {
+                    return Err(Error::invalid_arg(format!("Vector already exists: {}", id)));
+                }
+                vectors
+            }
+            UpsertMode::SkipIfExists => vectors.into_iter().filter(|(id, _, _)| !exists(id)).collect(),
+        };
+        if vectors.is_empty() {
+            return Ok(());
+        }
+
+        self.log(|| WalOp::Upsert {
+            collection: collection_name.to_string(),
+            vectors: vectors.clone(),
+        })?;
+        let collection = collections
+            .entry(collection_name.to_string())
+            .or_insert_with(HashMap::new);
+
+        for (id, vector, metadata) in vectors {
+            collection.insert(id, self.entry(vector, metadata));
         }
         Ok(())
     }
@@ -75,16 +429,24 @@ impl VectorStoreBase for InMemoryStore {
         limit: usize,
         score_threshold: Option<f32>,
     ) -> Result<Vec<SearchResult>> {
+        let metric = self
+            .metrics
+            .read()
+            .await
+            .get(collection_name)
+            .copied()
+            .unwrap_or_default();
+
         let collections = self.collections.read().await;
         let collection = collections
             .get(collection_name)
             .ok_or_else(|| Error::vector_store(format!("Collection not found: {}", collection_name)))?;
 
-        // Compute cosine similarity for all vectors
+        // Score all vectors with the collection's metric
         let mut results: Vec<_> = collection
             .iter()
             .filter_map(|(id, entry)| {
-                let score = cosine_similarity(&query_vector, &entry.vector);
+                let score = metric.score(&query_vector, &entry.vector.to_f32());
                 if let Some(threshold) = score_threshold {
                     if score < threshold {
                         return None;
@@ -105,18 +467,80 @@ impl VectorStoreBase for InMemoryStore {
                 id,
                 score,
                 metadata,
+                distance: metric.distance_from_score(score),
             })
             .collect();
 
         Ok(search_results)
     }
 
+    /// Sparse vectors are kept in memory only; they are not written to the
+    /// write-ahead log or checkpoints.
+    async fn upsert_sparse(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, SparseVector)>,
+    ) -> Result<()> {
+        let mut collections = self.collections.write().await;
+        let collection = collections
+            .get_mut(collection_name)
+            .ok_or_else(|| Error::vector_store(format!("Collection not found: {}", collection_name)))?;
+
+        if let Some((id, _)) = vectors.iter().find(|(id, _)| !collection.contains_key(id)) {
+            return Err(Error::not_found(format!("Vector not found: {}", id)));
+        }
+        for (id, sparse) in vectors {
+            if let Some(entry) = collection.get_mut(&id) {
+                entry.sparse = Some(sparse);
+            }
+        }
+        Ok(())
+    }
+
+    async fn search_sparse(
+        &self,
+        collection_name: &str,
+        query: SparseVector,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let collections = self.collections.read().await;
+        let collection = collections
+            .get(collection_name)
+            .ok_or_else(|| Error::vector_store(format!("Collection not found: {}", collection_name)))?;
+
+        let mut results: Vec<SearchResult> = collection
+            .iter()
+            .filter_map(|(id, entry)| {
+                let score = super::sparse_dot(&query, entry.sparse.as_ref()?);
+                (score > 0.0).then(|| SearchResult {
+                    id: id.clone(),
+                    score,
+                    metadata: entry.metadata.clone(),
+                    distance: None,
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        results.truncate(limit);
+        Ok(results)
+    }
+
     async fn delete(
         &self,
         collection_name: &str,
         ids: Vec<String>,
     ) -> Result<()> {
         let mut collections = self.collections.write().await;
+        self.log(|| WalOp::Delete {
+            collection: collection_name.to_string(),
+            ids: ids.clone(),
+        })?;
         if let Some(collection) = collections.get_mut(collection_name) {
             for id in ids {
                 collection.remove(&id);
@@ -127,10 +551,21 @@ impl VectorStoreBase for InMemoryStore {
 
     async fn delete_collection(&self, collection_name: &str) -> Result<()> {
         let mut collections = self.collections.write().await;
+        self.log(|| WalOp::DeleteCollection {
+            collection: collection_name.to_string(),
+        })?;
         collections.remove(collection_name);
+        self.metrics.write().await.remove(collection_name);
+        self.schemas.write().await.remove(collection_name);
         Ok(())
     }
 
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self.collections.read().await.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
     async fn count(&self, collection_name: &str) -> Result<usize> {
         let collections = self.collections.read().await;
         Ok(collections
@@ -151,6 +586,14 @@ impl VectorStoreBase for InMemoryStore {
             .map(|entry| entry.metadata.clone()))
     }
 
+    async fn get_vector(&self, collection_name: &str, id: &str) -> Result<Option<Vec<f32>>> {
+        let collections = self.collections.read().await;
+        Ok(collections
+            .get(collection_name)
+            .and_then(|c| c.get(id))
+            .map(|entry| entry.vector.to_f32().into_owned()))
+    }
+
     async fn get_all(
         &self,
         collection_name: &str,
@@ -161,9 +604,85 @@ impl VectorStoreBase for InMemoryStore {
             .map(|c| c.values().map(|e| e.metadata.clone()).collect())
             .unwrap_or_default())
     }
+
+    async fn scan(
+        &self,
+        collection_name: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<VectorMetadata>> {
+        let collections = self.collections.read().await;
+        let Some(collection) = collections.get(collection_name) else {
+            return Ok(Vec::new());
+        };
+
+        // Clone only the page, not the whole collection
+        let mut ids: Vec<&String> = collection
+            .keys()
+            .filter(|id| after.is_none_or(|after| id.as_str() > after))
+            .collect();
+        ids.sort_unstable();
+        Ok(ids
+            .into_iter()
+            .take(limit)
+            .map(|id| collection[id].metadata.clone())
+            .collect())
+    }
+
+    async fn update_metadata(
+        &self,
+        collection_name: &str,
+        id: &str,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.validate_schema(collection_name, [&metadata]).await?;
+        let mut collections = self.collections.write().await;
+        let entry = collections
+            .get_mut(collection_name)
+            .and_then(|c| c.get_mut(id))
+            .ok_or_else(|| Error::not_found(format!("Vector not found: {}", id)))?;
+
+        self.log(|| WalOp::UpdateMetadata {
+            collection: collection_name.to_string(),
+            id: id.to_string(),
+            metadata: Box::new(metadata.clone()),
+        })?;
+        entry.metadata = metadata;
+        Ok(())
+    }
+
+    async fn compare_and_swap(
+        &self,
+        collection_name: &str,
+        id: &str,
+        expected_updated_at: &str,
+        vector: Vec<f32>,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.validate_schema(collection_name, [&metadata]).await?;
+
+        // Check and write under a single write lock
+        let mut collections = self.collections.write().await;
+        let entry = collections
+            .get_mut(collection_name)
+            .and_then(|c| c.get_mut(id))
+            .ok_or_else(|| Error::not_found(format!("Vector not found: {}", id)))?;
+
+        if entry.metadata.updated_at != expected_updated_at {
+            return Err(Error::invalid_arg("conflict"));
+        }
+
+        self.log(|| WalOp::Upsert {
+            collection: collection_name.to_string(),
+            vectors: vec![(id.to_string(), vector.clone(), metadata.clone())],
+        })?;
+        *entry = self.entry(vector, metadata);
+        Ok(())
+    }
 }
 
 /// Compute cosine similarity between two vectors
+#[cfg(test)]
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     crate::utils::cosine_similarity(a, b)
 }
@@ -180,6 +699,15 @@ mod tests {
         assert!(store.collection_exists("test_collection").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_flush_is_noop() {
+        let store = InMemoryStore::new();
+        store.create_collection("test", 3).await.unwrap();
+        assert!(store.flush("test").await.is_ok());
+        assert!(store.flush("missing").await.is_ok());
+        assert_eq!(store.count("test").await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_upsert_and_search() {
         let store = InMemoryStore::new();
@@ -195,6 +723,8 @@ mod tests {
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-01".to_string(),
             custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
         };
 
         store
@@ -238,6 +768,8 @@ mod tests {
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-01".to_string(),
             custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
         };
 
         store
@@ -249,4 +781,412 @@ mod tests {
         store.delete("test", vec!["1".to_string()]).await.unwrap();
         assert_eq!(store.count("test").await.unwrap(), 0);
     }
+
+    #[tokio::test]
+    async fn test_create_collection_with_metric() {
+        let store = InMemoryStore::new();
+        store
+            .create_collection_with(
+                "dot",
+                CollectionConfig::new(2).with_distance(DistanceMetric::DotProduct),
+            )
+            .await
+            .unwrap();
+        store
+            .create_collection_with(
+                "euclidean",
+                CollectionConfig::new(2).with_distance(DistanceMetric::Euclidean),
+            )
+            .await
+            .unwrap();
+        store.create_collection("cosine", 2).await.unwrap();
+
+        for collection in ["dot", "euclidean", "cosine"] {
+            let metadata = VectorMetadata {
+                id: "1".to_string(),
+                user_id: "user1".to_string(),
+                agent_id: None,
+                run_id: None,
+                text: "test".to_string(),
+                memory_type: "fact".to_string(),
+                created_at: "2024-01-01".to_string(),
+                updated_at: "2024-01-01".to_string(),
+                custom_metadata: Default::default(),
+                tags: Vec::new(),
+                acl: Vec::new(),
+            };
+            store
+                .upsert(collection, vec![("1".to_string(), vec![2.0, 0.0], metadata)])
+                .await
+                .unwrap();
+        }
+
+        let dot = store.search("dot", vec![1.0, 0.0], 1, None).await.unwrap();
+        assert!((dot[0].score - 2.0).abs() < 0.001);
+        assert_eq!(dot[0].distance, None);
+
+        // Euclidean results carry the raw distance alongside the score
+        let euclidean = store.search("euclidean", vec![1.0, 0.0], 1, None).await.unwrap();
+        assert!((euclidean[0].score - 0.5).abs() < 0.001);
+        assert!((euclidean[0].distance.unwrap() - 1.0).abs() < 0.001);
+
+        let cosine = store.search("cosine", vec![1.0, 0.0], 1, None).await.unwrap();
+        assert!((cosine[0].score - 1.0).abs() < 0.001);
+        assert_eq!(cosine[0].distance, None);
+
+        assert_eq!(store.score_normalizer("dot").await, ScoreNormalizer::Sigmoid);
+        assert_eq!(store.score_normalizer("euclidean").await, ScoreNormalizer::Certainty);
+        assert_eq!(store.score_normalizer("cosine").await, ScoreNormalizer::Cosine);
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_swap() {
+        let store = InMemoryStore::new();
+        let metadata = VectorMetadata {
+            id: "1".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: "old".to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+        store
+            .upsert("test", vec![("1".to_string(), vec![1.0, 0.0], metadata.clone())])
+            .await
+            .unwrap();
+
+        let updated = VectorMetadata {
+            text: "new".to_string(),
+            updated_at: "2024-01-02".to_string(),
+            ..metadata
+        };
+
+        let stale = store
+            .compare_and_swap("test", "1", "2023-12-31", vec![0.0, 1.0], updated.clone())
+            .await;
+        assert!(matches!(stale, Err(Error::InvalidArgument(_))));
+
+        store
+            .compare_and_swap("test", "1", "2024-01-01", vec![0.0, 1.0], updated)
+            .await
+            .unwrap();
+        let stored = store.get_by_id("test", "1").await.unwrap().unwrap();
+        assert_eq!(stored.text, "new");
+    }
+
+    #[tokio::test]
+    async fn test_upsert_modes() {
+        let store = InMemoryStore::new();
+        let metadata = |id: &str, text: &str| VectorMetadata {
+            id: id.to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: text.to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+        store
+            .upsert("test", vec![("1".to_string(), vec![1.0, 0.0], metadata("1", "original"))])
+            .await
+            .unwrap();
+
+        let batch = || {
+            vec![
+                ("1".to_string(), vec![0.0, 1.0], metadata("1", "replacement")),
+                ("2".to_string(), vec![0.0, 1.0], metadata("2", "new")),
+            ]
+        };
+        let text = |id: &'static str| {
+            let store = &store;
+            async move { store.get_by_id("test", id).await.unwrap().map(|m| m.text) }
+        };
+
+        let failed = store.upsert_with_mode("test", batch(), UpsertMode::FailIfExists).await;
+        assert!(matches!(failed, Err(Error::InvalidArgument(_))));
+        assert_eq!(text("1").await.as_deref(), Some("original"));
+        assert_eq!(text("2").await, None);
+
+        store
+            .upsert_with_mode("test", batch(), UpsertMode::SkipIfExists)
+            .await
+            .unwrap();
+        assert_eq!(text("1").await.as_deref(), Some("original"));
+        assert_eq!(text("2").await.as_deref(), Some("new"));
+
+        store
+            .upsert_with_mode("test", batch(), UpsertMode::Overwrite)
+            .await
+            .unwrap();
+        assert_eq!(text("1").await.as_deref(), Some("replacement"));
+    }
+
+    /// Deterministic pseudo-random vectors in `[-1, 1]`
+    fn random_vectors(count: usize, dimension: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 2001) as f32 / 1000.0 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sparse_search_finds_rare_term() {
+        let store = InMemoryStore::new();
+        store.create_collection("test", 2).await.unwrap();
+
+        let metadata = |id: &str| VectorMetadata {
+            id: id.to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: format!("text {}", id),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+        store
+            .upsert(
+                "test",
+                vec![
+                    ("common".to_string(), vec![1.0, 0.0], metadata("common")),
+                    ("rare".to_string(), vec![0.0, 1.0], metadata("rare")),
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Term 1 is common, term 42 is rare and heavily weighted
+        store
+            .upsert_sparse(
+                "test",
+                vec![
+                    ("common".to_string(), SparseVector::from([(1, 0.5)])),
+                    ("rare".to_string(), SparseVector::from([(1, 0.1), (42, 3.0)])),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let dense = store.search("test", vec![1.0, 0.0], 2, None).await.unwrap();
+        assert_eq!(dense[0].id, "common");
+
+        let sparse = store
+            .search_sparse("test", SparseVector::from([(1, 1.0), (42, 1.0)]), 2)
+            .await
+            .unwrap();
+        assert_eq!(sparse[0].id, "rare");
+        assert_eq!(sparse.len(), 2);
+
+        assert!(store
+            .upsert_sparse("test", vec![("missing".to_string(), SparseVector::new())])
+            .await
+            .is_err());
+    }
+
+    /// Fill a store with deterministic random vectors
+    async fn populate_random(store: &InMemoryStore) {
+        let entries = random_vectors(500, 64, 42)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let metadata = VectorMetadata {
+                    id: i.to_string(),
+                    user_id: "user1".to_string(),
+                    agent_id: None,
+                    run_id: None,
+                    text: format!("text {}", i),
+                    memory_type: "fact".to_string(),
+                    created_at: "2024-01-01".to_string(),
+                    updated_at: "2024-01-01".to_string(),
+                    custom_metadata: Default::default(),
+                    tags: Vec::new(),
+                    acl: Vec::new(),
+                };
+                (i.to_string(), v, metadata)
+            })
+            .collect();
+        store.upsert("test", entries).await.unwrap();
+    }
+
+    /// Fraction of the full-precision top 10 that `candidate` also returns
+    async fn recall_at_10(full: &InMemoryStore, candidate: &InMemoryStore) -> f32 {
+        let mut hits = 0;
+        let queries = random_vectors(20, 64, 7);
+        for query in &queries {
+            let expected: std::collections::HashSet<String> = full
+                .search("test", query.clone(), 10, None)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            hits += candidate
+                .search("test", query.clone(), 10, None)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|r| expected.contains(&r.id))
+                .count();
+        }
+
+        hits as f32 / (queries.len() * 10) as f32
+    }
+
+    #[tokio::test]
+    async fn test_quantization_recall_and_memory() {
+        let full = InMemoryStore::new();
+        let quantized = InMemoryStore::with_quantization();
+        populate_random(&full).await;
+        populate_random(&quantized).await;
+
+        let recall = recall_at_10(&full, &quantized).await;
+        assert!(recall >= 0.9, "recall@10 was {}", recall);
+        assert!(quantized.vector_memory_bytes().await * 3 < full.vector_memory_bytes().await);
+    }
+
+    #[cfg(feature = "f16")]
+    #[tokio::test]
+    async fn test_f16_recall_and_memory() {
+        let full = InMemoryStore::new();
+        let half = InMemoryStore::with_f16();
+        populate_random(&full).await;
+        populate_random(&half).await;
+
+        let recall = recall_at_10(&full, &half).await;
+        assert!(recall >= 0.95, "recall@10 was {}", recall);
+        assert_eq!(half.vector_memory_bytes().await * 2, full.vector_memory_bytes().await);
+    }
+
+    #[tokio::test]
+    async fn test_wal_recovery() {
+        let dir = std::env::temp_dir();
+        let wal_path = dir.join(format!("store_{}.wal", uuid::Uuid::new_v4()));
+        let snapshot_path = dir.join(format!("store_{}.snapshot", uuid::Uuid::new_v4()));
+        let metadata = |id: &str, text: &str| VectorMetadata {
+            id: id.to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: text.to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+
+        {
+            let store = InMemoryStore::new().with_wal(&wal_path).unwrap();
+            store
+                .upsert("test", vec![("1".to_string(), vec![1.0, 0.0], metadata("1", "one"))])
+                .await
+                .unwrap();
+            store.checkpoint(&snapshot_path).await.unwrap();
+
+            store
+                .upsert(
+                    "test",
+                    vec![
+                        ("2".to_string(), vec![0.0, 1.0], metadata("2", "two")),
+                        ("3".to_string(), vec![1.0, 1.0], metadata("3", "three")),
+                    ],
+                )
+                .await
+                .unwrap();
+            store.delete("test", vec!["1".to_string()]).await.unwrap();
+            // Dropped without a checkpoint, simulating a crash
+        }
+
+        let recovered = InMemoryStore::new()
+            .recover(&wal_path, Some(snapshot_path.as_path()))
+            .await
+            .unwrap();
+        let mut ids: Vec<String> = recovered
+            .get_all("test")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["2", "3"]);
+
+        std::fs::remove_file(&wal_path).unwrap();
+        std::fs::remove_file(&snapshot_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wal_recovery_into_configured_store() {
+        let wal_path = std::env::temp_dir().join(format!("store_{}.wal", uuid::Uuid::new_v4()));
+        let metadata = VectorMetadata {
+            id: "1".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: None,
+            run_id: None,
+            text: "one".to_string(),
+            memory_type: "fact".to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+            custom_metadata: Default::default(),
+            tags: Vec::new(),
+            acl: Vec::new(),
+        };
+
+        {
+            let store = InMemoryStore::new().with_wal(&wal_path).unwrap();
+            let config = CollectionConfig::new(2).with_distance(DistanceMetric::DotProduct);
+            store.create_collection_with("test", config.clone()).await.unwrap();
+            store.create_collection_with("test", config).await.unwrap();
+            store
+                .upsert("test", vec![("1".to_string(), vec![1.0, 0.0], metadata.clone())])
+                .await
+                .unwrap();
+            store.update_metadata("test", "1", metadata).await.unwrap();
+            store.delete("test", vec!["1".to_string()]).await.unwrap();
+        }
+
+        // The repeated creation is not logged
+        let ops = WriteAheadLog::read_ops(&wal_path).unwrap();
+        assert_eq!(ops.len(), 4);
+
+        // Delete the vector before its metadata update, leaving the update stale
+        let reordered: Vec<WalOp> = vec![ops[0].clone(), ops[1].clone(), ops[3].clone(), ops[2].clone()];
+        let log = WriteAheadLog::open(&wal_path).unwrap();
+        log.truncate().unwrap();
+        for op in &reordered {
+            log.append(op).unwrap();
+        }
+
+        let recovered = InMemoryStore::with_quantization()
+            .recover(&wal_path, None)
+            .await
+            .unwrap();
+        assert_eq!(recovered.count("test").await.unwrap(), 0);
+        assert_eq!(recovered.precision, VectorPrecision::Int8);
+        assert_eq!(recovered.score_normalizer("test").await, ScoreNormalizer::Sigmoid);
+
+        std::fs::remove_file(&wal_path).unwrap();
+    }
 }
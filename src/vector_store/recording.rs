@@ -0,0 +1,409 @@
+//! Vector store that logs every call, for testing code built on `VectorStoreBase`
+
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+use crate::Result;
+use super::{
+    CollectionConfig, DistanceMetric, InMemoryStore, ScoreNormalizer, SearchResult, SparseVector,
+    UpsertMode, VectorMetadata, VectorStoreBase,
+};
+
+/// A recorded store call and its arguments
+///
+/// Vectors are not recorded; writes log the IDs they carry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreCall {
+    CreateCollection { collection: String, vector_size: usize },
+    CreateCollectionWith { collection: String, vector_size: usize, distance: DistanceMetric },
+    CollectionExists { collection: String },
+    Upsert { collection: String, ids: Vec<String> },
+    UpsertWithMode { collection: String, ids: Vec<String>, mode: UpsertMode },
+    Search { collection: String, limit: usize, score_threshold: Option<f32> },
+    SearchWithPayload {
+        collection: String,
+        limit: usize,
+        score_threshold: Option<f32>,
+        payload_fields: Vec<String>,
+    },
+    UpsertSparse { collection: String, ids: Vec<String> },
+    SearchSparse { collection: String, limit: usize },
+    Delete { collection: String, ids: Vec<String> },
+    DeleteCollection { collection: String },
+    ListCollections,
+    Count { collection: String },
+    GetById { collection: String, id: String },
+    GetVector { collection: String, id: String },
+    GetAll { collection: String },
+    Scan { collection: String, after: Option<String>, limit: usize },
+    FindByMetadata { collection: String, key: String, value: String },
+    UpdateMetadata { collection: String, id: String },
+    CompareAndSwap { collection: String, id: String, expected_updated_at: String },
+    Flush { collection: String },
+}
+
+impl StoreCall {
+    /// Method name of the call, e.g. `"upsert"`
+    pub fn method(&self) -> &'static str {
+        match self {
+            StoreCall::CreateCollection { .. } => "create_collection",
+            StoreCall::CreateCollectionWith { .. } => "create_collection_with",
+            StoreCall::CollectionExists { .. } => "collection_exists",
+            StoreCall::Upsert { .. } => "upsert",
+            StoreCall::UpsertWithMode { .. } => "upsert_with_mode",
+            StoreCall::Search { .. } => "search",
+            StoreCall::SearchWithPayload { .. } => "search_with_payload",
+            StoreCall::UpsertSparse { .. } => "upsert_sparse",
+            StoreCall::SearchSparse { .. } => "search_sparse",
+            StoreCall::Delete { .. } => "delete",
+            StoreCall::DeleteCollection { .. } => "delete_collection",
+            StoreCall::ListCollections => "list_collections",
+            StoreCall::Count { .. } => "count",
+            StoreCall::GetById { .. } => "get_by_id",
+            StoreCall::GetVector { .. } => "get_vector",
+            StoreCall::GetAll { .. } => "get_all",
+            StoreCall::Scan { .. } => "scan",
+            StoreCall::FindByMetadata { .. } => "find_by_metadata",
+            StoreCall::UpdateMetadata { .. } => "update_metadata",
+            StoreCall::CompareAndSwap { .. } => "compare_and_swap",
+            StoreCall::Flush { .. } => "flush",
+        }
+    }
+}
+
+/// `InMemoryStore` wrapper recording an ordered log of calls
+///
+/// Available in tests and with the `test-utils` feature.
+pub struct RecordingStore {
+    inner: InMemoryStore,
+    calls: Mutex<Vec<StoreCall>>,
+}
+
+impl RecordingStore {
+    /// Create a recording store over an empty `InMemoryStore`
+    pub fn new() -> Self {
+        Self::wrap(InMemoryStore::new())
+    }
+
+    /// Record calls made to an existing store
+    pub fn wrap(inner: InMemoryStore) -> Self {
+        Self {
+            inner,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// All calls so far, in order
+    pub fn calls(&self) -> Vec<StoreCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Number of calls to the method named `method`
+    pub fn count_calls(&self, method: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.method() == method)
+            .count()
+    }
+
+    /// Forget the calls recorded so far
+    pub fn clear(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    fn record(&self, call: StoreCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl Default for RecordingStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// IDs of a batch of vectors
+fn ids_of<T, U>(vectors: &[(String, T, U)]) -> Vec<String> {
+    vectors.iter().map(|(id, _, _)| id.clone()).collect()
+}
+
+#[async_trait]
+impl VectorStoreBase for RecordingStore {
+    async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+        self.record(StoreCall::CreateCollection {
+            collection: collection_name.to_string(),
+            vector_size,
+        });
+        self.inner.create_collection(collection_name, vector_size).await
+    }
+
+    async fn create_collection_with(
+        &self,
+        collection_name: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        self.record(StoreCall::CreateCollectionWith {
+            collection: collection_name.to_string(),
+            vector_size: config.vector_size,
+            distance: config.distance,
+        });
+        self.inner.create_collection_with(collection_name, config).await
+    }
+
+    async fn score_normalizer(&self, collection_name: &str) -> ScoreNormalizer {
+        self.inner.score_normalizer(collection_name).await
+    }
+
+    async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+        self.record(StoreCall::CollectionExists {
+            collection: collection_name.to_string(),
+        });
+        self.inner.collection_exists(collection_name).await
+    }
+
+    async fn upsert(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+    ) -> Result<()> {
+        self.record(StoreCall::Upsert {
+            collection: collection_name.to_string(),
+            ids: ids_of(&vectors),
+        });
+        self.inner.upsert(collection_name, vectors).await
+    }
+
+    async fn upsert_with_mode(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        mode: UpsertMode,
+    ) -> Result<()> {
+        self.record(StoreCall::UpsertWithMode {
+            collection: collection_name.to_string(),
+            ids: ids_of(&vectors),
+            mode,
+        });
+        self.inner.upsert_with_mode(collection_name, vectors, mode).await
+    }
+
+    async fn search(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+    ) -> Result<Vec<SearchResult>> {
+        self.record(StoreCall::Search {
+            collection: collection_name.to_string(),
+            limit,
+            score_threshold,
+        });
+        self.inner
+            .search(collection_name, query_vector, limit, score_threshold)
+            .await
+    }
+
+    async fn search_with_payload(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+        payload_fields: &[&str],
+    ) -> Result<Vec<SearchResult>> {
+        self.record(StoreCall::SearchWithPayload {
+            collection: collection_name.to_string(),
+            limit,
+            score_threshold,
+            payload_fields: payload_fields.iter().map(|f| f.to_string()).collect(),
+        });
+        self.inner
+            .search_with_payload(collection_name, query_vector, limit, score_threshold, payload_fields)
+            .await
+    }
+
+    async fn upsert_sparse(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, SparseVector)>,
+    ) -> Result<()> {
+        self.record(StoreCall::UpsertSparse {
+            collection: collection_name.to_string(),
+            ids: vectors.iter().map(|(id, _)| id.clone()).collect(),
+        });
+        self.inner.upsert_sparse(collection_name, vectors).await
+    }
+
+    async fn search_sparse(
+        &self,
+        collection_name: &str,
+        query: SparseVector,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        self.record(StoreCall::SearchSparse {
+            collection: collection_name.to_string(),
+            limit,
+        });
+        self.inner.search_sparse(collection_name, query, limit).await
+    }
+
+    async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+        self.record(StoreCall::Delete {
+            collection: collection_name.to_string(),
+            ids: ids.clone(),
+        });
+        self.inner.delete(collection_name, ids).await
+    }
+
+    async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+        self.record(StoreCall::DeleteCollection {
+            collection: collection_name.to_string(),
+        });
+        self.inner.delete_collection(collection_name).await
+    }
+
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        self.record(StoreCall::ListCollections);
+        self.inner.list_collections().await
+    }
+
+    async fn count(&self, collection_name: &str) -> Result<usize> {
+        self.record(StoreCall::Count {
+            collection: collection_name.to_string(),
+        });
+        self.inner.count(collection_name).await
+    }
+
+    async fn get_by_id(&self, collection_name: &str, id: &str) -> Result<Option<VectorMetadata>> {
+        self.record(StoreCall::GetById {
+            collection: collection_name.to_string(),
+            id: id.to_string(),
+        });
+        self.inner.get_by_id(collection_name, id).await
+    }
+
+    async fn get_vector(&self, collection_name: &str, id: &str) -> Result<Option<Vec<f32>>> {
+        self.record(StoreCall::GetVector {
+            collection: collection_name.to_string(),
+            id: id.to_string(),
+        });
+        self.inner.get_vector(collection_name, id).await
+    }
+
+    async fn get_all(&self, collection_name: &str) -> Result<Vec<VectorMetadata>> {
+        self.record(StoreCall::GetAll {
+            collection: collection_name.to_string(),
+        });
+        self.inner.get_all(collection_name).await
+    }
+
+    async fn scan(
+        &self,
+        collection_name: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.record(StoreCall::Scan {
+            collection: collection_name.to_string(),
+            after: after.map(str::to_string),
+            limit,
+        });
+        self.inner.scan(collection_name, after, limit).await
+    }
+
+    async fn find_by_metadata(
+        &self,
+        collection_name: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.record(StoreCall::FindByMetadata {
+            collection: collection_name.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+        self.inner.find_by_metadata(collection_name, key, value).await
+    }
+
+    async fn update_metadata(
+        &self,
+        collection_name: &str,
+        id: &str,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.record(StoreCall::UpdateMetadata {
+            collection: collection_name.to_string(),
+            id: id.to_string(),
+        });
+        self.inner.update_metadata(collection_name, id, metadata).await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        collection_name: &str,
+        id: &str,
+        expected_updated_at: &str,
+        vector: Vec<f32>,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.record(StoreCall::CompareAndSwap {
+            collection: collection_name.to_string(),
+            id: id.to_string(),
+            expected_updated_at: expected_updated_at.to_string(),
+        });
+        self.inner
+            .compare_and_swap(collection_name, id, expected_updated_at, vector, metadata)
+            .await
+    }
+
+    async fn flush(&self, collection_name: &str) -> Result<()> {
+        self.record(StoreCall::Flush {
+            collection: collection_name.to_string(),
+        });
+        self.inner.flush(collection_name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::MemoryConfig;
+    use crate::embeddings::LocalEmbedder;
+    use crate::memory::{Memory, MemoryBase};
+
+    #[tokio::test]
+    async fn test_add_logs_create_and_upsert() {
+        let store = Arc::new(RecordingStore::new());
+        let memory = Memory::new(
+            MemoryConfig::new("memory.db".to_string()),
+            store.clone(),
+            Arc::new(LocalEmbedder::with_defaults()),
+        );
+
+        let item = memory.add("user_1", "I like coffee", None).await.unwrap();
+
+        assert_eq!(
+            store.calls(),
+            vec![
+                StoreCall::CreateCollection {
+                    collection: "memory_user_1".to_string(),
+                    vector_size: 384,
+                },
+                StoreCall::Upsert {
+                    collection: "memory_user_1".to_string(),
+                    ids: vec![item.id],
+                },
+            ]
+        );
+        assert_eq!(store.count_calls("upsert"), 1);
+
+        store.clear();
+        memory.get_all("user_1").await.unwrap();
+        assert_eq!(store.count_calls("get_all"), 1);
+        assert_eq!(store.count_calls("upsert"), 0);
+    }
+}
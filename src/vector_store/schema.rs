@@ -0,0 +1,139 @@
+//! Optional per-collection schema for custom metadata
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{Error, Result};
+
+/// Type a custom metadata value must parse as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataType {
+    /// Any string
+    String,
+    /// Signed integer
+    Integer,
+    /// Floating-point number
+    Float,
+    /// `true` or `false`
+    Boolean,
+}
+
+impl MetadataType {
+    /// Whether a stored string value is of this type
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Float => value.parse::<f64>().is_ok(),
+            Self::Boolean => value.parse::<bool>().is_ok(),
+        }
+    }
+}
+
+/// Keys and value types expected in a collection's custom metadata
+///
+/// Required and typed keys are always allowed. When allowed keys are
+/// declared, any other key is rejected, so include keys written by
+/// `Memory` itself (e.g. `lang`, `importance`) if they are in use.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectionSchema {
+    /// Keys every vector must carry
+    pub required_keys: BTreeSet<String>,
+    /// Additional permitted keys; any key is permitted when `None`
+    pub allowed_keys: Option<BTreeSet<String>>,
+    /// Expected value type per key
+    pub key_types: BTreeMap<String, MetadataType>,
+}
+
+impl CollectionSchema {
+    /// Create a schema that accepts any metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a key
+    pub fn with_required_key(mut self, key: impl Into<String>) -> Self {
+        self.required_keys.insert(key.into());
+        self
+    }
+
+    /// Permit a key, restricting metadata to declared keys
+    pub fn with_allowed_key(mut self, key: impl Into<String>) -> Self {
+        self.allowed_keys
+            .get_or_insert_with(BTreeSet::new)
+            .insert(key.into());
+        self
+    }
+
+    /// Set the value type of a key
+    pub fn with_key_type(mut self, key: impl Into<String>, key_type: MetadataType) -> Self {
+        self.key_types.insert(key.into(), key_type);
+        self
+    }
+
+    /// Check custom metadata against the schema
+    pub fn validate(&self, metadata: &HashMap<String, String>) -> Result<()> {
+        if let Some(missing) = self.required_keys.iter().find(|k| !metadata.contains_key(*k)) {
+            return Err(Error::invalid_arg(format!(
+                "Missing required metadata key: {}",
+                missing
+            )));
+        }
+
+        for (key, value) in metadata {
+            if let Some(allowed) = &self.allowed_keys
+                && !allowed.contains(key)
+                && !self.required_keys.contains(key)
+                && !self.key_types.contains_key(key)
+            {
+                return Err(Error::invalid_arg(format!("Unknown metadata key: {}", key)));
+            }
+
+            if let Some(key_type) = self.key_types.get(key)
+                && !key_type.accepts(value)
+            {
+                return Err(Error::invalid_arg(format!(
+                    "Metadata key {} expects {:?}, got {:?}",
+                    key, key_type, value
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = CollectionSchema::new()
+            .with_required_key("source")
+            .with_allowed_key("lang")
+            .with_key_type("priority", MetadataType::Integer);
+
+        assert!(schema.validate(&metadata(&[("source", "chat")])).is_ok());
+        assert!(schema
+            .validate(&metadata(&[("source", "chat"), ("lang", "eng"), ("priority", "2")]))
+            .is_ok());
+
+        assert!(schema.validate(&metadata(&[("lang", "eng")])).is_err());
+        assert!(schema.validate(&metadata(&[("source", "chat"), ("sorce", "x")])).is_err());
+        assert!(schema.validate(&metadata(&[("source", "chat"), ("priority", "high")])).is_err());
+    }
+
+    #[test]
+    fn test_empty_schema_accepts_anything() {
+        let schema = CollectionSchema::new();
+        assert!(schema.validate(&metadata(&[("anything", "goes")])).is_ok());
+    }
+}
@@ -0,0 +1,295 @@
+//! Vector store decorator bounding the duration of each call
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Error, Result};
+use super::{
+    CollectionConfig, ScoreNormalizer, SearchResult, SparseVector, UpsertMode, VectorMetadata,
+    VectorStoreBase,
+};
+
+/// Vector store wrapper that fails calls exceeding a timeout
+///
+/// A timed-out call is abandoned, not cancelled on the server; writes may
+/// still be applied.
+pub struct TimeoutStore {
+    inner: Arc<dyn VectorStoreBase>,
+    timeout: Duration,
+}
+
+impl TimeoutStore {
+    /// Wrap a store, bounding each call by `timeout`
+    pub fn new(inner: Arc<dyn VectorStoreBase>, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Run a call on the inner store within the timeout
+    async fn bounded<T: Send>(&self, call: impl Future<Output = Result<T>> + Send) -> Result<T> {
+        tokio::time::timeout(self.timeout, call)
+            .await
+            .map_err(|_| Error::timeout(format!("vector store timed out after {:?}", self.timeout)))?
+    }
+}
+
+#[async_trait]
+impl VectorStoreBase for TimeoutStore {
+    async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+        self.bounded(self.inner.create_collection(collection_name, vector_size))
+            .await
+    }
+
+    async fn create_collection_with(
+        &self,
+        collection_name: &str,
+        config: CollectionConfig,
+    ) -> Result<()> {
+        self.bounded(self.inner.create_collection_with(collection_name, config))
+            .await
+    }
+
+    async fn score_normalizer(&self, collection_name: &str) -> ScoreNormalizer {
+        self.inner.score_normalizer(collection_name).await
+    }
+
+    async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+        self.bounded(self.inner.collection_exists(collection_name))
+            .await
+    }
+
+    async fn upsert(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+    ) -> Result<()> {
+        self.bounded(self.inner.upsert(collection_name, vectors))
+            .await
+    }
+
+    async fn upsert_with_mode(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        mode: UpsertMode,
+    ) -> Result<()> {
+        self.bounded(self.inner.upsert_with_mode(collection_name, vectors, mode))
+            .await
+    }
+
+    async fn search(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+    ) -> Result<Vec<SearchResult>> {
+        self.bounded(
+            self.inner
+                .search(collection_name, query_vector, limit, score_threshold),
+        )
+        .await
+    }
+
+    async fn search_with_payload(
+        &self,
+        collection_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        score_threshold: Option<f32>,
+        payload_fields: &[&str],
+    ) -> Result<Vec<SearchResult>> {
+        self.bounded(self.inner.search_with_payload(
+            collection_name,
+            query_vector,
+            limit,
+            score_threshold,
+            payload_fields,
+        ))
+        .await
+    }
+
+    async fn upsert_sparse(
+        &self,
+        collection_name: &str,
+        vectors: Vec<(String, SparseVector)>,
+    ) -> Result<()> {
+        self.bounded(self.inner.upsert_sparse(collection_name, vectors))
+            .await
+    }
+
+    async fn search_sparse(
+        &self,
+        collection_name: &str,
+        query: SparseVector,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        self.bounded(self.inner.search_sparse(collection_name, query, limit))
+            .await
+    }
+
+    async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+        self.bounded(self.inner.delete(collection_name, ids)).await
+    }
+
+    async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+        self.bounded(self.inner.delete_collection(collection_name))
+            .await
+    }
+
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        self.bounded(self.inner.list_collections()).await
+    }
+
+    async fn count(&self, collection_name: &str) -> Result<usize> {
+        self.bounded(self.inner.count(collection_name)).await
+    }
+
+    async fn get_by_id(&self, collection_name: &str, id: &str) -> Result<Option<VectorMetadata>> {
+        self.bounded(self.inner.get_by_id(collection_name, id))
+            .await
+    }
+
+    async fn get_vector(&self, collection_name: &str, id: &str) -> Result<Option<Vec<f32>>> {
+        self.bounded(self.inner.get_vector(collection_name, id))
+            .await
+    }
+
+    async fn get_all(&self, collection_name: &str) -> Result<Vec<VectorMetadata>> {
+        self.bounded(self.inner.get_all(collection_name)).await
+    }
+
+    async fn scan(
+        &self,
+        collection_name: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.bounded(self.inner.scan(collection_name, after, limit))
+            .await
+    }
+
+    async fn find_by_metadata(
+        &self,
+        collection_name: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<VectorMetadata>> {
+        self.bounded(self.inner.find_by_metadata(collection_name, key, value))
+            .await
+    }
+
+    async fn update_metadata(
+        &self,
+        collection_name: &str,
+        id: &str,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.bounded(self.inner.update_metadata(collection_name, id, metadata))
+            .await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        collection_name: &str,
+        id: &str,
+        expected_updated_at: &str,
+        vector: Vec<f32>,
+        metadata: VectorMetadata,
+    ) -> Result<()> {
+        self.bounded(self.inner.compare_and_swap(
+            collection_name,
+            id,
+            expected_updated_at,
+            vector,
+            metadata,
+        ))
+        .await
+    }
+
+    async fn flush(&self, collection_name: &str) -> Result<()> {
+        self.bounded(self.inner.flush(collection_name)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::InMemoryStore;
+
+    /// Store whose `count` never completes in time
+    struct SlowCountStore {
+        inner: InMemoryStore,
+    }
+
+    #[async_trait]
+    impl VectorStoreBase for SlowCountStore {
+        async fn create_collection(&self, collection_name: &str, vector_size: usize) -> Result<()> {
+            self.inner.create_collection(collection_name, vector_size).await
+        }
+
+        async fn collection_exists(&self, collection_name: &str) -> Result<bool> {
+            self.inner.collection_exists(collection_name).await
+        }
+
+        async fn upsert(
+            &self,
+            collection_name: &str,
+            vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+        ) -> Result<()> {
+            self.inner.upsert(collection_name, vectors).await
+        }
+
+        async fn search(
+            &self,
+            collection_name: &str,
+            query_vector: Vec<f32>,
+            limit: usize,
+            score_threshold: Option<f32>,
+        ) -> Result<Vec<SearchResult>> {
+            self.inner
+                .search(collection_name, query_vector, limit, score_threshold)
+                .await
+        }
+
+        async fn delete(&self, collection_name: &str, ids: Vec<String>) -> Result<()> {
+            self.inner.delete(collection_name, ids).await
+        }
+
+        async fn delete_collection(&self, collection_name: &str) -> Result<()> {
+            self.inner.delete_collection(collection_name).await
+        }
+
+        async fn list_collections(&self) -> Result<Vec<String>> {
+            self.inner.list_collections().await
+        }
+
+        async fn count(&self, collection_name: &str) -> Result<usize> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            self.inner.count(collection_name).await
+        }
+
+        async fn get_by_id(&self, collection_name: &str, id: &str) -> Result<Option<VectorMetadata>> {
+            self.inner.get_by_id(collection_name, id).await
+        }
+
+        async fn get_all(&self, collection_name: &str) -> Result<Vec<VectorMetadata>> {
+            self.inner.get_all(collection_name).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_times_out_slow_calls_only() {
+        let store = TimeoutStore::new(
+            Arc::new(SlowCountStore {
+                inner: InMemoryStore::new(),
+            }),
+            Duration::from_millis(20),
+        );
+
+        store.create_collection("c", 4).await.unwrap();
+        assert!(store.collection_exists("c").await.unwrap());
+        assert!(matches!(store.count("c").await, Err(Error::Timeout(_))));
+    }
+}
@@ -0,0 +1,165 @@
+//! Write-ahead log for the in-memory vector store
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::{Error, Result};
+use super::{CollectionSchema, DistanceMetric, VectorMetadata};
+
+/// Logged store mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WalOp {
+    /// Collection created
+    CreateCollection {
+        collection: String,
+        distance: DistanceMetric,
+        #[serde(default)]
+        schema: Option<CollectionSchema>,
+    },
+    /// Vectors inserted or replaced
+    Upsert {
+        collection: String,
+        vectors: Vec<(String, Vec<f32>, VectorMetadata)>,
+    },
+    /// Metadata of a vector replaced
+    UpdateMetadata {
+        collection: String,
+        id: String,
+        metadata: Box<VectorMetadata>,
+    },
+    /// Vectors deleted
+    Delete {
+        collection: String,
+        ids: Vec<String>,
+    },
+    /// Collection deleted
+    DeleteCollection { collection: String },
+}
+
+/// Full store snapshot written at checkpoints
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct StoreSnapshot {
+    pub collections: HashMap<String, Vec<(String, Vec<f32>, VectorMetadata)>>,
+    pub metrics: HashMap<String, DistanceMetric>,
+    #[serde(default)]
+    pub schemas: HashMap<String, CollectionSchema>,
+}
+
+impl StoreSnapshot {
+    /// Read a snapshot file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::internal(format!("Failed to read snapshot: {}", e)))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write a snapshot file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::internal(format!("Failed to write snapshot: {}", e)))
+    }
+}
+
+/// Append-only log of store mutations, one JSON line per operation
+pub struct WriteAheadLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl WriteAheadLog {
+    /// Open a log for appending, creating it if missing
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| Error::internal(format!("Failed to open WAL: {}", e)))?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Get the log path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an operation and flush it to disk
+    pub fn append(&self, op: &WalOp) -> Result<()> {
+        let line = serde_json::to_string(op)?;
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| Error::internal(format!("Failed to append to WAL: {}", e)))
+    }
+
+    /// Byte offset at which the next operation will be appended
+    pub fn position(&self) -> Result<u64> {
+        let file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.metadata()
+            .map(|m| m.len())
+            .map_err(|e| Error::internal(format!("Failed to read WAL length: {}", e)))
+    }
+
+    /// Discard the operations before `position`, keeping those appended since
+    pub fn discard_prefix(&self, position: u64) -> Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let contents = std::fs::read(&self.path)
+            .map_err(|e| Error::internal(format!("Failed to read WAL: {}", e)))?;
+        let tail = usize::try_from(position)
+            .ok()
+            .and_then(|start| contents.get(start..))
+            .unwrap_or_default();
+        file.set_len(0)
+            .and_then(|_| file.write_all(tail))
+            .and_then(|_| file.sync_data())
+            .map_err(|e| Error::internal(format!("Failed to truncate WAL: {}", e)))
+    }
+
+    /// Discard all logged operations
+    pub fn truncate(&self) -> Result<()> {
+        let file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.set_len(0)
+            .map_err(|e| Error::internal(format!("Failed to truncate WAL: {}", e)))
+    }
+
+    /// Read the operations in a log
+    ///
+    /// A torn final line from a crash mid-append is skipped.
+    pub fn read_ops(path: &Path) -> Result<Vec<WalOp>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = File::open(path)
+            .map_err(|e| Error::internal(format!("Failed to open WAL: {}", e)))?;
+        let lines = BufReader::new(file)
+            .lines()
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(|e| Error::internal(format!("Failed to read WAL: {}", e)))?;
+
+        let mut ops = Vec::with_capacity(lines.len());
+        let last = lines.len().saturating_sub(1);
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(op) => ops.push(op),
+                Err(e) if i == last => {
+                    tracing::warn!("Skipping torn WAL entry: {}", e);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(ops)
+    }
+}